@@ -1,5 +1,10 @@
-use color_eyre::Result;
-use crossterm::event::{KeyCode, KeyEvent, KeyEventKind, KeyModifiers, MouseEvent};
+use color_eyre::{
+    eyre::{eyre, WrapErr},
+    Result,
+};
+use crossterm::event::{
+    KeyCode, KeyEvent, KeyEventKind, KeyModifiers, MouseButton, MouseEvent, MouseEventKind,
+};
 use ratatui::{
     layout::Rect,
     style::{Color, Style},
@@ -7,30 +12,57 @@ use ratatui::{
     widgets::{Block, Borders, Clear, Paragraph},
     Frame,
 };
-use regex;
-use std::time::{Duration, Instant};
+use std::{
+    fs,
+    path::{Path, PathBuf},
+    time::{Duration, Instant},
+};
 use tokio::runtime::Runtime;
 
 use crate::{
+    config::{Config, SortConfig},
     slurm::{
+        columns::register_custom_columns,
         command::{execute_scancel, get_partitions, get_qos},
+        jobdetails::job_fields,
+        logpaths::resolve_log_paths,
         squeue::{run_squeue, SqueueOptions},
-        JobState,
+        submit::submit_script,
+        transitions::JobSnapshot,
+        Job, JobState,
     },
     ui::{
-        columns::{ColumnsAction, ColumnsPopup, JobColumn, SortColumn, SortOrder},
+        columns::{
+            cycle_sort, layout_columns, ColumnsAction, ColumnsPopup, JobColumn, SortColumn,
+            SortOrder,
+        },
+        diffview::DiffView,
+        export::{ExportAction, ExportPopup, ExportScope},
         filter::{FilterAction, FilterPopup},
         jobscript::JobScript,
-        jobslist::JobsList,
-        layout::{centered_popup_area, draw_footer, draw_header, draw_main_layout},
+        jobslist::{JobsList, JobsListHit},
+        layout::{centered_popup_area, draw_footer, draw_header, draw_main_layout, JobStats},
+        logissues::IssuePatterns,
         logview::LogView,
+        resubmit::{ResubmitAction, ResubmitPopup},
+        viewer::{resolve_path, ViewerAction},
     },
     utils::{
+        clipboard::Clipboard,
         event::{Event as AppEvent, EventConfig, EventHandler},
+        export::format_jobs,
+        external::{run_suspended, ExternalProgram},
         get_username,
+        hooks::HookRunner,
+        notification::Notifier,
     },
 };
 
+/// Lines scrolled by one step of the mouse wheel in the log, script and diff views
+const WHEEL_SCROLL_LINES: usize = 3;
+/// Longest time between two clicks on a job that open its details
+const DOUBLE_CLICK_INTERVAL: Duration = Duration::from_millis(400);
+
 /// Application state and logic
 pub struct App {
     /// Is the application running?
@@ -54,6 +86,12 @@ pub struct App {
     pub log_view: LogView,
     /// Script View state
     pub script_view: JobScript,
+    /// Diff view of two jobs
+    pub diff_view: DiffView,
+    /// Resubmit popup state
+    pub resubmit_popup: ResubmitPopup,
+    /// Export popup state
+    pub export_popup: ExportPopup,
     /// Status message to display in the status bar
     pub status_message: String,
     /// Status message display timeout
@@ -72,11 +110,33 @@ pub struct App {
     pub sort_columns: Vec<SortColumn>,
     /// Confirm cancel popup state
     cancel_confirm: bool,
+    /// User configuration, written back when the layout is saved
+    config: Config,
+    /// File to open in an external program once the current event is handled,
+    /// and whether it is a temporary copy
+    pending_open: Option<(ExternalProgram, PathBuf, bool)>,
+    /// Job states at the last refresh, to detect state changes
+    job_snapshot: JobSnapshot,
+    /// Notifications on job state changes
+    notifier: Notifier,
+    /// Commands run on job events
+    hooks: HookRunner,
+    /// System clipboard, and the register of the last copied text
+    clipboard: Clipboard,
+    /// `y` was pressed, the next key chooses what to copy
+    copy_pending: bool,
+    /// Time and row of the last click on a job, to spot double clicks
+    last_click: Option<(Instant, usize)>,
+    /// Displayed columns squeue can't fetch with the current query, already reported
+    unfetchable_columns: Vec<JobColumn>,
 }
 
 impl App {
     /// Create a new application instance
     pub fn new() -> Result<Self> {
+        let config = Config::load()?;
+        register_custom_columns(config.columns.clone());
+
         let runtime = tokio::runtime::Builder::new_multi_thread()
             .enable_all()
             .build()
@@ -84,7 +144,7 @@ impl App {
 
         // Default username for squeue
         let username = get_username();
-        let squeue_options = SqueueOptions {
+        let mut squeue_options = SqueueOptions {
             user: Some(username),
             ..Default::default()
         };
@@ -94,24 +154,40 @@ impl App {
         let available_qos = runtime.block_on(async { get_qos().await })?;
         let available_states = JobState::get_available_states();
 
-        // Default columns and sort options
-        let selected_columns = JobColumn::defaults();
-        let sort_columns = vec![SortColumn {
-            column: JobColumn::Id,
-            order: SortOrder::Ascending,
-        }];
+        // Columns and sort options from the saved layout, or the defaults
+        let layout = &config.layout;
+        let (selected_columns, sort_columns) = layout_columns(layout);
+
+        let issue_patterns =
+            IssuePatterns::new(&config.log.error_patterns, &config.log.warning_patterns)?;
+
+        let notifier = Notifier::new(config.notifications.clone())?;
+        let hooks = HookRunner::new(config.hooks.clone())?;
+        squeue_options.hidden_columns = hooks.columns();
+        let clipboard = Clipboard::new(config.clipboard.osc52);
+
+        let mut jobs_list =
+            JobsList::new(config.display.time_format, config.display.auto_fit_columns);
+        jobs_list.column_widths = layout
+            .widths
+            .iter()
+            .filter_map(|(title, width)| Some((JobColumn::from_title(title)?, *width)))
+            .collect();
 
         Ok(Self {
             running: true,
             event_handler: EventHandler::new(EventConfig::default()),
-            jobs_list: JobsList::new(),
+            jobs_list,
             squeue_options,
             runtime,
             last_refresh: Instant::now(),
             filter_popup: FilterPopup::new(),
             columns_popup: ColumnsPopup::new(selected_columns.clone(), sort_columns.clone()),
-            log_view: LogView::new(),
-            script_view: JobScript::new(),
+            log_view: LogView::new(config.log.max_retained_mb * 1024 * 1024, issue_patterns),
+            script_view: JobScript::new(config.display.theme),
+            diff_view: DiffView::new(),
+            resubmit_popup: ResubmitPopup::new(),
+            export_popup: ExportPopup::new(),
             status_message: String::new(),
             status_timeout: None,
             job_refresh_interval: 10, // Default to 10 seconds refresh
@@ -121,6 +197,15 @@ impl App {
             selected_columns,
             sort_columns,
             cancel_confirm: false,
+            config,
+            pending_open: None,
+            job_snapshot: JobSnapshot::default(),
+            notifier,
+            hooks,
+            clipboard,
+            copy_pending: false,
+            last_click: None,
+            unfetchable_columns: Vec::new(),
         })
     }
 
@@ -129,20 +214,225 @@ impl App {
         &mut self,
         terminal: &mut ratatui::Terminal<B>,
     ) -> Result<()> {
-        // Initial job loading
-        self.refresh_jobs()?;
+        // Initial job loading, a failing squeue is reported like on later refreshes
+        if let Err(e) = self.refresh_jobs() {
+            self.set_status_message(format!("Failed to refresh: {}", e), 5);
+        }
 
         while self.running {
             terminal.draw(|frame| self.render(frame))?;
             self.handle_events()?;
+
+            if let Some((program, path, copy)) = self.pending_open.take() {
+                self.open_external(terminal, program, &path, copy)?;
+            }
         }
 
         Ok(())
     }
 
+    /// Suspend the UI while a pager or editor shows a file, then redraw it.
+    /// Edits to a temporary copy are lost, which the user is told afterwards.
+    fn open_external<B: ratatui::backend::Backend>(
+        &mut self,
+        terminal: &mut ratatui::Terminal<B>,
+        program: ExternalProgram,
+        path: &Path,
+        copy: bool,
+    ) -> Result<()> {
+        // Leave the keyboard to the program
+        self.event_handler.pause();
+        let result = run_suspended(program, path);
+        self.event_handler.resume();
+        terminal.clear()?;
+
+        match result {
+            Ok(status) if status.success() => {
+                if copy && program == ExternalProgram::Editor {
+                    self.set_status_message(
+                        "That was a temporary copy of the script, edits to it are not kept"
+                            .to_string(),
+                        5,
+                    );
+                }
+            }
+            Ok(status) => self.set_status_message(
+                format!("The {} exited with {}", program.as_str(), status),
+                5,
+            ),
+            Err(e) => self.set_status_message(format!("{:#}", e), 5),
+        }
+        Ok(())
+    }
+
+    /// Carry out what the log or script view asked for
+    fn handle_viewer_action(&mut self, action: ViewerAction) {
+        match action {
+            ViewerAction::None => {}
+            ViewerAction::Open(program, path) => self.pending_open = Some((program, path, false)),
+            ViewerAction::OpenCopy(program, path) => {
+                self.pending_open = Some((program, path, true))
+            }
+            ViewerAction::Status(message) => self.set_status_message(message, 5),
+            ViewerAction::Copy(text) => self.copy_text(text),
+        }
+    }
+
+    /// Copy text to the clipboard and show what was copied
+    fn copy_text(&mut self, text: String) {
+        let mut preview: String = text
+            .lines()
+            .next()
+            .unwrap_or_default()
+            .chars()
+            .take(60)
+            .collect();
+        if preview.len() < text.len() {
+            preview.push('…');
+        }
+        let message = if self.clipboard.copy(text) {
+            format!("Copied: {}", preview)
+        } else {
+            format!("Copied to the register (Ctrl+v pastes): {}", preview)
+        };
+        self.set_status_message(message, 3);
+    }
+
+    /// Copy something about the job under the cursor, chosen by the key
+    /// pressed after `y`
+    fn copy_job_info(&mut self, key: KeyCode) {
+        let Some(job) = self.jobs_list.selected_job().cloned() else {
+            self.status_message.clear();
+            return;
+        };
+        let text = match key {
+            KeyCode::Char('y') | KeyCode::Char('i') => Ok(job.id.clone()),
+            KeyCode::Char('s') => {
+                let ids = self.jobs_list.get_selected_jobs();
+                if ids.is_empty() {
+                    Err("No jobs selected".to_string())
+                } else {
+                    // As `squeue -j` and `sacct -j` take them
+                    Ok(ids.join(","))
+                }
+            }
+            KeyCode::Char('l') => resolve_log_paths(&job.id)
+                .stdout
+                .filter(|path| !path.is_empty())
+                .ok_or_else(|| format!("No log file found for job {}", job.id)),
+            KeyCode::Char('w') => job
+                .work_dir
+                .clone()
+                .or_else(|| {
+                    job_fields(&job.id)?
+                        .into_iter()
+                        .find(|(key, _)| key == "WorkDir")
+                        .map(|(_, dir)| dir)
+                })
+                .ok_or_else(|| format!("No working directory found for job {}", job.id)),
+            _ => {
+                self.status_message.clear();
+                return;
+            }
+        };
+        match text {
+            Ok(text) => self.copy_text(text),
+            Err(message) => self.set_status_message(message, 3),
+        }
+    }
+
+    /// Whether a text field has the keyboard
+    fn is_typing(&self) -> bool {
+        self.log_view.is_editing()
+            || self.script_view.is_editing()
+            || self.resubmit_popup.visible
+            || self.export_popup.visible
+            || (self.filter_popup.visible && self.filter_popup.input_mode)
+    }
+
+    /// Type the last copied text into the focused text field
+    fn paste_register(&mut self) {
+        let Some(text) = self.clipboard.register().map(str::to_string) else {
+            self.set_status_message("Nothing copied yet".to_string(), 3);
+            return;
+        };
+        for c in text.chars().filter(|c| !c.is_control()) {
+            self.handle_key_event(KeyEvent::new(KeyCode::Char(c), KeyModifiers::NONE));
+        }
+    }
+
+    /// Submit the script in the resubmit popup and move the cursor to the new job
+    fn resubmit_job(&mut self) {
+        let popup = &self.resubmit_popup;
+        let result = popup
+            .overrides()
+            .map_err(|e| eyre!(e))
+            .and_then(|overrides| {
+                let script = popup
+                    .script_path()
+                    .ok_or_else(|| eyre!("No script to submit"))?;
+                submit_script(script, popup.work_dir.as_deref(), &overrides)
+            });
+
+        match result {
+            Ok(new_id) => {
+                let old_id = self.resubmit_popup.job_id.clone();
+                self.resubmit_popup.hide();
+                if let Err(e) = self.refresh_jobs() {
+                    self.set_status_message(format!("Failed to refresh: {}", e), 3);
+                    return;
+                }
+                self.jobs_list.select_job_id(&new_id);
+                self.set_status_message(
+                    format!("Submitted batch job {} (resubmit of {})", new_id, old_id),
+                    5,
+                );
+            }
+            Err(e) => self.resubmit_popup.error = Some(format!("{:#}", e)),
+        }
+    }
+
+    /// Write the listed or selected jobs to the file chosen in the export popup
+    fn export_jobs(&mut self) {
+        let jobs: Vec<Job> = match self.export_popup.scope {
+            ExportScope::Listed => self.jobs_list.jobs.clone(),
+            // In list order rather than selection order
+            ExportScope::Selected => self
+                .jobs_list
+                .jobs
+                .iter()
+                .enumerate()
+                .filter(|(i, _)| self.jobs_list.selected_jobs.contains(i))
+                .map(|(_, job)| job.clone())
+                .collect(),
+        };
+        let output = format_jobs(
+            &jobs,
+            &self.selected_columns,
+            self.export_popup.format,
+            self.jobs_list.time_format,
+        );
+
+        let result = resolve_path(&self.export_popup.path).and_then(|path| {
+            fs::write(&path, output)
+                .wrap_err_with(|| format!("Cannot write {}", path.display()))
+                .map(|_| path)
+        });
+        match result {
+            Ok(path) => {
+                self.export_popup.hide();
+                self.set_status_message(
+                    format!("Exported {} jobs to {}", jobs.len(), path.display()),
+                    5,
+                );
+            }
+            Err(e) => self.export_popup.error = Some(format!("{:#}", e)),
+        }
+    }
+
     /// Refresh the jobs list from Slurm
     fn refresh_jobs(&mut self) -> Result<()> {
-        // Update squeue format and sort options
+        // Update squeue format
         self.update_squeue_format();
 
         // Clone options after format has been updated
@@ -151,72 +441,20 @@ impl App {
             .runtime
             .block_on(async { run_squeue(&options).await })?;
 
-        let mut filter_stats = Vec::new();
-        let initial_count = jobs.len();
-
-        // Apply regex name filter if it exists
-        if let Some(name_filter) = &self.squeue_options.name_filter {
-            if !name_filter.is_empty() {
-                // Try to compile the regex pattern
-                match regex::Regex::new(name_filter) {
-                    Ok(re) => {
-                        // Filter jobs by name using regex
-                        let before_count = jobs.len();
-                        jobs.retain(|job| re.is_match(&job.name));
-                        let after_count = jobs.len();
-
-                        // Track filtering stats
-                        if before_count != after_count && before_count > 0 {
-                            filter_stats.push(format!(
-                                "name: {}/{} ({:.1}%)",
-                                after_count,
-                                before_count,
-                                (after_count as f64 / before_count as f64) * 100.0
-                            ));
-                        }
-                    }
-                    Err(e) => {
-                        self.set_status_message(format!("Invalid name regex pattern: {}", e), 3);
-                    }
-                }
-            }
+        // Notify about state changes before the regex filters hide any job
+        let transitions = self.job_snapshot.update(&jobs);
+        self.hooks.on_refresh(&jobs, &transitions);
+        let notified = self.notifier.notify(&transitions);
+        if let Some(transition) = notified.last() {
+            self.set_status_message(transition.message(), 5);
         }
 
-        // Apply regex node filter if it exists
-        if let Some(node_filter) = &self.squeue_options.node_filter {
-            if !node_filter.is_empty() {
-                // Try to compile the regex pattern
-                match regex::Regex::new(node_filter) {
-                    Ok(re) => {
-                        // Filter jobs by node name using regex
-                        let before_count = jobs.len();
-                        jobs.retain(|job| {
-                            // If there's a node field, check it against the regex
-                            if let Some(node) = &job.node {
-                                re.is_match(node)
-                            } else {
-                                // If no node name available, don't filter this job
-                                true
-                            }
-                        });
-                        let after_count = jobs.len();
-
-                        // Track filtering stats
-                        if before_count != after_count && before_count > 0 {
-                            filter_stats.push(format!(
-                                "node: {}/{} ({:.1}%)",
-                                after_count,
-                                before_count,
-                                (after_count as f64 / before_count as f64) * 100.0
-                            ));
-                        }
-                    }
-                    Err(e) => {
-                        self.set_status_message(format!("Invalid node regex pattern: {}", e), 3);
-                    }
-                }
-            }
+        let initial_count = jobs.len();
+        let report = self.squeue_options.filter_jobs(&mut jobs);
+        if let Some(error) = report.errors.last() {
+            self.set_status_message(error.clone(), 3);
         }
+        let filter_stats = report.stats;
 
         // Show filter statistics if any filters were applied
         if !filter_stats.is_empty() {
@@ -239,7 +477,7 @@ impl App {
             );
         }
 
-        self.jobs_list.update_jobs(jobs);
+        self.jobs_list.update_jobs(jobs, &self.sort_columns);
         self.last_refresh = Instant::now();
 
         Ok(())
@@ -284,6 +522,24 @@ impl App {
             self.render_log_view(frame, popup_area);
         }
 
+        // If diff view is visible, draw it
+        if self.diff_view.visible {
+            let popup_area = centered_popup_area(frame.area(), 90, 80);
+            self.diff_view.render(frame, popup_area);
+        }
+
+        // If export popup is visible, draw it
+        if self.export_popup.visible {
+            let popup_area = centered_popup_area(frame.area(), 70, 40);
+            self.export_popup.render(frame, popup_area);
+        }
+
+        // If resubmit popup is visible, draw it
+        if self.resubmit_popup.visible {
+            let popup_area = centered_popup_area(frame.area(), 70, 60);
+            self.resubmit_popup.render(frame, popup_area);
+        }
+
         // If cancel confirm popup is visible, draw it
         if self.cancel_confirm {
             let popup_area = centered_popup_area(frame.area(), 50, 30);
@@ -323,30 +579,32 @@ impl App {
     }
 
     /// Render job detail popup
-    fn render_job_script(&self, frame: &mut Frame, area: Rect) {
+    fn render_job_script(&mut self, frame: &mut Frame, area: Rect) {
         self.script_view.render(frame, area);
     }
 
     /// Render the footer with XXX TODO:replace it
     fn render_footer(&self, frame: &mut Frame, area: Rect) {
-        // calculate Pending/Running/Other jobs count percentages
-        let pending_count = self
-            .jobs_list
-            .jobs
-            .iter()
-            .filter(|j| j.state == JobState::Pending)
-            .count();
-        let running_count = self
-            .jobs_list
-            .jobs
-            .iter()
-            .filter(|j| j.state == JobState::Running)
-            .count();
-        let other_count = self.jobs_list.jobs.len() - pending_count - running_count;
-        let job_stat = (pending_count, running_count, other_count);
+        // calculate Pending/Running/Other jobs count and running job totals
+        let jobs = &self.jobs_list.jobs;
+        let running = jobs.iter().filter(|j| j.state == JobState::Running);
+        let pending_count = jobs.iter().filter(|j| j.state == JobState::Pending).count();
+        let running_count = running.clone().count();
+        let job_stat = JobStats {
+            pending: pending_count,
+            running: running_count,
+            other: jobs.len() - pending_count - running_count,
+            running_memory_mb: running
+                .clone()
+                .filter_map(|j| j.memory.map(|m| m.total_mb(j.cpus, j.nodes)))
+                .sum(),
+            max_elapsed: running
+                .filter_map(|j| j.time.and_then(|t| t.as_secs()))
+                .max(),
+        };
 
         // Draw the footer
-        draw_footer(frame, area, job_stat);
+        draw_footer(frame, area, &job_stat);
     }
 
     /// Render the header with status information
@@ -427,26 +685,78 @@ impl App {
     /// Handle key events
     fn handle_key_event(&mut self, key: KeyEvent) {
         match (key.modifiers, key.code) {
+            // Paste the register into the text field being edited
+            (KeyModifiers::CONTROL, KeyCode::Char('v')) if self.is_typing() => {
+                self.paste_register();
+            }
+
+            // The log and script prompts take all keys, including Esc and letters
+            _ if self.log_view.is_editing() => {
+                let action = self.log_view.handle_key(key);
+                self.handle_viewer_action(action);
+            }
+            _ if self.script_view.is_editing() => {
+                let action = self.script_view.handle_key(key);
+                self.handle_viewer_action(action);
+            }
+
+            // The resubmit popup takes all keys, its fields are typed into
+            _ if self.resubmit_popup.visible => {
+                let action = self.resubmit_popup.handle_key(key);
+                match action {
+                    ResubmitAction::Close => self.resubmit_popup.hide(),
+                    ResubmitAction::Edit(path) => {
+                        self.pending_open = Some((ExternalProgram::Editor, path, false));
+                    }
+                    ResubmitAction::Submit => self.resubmit_job(),
+                    ResubmitAction::None => {}
+                }
+            }
+
+            // The export popup takes all keys, its path is typed into
+            _ if self.export_popup.visible => match self.export_popup.handle_key(key) {
+                ExportAction::Close => self.export_popup.hide(),
+                ExportAction::Export => self.export_jobs(),
+                ExportAction::None => {}
+            },
+
+            // The key after `y` chooses what to copy, others cancel
+            _ if self.copy_pending => {
+                self.copy_pending = false;
+                self.copy_job_info(key.code);
+            }
+
             // Quit application
             (_, KeyCode::Esc) | (KeyModifiers::CONTROL, KeyCode::Char('c')) => {
                 if self.filter_popup.visible
                     || self.script_view.visible
                     || self.columns_popup.visible
                     || self.log_view.visible
+                    || self.diff_view.visible
                     || self.cancel_confirm
                 {
                     self.filter_popup.visible = false;
                     self.script_view.visible = false;
                     self.columns_popup.visible = false;
                     self.log_view.hide();
+                    self.diff_view.hide();
                     self.cancel_confirm = false;
                 } else {
                     self.quit();
                 }
             }
 
-            // Filter toggle
-            (_, KeyCode::Char('f')) if !self.script_view.visible && !self.filter_popup.visible => {
+            // Handle diff view key events
+            _ if self.diff_view.visible => {
+                self.diff_view.handle_key(key);
+            }
+
+            // Filter toggle (in the log view, f toggles follow mode instead)
+            (_, KeyCode::Char('f'))
+                if !self.script_view.visible
+                    && !self.filter_popup.visible
+                    && !self.log_view.visible =>
+            {
                 self.filter_popup.visible = true;
                 // Initialize filter popup with current options
                 self.filter_popup.initialize(&self.squeue_options);
@@ -470,6 +780,71 @@ impl App {
                 self.jobs_list.next();
             }
 
+            // Header column focus and sort cycling
+            (_, KeyCode::Left)
+                if !self.filter_popup.visible
+                    && !self.script_view.visible
+                    && !self.columns_popup.visible
+                    && !self.log_view.visible
+                    && !self.cancel_confirm =>
+            {
+                self.jobs_list
+                    .focus_previous_column(self.selected_columns.len());
+            }
+            (_, KeyCode::Right)
+                if !self.filter_popup.visible
+                    && !self.script_view.visible
+                    && !self.columns_popup.visible
+                    && !self.log_view.visible
+                    && !self.cancel_confirm =>
+            {
+                self.jobs_list
+                    .focus_next_column(self.selected_columns.len());
+            }
+            (_, KeyCode::Char('s'))
+                if !self.filter_popup.visible
+                    && !self.script_view.visible
+                    && !self.columns_popup.visible
+                    && !self.log_view.visible
+                    && !self.cancel_confirm =>
+            {
+                self.cycle_focused_sort();
+            }
+
+            // Resize the focused column, or return it to its automatic width
+            (_, KeyCode::Char(c @ ('<' | '>')))
+                if !self.filter_popup.visible
+                    && !self.script_view.visible
+                    && !self.columns_popup.visible
+                    && !self.log_view.visible
+                    && !self.cancel_confirm =>
+            {
+                let delta = if c == '>' { 1 } else { -1 };
+                self.jobs_list
+                    .resize_focused_column(&self.selected_columns, delta);
+            }
+            (_, KeyCode::Char('='))
+                if !self.filter_popup.visible
+                    && !self.script_view.visible
+                    && !self.columns_popup.visible
+                    && !self.log_view.visible
+                    && !self.cancel_confirm =>
+            {
+                self.jobs_list
+                    .reset_focused_column_width(&self.selected_columns);
+            }
+
+            // Toggle absolute/relative timestamps
+            (_, KeyCode::Char('t'))
+                if !self.filter_popup.visible
+                    && !self.script_view.visible
+                    && !self.columns_popup.visible
+                    && !self.log_view.visible
+                    && !self.cancel_confirm =>
+            {
+                self.jobs_list.time_format = self.jobs_list.time_format.toggle();
+            }
+
             // Selection
             (_, KeyCode::Char(' '))
                 if !self.filter_popup.visible
@@ -519,11 +894,12 @@ impl App {
                 self.cancel_confirm = false;
             }
 
-            // Column management popup
+            // Column management popup (in the log view, c toggles colors instead)
             (_, KeyCode::Char('c'))
                 if !self.filter_popup.visible
                     && !self.script_view.visible
                     && !self.columns_popup.visible
+                    && !self.log_view.visible
                     && !self.cancel_confirm =>
             {
                 self.columns_popup =
@@ -593,28 +969,19 @@ impl App {
             }
 
             // Change job for script view
+            // If Shift is pressed, switch to previous job and show its script
             (KeyModifiers::SHIFT, KeyCode::Up) if self.script_view.visible => {
-                // If Shift is pressed, switch to previous job and show its script
-                if self.jobs_list.previous() {
-                    if let Some(job) = self.jobs_list.selected_job() {
-                        self.script_view
-                            .change_job(job.id.clone(), job.name.clone());
-                    }
-                }
+                self.switch_viewed_job(false);
             }
+            // If Shift is pressed, switch to next job and show its script
             (KeyModifiers::SHIFT, KeyCode::Down) if self.script_view.visible => {
-                // If Shift is pressed, switch to next job and show its script
-                if self.jobs_list.next() {
-                    if let Some(job) = self.jobs_list.selected_job() {
-                        self.script_view
-                            .change_job(job.id.clone(), job.name.clone());
-                    }
-                }
+                self.switch_viewed_job(true);
             }
 
             _ if self.script_view.visible => {
                 // If script view is visible, handle script view specific keys
-                self.script_view.handle_key(key);
+                let action = self.script_view.handle_key(key);
+                self.handle_viewer_action(action);
             }
 
             // Close job detail view
@@ -622,6 +989,71 @@ impl App {
                 self.script_view.visible = false;
             }
 
+            // Resubmit the job under the cursor
+            (_, KeyCode::Char('R'))
+                if !self.filter_popup.visible
+                    && !self.script_view.visible
+                    && !self.columns_popup.visible
+                    && !self.log_view.visible
+                    && !self.cancel_confirm =>
+            {
+                if let Some(job) = self.jobs_list.selected_job().cloned() {
+                    if let Err(e) = self.resubmit_popup.open(&job) {
+                        self.set_status_message(e, 5);
+                    }
+                }
+            }
+
+            // Copy the job ID, selected IDs, log path or working directory
+            (_, KeyCode::Char('y'))
+                if !self.filter_popup.visible
+                    && !self.script_view.visible
+                    && !self.columns_popup.visible
+                    && !self.log_view.visible
+                    && !self.cancel_confirm =>
+            {
+                self.copy_pending = true;
+                self.set_status_message(
+                    "Copy: [y] Job ID | [s] Selected IDs | [l] Log path | [w] Working directory"
+                        .to_string(),
+                    5,
+                );
+            }
+
+            // Export the listed or selected jobs
+            (_, KeyCode::Char('e'))
+                if !self.filter_popup.visible
+                    && !self.script_view.visible
+                    && !self.columns_popup.visible
+                    && !self.log_view.visible
+                    && !self.cancel_confirm =>
+            {
+                let columns = self
+                    .selected_columns
+                    .iter()
+                    .map(|col| col.title().to_string())
+                    .collect();
+                self.export_popup.open(
+                    self.jobs_list.jobs.len(),
+                    self.jobs_list.selected_jobs.len(),
+                    columns,
+                );
+            }
+
+            // Compare the two selected jobs
+            (_, KeyCode::Char('d'))
+                if !self.filter_popup.visible
+                    && !self.script_view.visible
+                    && !self.columns_popup.visible
+                    && !self.log_view.visible
+                    && !self.cancel_confirm =>
+            {
+                match self.jobs_list.get_selected_jobs().as_slice() {
+                    [left, right] => self.diff_view.show(left.clone(), right.clone()),
+                    _ => self.set_status_message("Select two jobs to compare".to_string(), 3),
+                }
+            }
+
             // Show log view
             (_, KeyCode::Char('v'))
                 if !self.filter_popup.visible
@@ -635,27 +1067,20 @@ impl App {
             }
 
             // Change job for log view
+            // If Shift is pressed, switch to previous job and show its logs
             (KeyModifiers::SHIFT, KeyCode::Up) if self.log_view.visible => {
-                // If Shift is pressed, switch to previous job and show its logs
-                if self.jobs_list.previous() {
-                    if let Some(job) = self.jobs_list.selected_job() {
-                        self.log_view.change_job(job.id.clone());
-                    }
-                }
+                self.switch_viewed_job(false);
             }
+            // If Shift is pressed, switch to next job and show its logs
             (KeyModifiers::SHIFT, KeyCode::Down) if self.log_view.visible => {
-                // If Shift is pressed, switch to next job and show its logs
-                if self.jobs_list.next() {
-                    if let Some(job) = self.jobs_list.selected_job() {
-                        self.log_view.change_job(job.id.clone());
-                    }
-                }
+                self.switch_viewed_job(true);
             }
 
             // Handle log view keys events
             _ if self.log_view.visible => {
                 // If log view is visible, handle log view specific keys
-                self.log_view.handle_key(key);
+                let action = self.log_view.handle_key(key);
+                self.handle_viewer_action(action);
             }
 
             // Handle columns popup key events
@@ -683,8 +1108,16 @@ impl App {
                         self.selected_columns = self.columns_popup.selected_columns.clone();
                         self.sort_columns = self.columns_popup.sort_columns.clone();
 
-                        // TODO: Save settings to config file
-                        self.set_status_message("Column settings saved and applied".to_string(), 3);
+                        match self.save_layout() {
+                            Ok(()) => self.set_status_message(
+                                "Column settings saved and applied".to_string(),
+                                3,
+                            ),
+                            Err(e) => self.set_status_message(
+                                format!("Failed to save column settings: {}", e),
+                                5,
+                            ),
+                        }
 
                         // Update the format and refresh
                         if let Err(e) = self.refresh_jobs() {
@@ -710,10 +1143,107 @@ impl App {
         }
     }
 
-    /// Handle mouse events
-    fn handle_mouse_event(&mut self, _mouse: MouseEvent) {
-        // TODO: Implement mouse event handling for TUI interactions
-        // seems unnecessary?
+    /// Move the cursor to the next or previous job and show it in the open
+    /// script or log view
+    fn switch_viewed_job(&mut self, forward: bool) {
+        let moved = if forward {
+            self.jobs_list.next()
+        } else {
+            self.jobs_list.previous()
+        };
+        if !moved {
+            return;
+        }
+        let Some(job) = self.jobs_list.selected_job() else {
+            return;
+        };
+        if self.script_view.visible {
+            self.script_view
+                .change_job(job.id.clone(), job.name.clone());
+        } else if self.log_view.visible {
+            self.log_view.change_job(job.id.clone());
+        }
+    }
+
+    /// Handle mouse events, sent to the topmost view
+    fn handle_mouse_event(&mut self, mouse: MouseEvent) {
+        // Only the wheel scrolls the viewers, as Up/Down would
+        let wheel = match mouse.kind {
+            MouseEventKind::ScrollUp => Some(KeyCode::Up),
+            MouseEventKind::ScrollDown => Some(KeyCode::Down),
+            _ => None,
+        };
+
+        if self.cancel_confirm || self.resubmit_popup.visible || self.export_popup.visible {
+            // Keyboard only
+        } else if self.diff_view.visible {
+            if let Some(key) = wheel {
+                for _ in 0..WHEEL_SCROLL_LINES {
+                    self.diff_view.handle_key(KeyEvent::from(key));
+                }
+            }
+        } else if self.log_view.visible {
+            for _ in 0..WHEEL_SCROLL_LINES {
+                match wheel {
+                    Some(KeyCode::Up) => self.log_view.scroll_up(),
+                    Some(_) => self.log_view.scroll_down(),
+                    None => break,
+                }
+            }
+        } else if self.columns_popup.visible {
+            self.columns_popup.handle_mouse(mouse);
+        } else if self.script_view.visible {
+            for _ in 0..WHEEL_SCROLL_LINES {
+                match wheel {
+                    Some(KeyCode::Up) => self.script_view.scroll_up(),
+                    Some(_) => self.script_view.scroll_down(),
+                    None => break,
+                }
+            }
+        } else if self.filter_popup.visible {
+            self.filter_popup.handle_mouse(
+                mouse,
+                &mut self.squeue_options,
+                &self.available_states,
+                &self.available_partitions,
+                &self.available_qos,
+            );
+        } else {
+            self.handle_jobs_list_mouse(mouse);
+        }
+    }
+
+    /// Click a job to move the cursor to it, twice to view its script, or a
+    /// header to sort by its column; the wheel moves the cursor
+    fn handle_jobs_list_mouse(&mut self, mouse: MouseEvent) {
+        match mouse.kind {
+            MouseEventKind::ScrollUp => self.jobs_list.scroll_by(-1),
+            MouseEventKind::ScrollDown => self.jobs_list.scroll_by(1),
+            MouseEventKind::Down(MouseButton::Left) => {
+                match self.jobs_list.hit_test(mouse.column, mouse.row) {
+                    Some(JobsListHit::Header(column)) => {
+                        self.jobs_list.focused_column = column;
+                        self.cycle_focused_sort();
+                    }
+                    Some(JobsListHit::Row(row)) => {
+                        self.jobs_list.state.select(Some(row));
+                        let double_click = self.last_click.is_some_and(|(time, last_row)| {
+                            last_row == row && time.elapsed() <= DOUBLE_CLICK_INTERVAL
+                        });
+                        if double_click {
+                            self.last_click = None;
+                            if let Some(job) = self.jobs_list.selected_job() {
+                                self.script_view.show(job.id.clone(), job.name.clone());
+                            }
+                        } else {
+                            self.last_click = Some((Instant::now(), row));
+                        }
+                    }
+                    None => {}
+                }
+            }
+            _ => {}
+        }
     }
 
     /// Handle tick events (called periodically)
@@ -752,6 +1282,8 @@ impl App {
     fn apply_filters(&mut self) -> Result<()> {
         self.filter_popup.visible = false;
         self.set_status_message("Applying filters...".to_string(), 3);
+        // Jobs leaving the list because of the new filters didn't change state
+        self.job_snapshot.reset();
 
         // Ensure we refresh the jobs with the updated filters
         let result = self.refresh_jobs();
@@ -823,6 +1355,11 @@ impl App {
             parts.push(format!("node_regex={}", node));
         }
 
+        // Minimum elapsed time
+        if let Some(min_time) = &self.squeue_options.min_time {
+            parts.push(format!("elapsed>={}", min_time));
+        }
+
         parts.join(", ")
     }
 
@@ -831,7 +1368,7 @@ impl App {
         self.running = false;
     }
 
-    /// Update the squeue format string and sort options based on selected columns
+    /// Update the squeue format string based on selected columns
     fn update_squeue_format(&mut self) {
         // Ensure we have at least one column selected
         // if self.selected_columns.is_empty() {
@@ -839,50 +1376,64 @@ impl App {
         //     self.selected_columns = JobColumn::defaults();
         // }
 
-        // Generate format string for squeue based on column selection
-        let format_string = self
+        let unfetchable = self.squeue_options.set_columns(&self.selected_columns);
+        if unfetchable != self.unfetchable_columns {
+            if !unfetchable.is_empty() {
+                let titles: Vec<&str> = unfetchable.iter().map(|col| col.title()).collect();
+                self.set_status_message(
+                    format!(
+                        "Empty columns: {} only have a % code, another column needs --Format",
+                        titles.join(", ")
+                    ),
+                    10,
+                );
+            }
+            self.unfetchable_columns = unfetchable;
+        }
+    }
+
+    /// Save the displayed columns, sort keys and column widths to the config file
+    fn save_layout(&mut self) -> Result<()> {
+        let layout = &mut self.config.layout;
+        layout.selected = self
             .selected_columns
             .iter()
-            .map(|col| col.format_code())
-            .collect::<Vec<&str>>()
-            .join("|");
-        self.squeue_options.format = format_string;
-
-        // Build sort string based on sort columns
-        // remove any existing sort columns
-        self.squeue_options.sorts.clear();
-        if !self.sort_columns.is_empty() {
-            // Add sort colsumns to the squeue options
-            for sort_col in &self.sort_columns {
-                // get the format code for the column, removing any leading '%'
-                let sort_code = sort_col.column.format_code().trim_start_matches('%');
-                // set the sort order
-                let is_ascending = matches!(sort_col.order, SortOrder::Ascending);
-
-                // add to the squeue options
-                self.squeue_options
-                    .sorts
-                    .insert(sort_code.to_string(), is_ascending);
-            }
-
-            // Set the first sort column as the primary sort
-            if let Some(first_sort) = self.sort_columns.first() {
-                let sort_column_index = self
-                    .selected_columns
-                    .iter()
-                    .position(|col| {
-                        std::mem::discriminant(col) == std::mem::discriminant(&first_sort.column)
-                    })
-                    .unwrap_or(0);
+            .map(|col| col.title().to_string())
+            .collect();
+        layout.sort = self
+            .sort_columns
+            .iter()
+            .map(|sc| SortConfig {
+                column: sc.column.title().to_string(),
+                descending: sc.order == SortOrder::Descending,
+            })
+            .collect();
+        layout.widths = self
+            .jobs_list
+            .column_widths
+            .iter()
+            .map(|(col, width)| (col.title().to_string(), *width))
+            .collect();
+        self.config.save_layout()
+    }
 
-                // Set the jobs list sort column and order
-                self.jobs_list.sort_column = sort_column_index;
-                self.jobs_list.sort_ascending = matches!(first_sort.order, SortOrder::Ascending);
+    /// Cycle the sort of the focused header column and re-sort the jobs locally
+    fn cycle_focused_sort(&mut self) {
+        if let Some(column) = self.jobs_list.focused_column(&self.selected_columns) {
+            cycle_sort(&mut self.sort_columns, column);
+            self.jobs_list.sort_jobs(&self.sort_columns);
+
+            let sort_desc = self
+                .sort_columns
+                .iter()
+                .map(|sc| format!("{}{}", sc.column.title(), sc.order.indicator()))
+                .collect::<Vec<_>>()
+                .join(", ");
+            if sort_desc.is_empty() {
+                self.set_status_message("Sort cleared".to_string(), 3);
+            } else {
+                self.set_status_message(format!("Sort: {}", sort_desc), 3);
             }
-        } else {
-            self.squeue_options.sorts.insert("i".to_string(), true);
-            self.jobs_list.sort_column = 0;
-            self.jobs_list.sort_ascending = true;
         }
     }
 
@@ -0,0 +1,533 @@
+use std::{
+    fmt,
+    io::{self, IsTerminal, Write},
+    slice, thread,
+    time::{Duration, Instant},
+};
+
+use tokio::runtime::Runtime;
+
+use crate::{
+    config::Config,
+    slurm::{
+        columns::register_custom_columns,
+        fields::SlurmDuration,
+        squeue::{run_squeue, SqueueOptions},
+        wait::JobWaiter,
+        Job, JobState,
+    },
+    ui::columns::{compare_jobs, layout_columns, JobColumn, SortColumn},
+    utils::{
+        export::{format_jobs, format_table, OutputFormat},
+        format_duration, get_username,
+    },
+};
+
+const USAGE: &str = "\
+Usage: slurmer                 Start the interactive job viewer
+       slurmer list [OPTIONS]  Print the job list once and exit
+       slurmer wait [OPTIONS] [JOBID...]
+                               Wait until jobs end and print their final states
+
+List options:
+  -f, --format <FORMAT>      table (default), json, csv, tsv or markdown
+  -u, --user <USER>          Jobs of this user (default: the current user)
+  -a, --all-users            Jobs of all users
+  -s, --state <STATES>       Comma-separated job states, e.g. RUNNING,PD
+  -p, --partition <NAMES>    Comma-separated partitions
+  -q, --qos <NAMES>          Comma-separated QoS
+  -n, --name <REGEX>         Job names matching a regex
+      --node <REGEX>         Node lists matching a regex
+      --min-time <DURATION>  Jobs that ran at least this long, e.g. 1:00:00
+  -c, --columns <TITLES>     Comma-separated column titles (default: the saved layout)
+  -h, --help                 Show this help
+
+Wait options:
+  -n, --name <REGEX>         Jobs with names matching a regex
+      --array <JOBID>        All tasks of an array job
+  -u, --user <USER>          Jobs of this user (default: the current user, or
+                             any user when job IDs or arrays are given)
+  -a, --all-users            Jobs of all users
+  -t, --timeout <DURATION>   Give up after this long, e.g. 90 (minutes) or 2:00:00
+  -i, --interval <SECONDS>   Time between two polls of squeue (default: 10)
+  -q, --quiet                Don't show the progress line
+  -h, --help                 Show this help
+
+Exit status: 0 on success, 1 when a Slurm command fails, 2 on invalid arguments
+or configuration, 3 when a waited job did not complete and 124 on timeout.";
+
+/// Exit status when a Slurm command fails
+const EXIT_SLURM: i32 = 1;
+/// Exit status for invalid arguments or configuration
+const EXIT_USAGE: i32 = 2;
+/// Exit status when a waited job ended in another state than `COMPLETED`
+const EXIT_JOB_FAILED: i32 = 3;
+/// Exit status when waiting timed out, as with timeout(1)
+const EXIT_TIMEOUT: i32 = 124;
+
+/// Default time between two polls of `slurmer wait`, as the TUI's refresh
+const DEFAULT_WAIT_INTERVAL: Duration = Duration::from_secs(10);
+
+/// Why a subcommand failed, deciding the exit status
+#[derive(Debug)]
+enum CliError {
+    /// Invalid arguments or configuration
+    Usage(String),
+    /// A Slurm command failed or could not be run
+    Slurm(String),
+    /// A waited job did not complete
+    JobFailed(String),
+    /// Waiting timed out
+    Timeout(String),
+}
+
+impl CliError {
+    fn exit_code(&self) -> i32 {
+        match self {
+            CliError::Usage(_) => EXIT_USAGE,
+            CliError::Slurm(_) => EXIT_SLURM,
+            CliError::JobFailed(_) => EXIT_JOB_FAILED,
+            CliError::Timeout(_) => EXIT_TIMEOUT,
+        }
+    }
+}
+
+impl fmt::Display for CliError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            CliError::Usage(message)
+            | CliError::Slurm(message)
+            | CliError::JobFailed(message)
+            | CliError::Timeout(message) => write!(f, "{}", message),
+        }
+    }
+}
+
+/// Run a subcommand given on the command line, returning the exit status.
+/// The terminal is left in its normal mode.
+pub fn run(args: &[String]) -> i32 {
+    let result = match args[0].as_str() {
+        "list" => list(&args[1..]),
+        "wait" => wait(&args[1..]),
+        "-h" | "--help" | "help" => {
+            println!("{}", USAGE);
+            Ok(())
+        }
+        command => Err(CliError::Usage(format!("Unknown command: {}", command))),
+    };
+
+    match result {
+        Ok(()) => 0,
+        Err(e) => {
+            eprintln!("slurmer: {}", e);
+            if let CliError::Usage(_) = e {
+                eprintln!("Try 'slurmer --help' for more information.");
+            }
+            e.exit_code()
+        }
+    }
+}
+
+/// Walks the arguments of a subcommand, accepting both `--flag value` and
+/// `--flag=value`
+struct ArgParser<'a> {
+    args: slice::Iter<'a, String>,
+    /// Value given with `=` to the last flag
+    inline: Option<String>,
+}
+
+impl<'a> ArgParser<'a> {
+    fn new(args: &'a [String]) -> Self {
+        Self {
+            args: args.iter(),
+            inline: None,
+        }
+    }
+
+    /// The next flag or positional argument
+    fn next_arg(&mut self) -> Option<&'a str> {
+        let arg = self.args.next()?;
+        match arg.split_once('=') {
+            Some((flag, value)) if flag.starts_with("--") => {
+                self.inline = Some(value.to_string());
+                Some(flag)
+            }
+            _ => {
+                self.inline = None;
+                Some(arg)
+            }
+        }
+    }
+
+    /// The value of a flag
+    fn value(&mut self, flag: &str) -> Result<String, CliError> {
+        self.inline
+            .take()
+            .or_else(|| self.args.next().cloned())
+            .ok_or_else(|| CliError::Usage(format!("Missing value for {}", flag)))
+    }
+}
+
+/// Options of `slurmer list`
+struct ListArgs {
+    format: OutputFormat,
+    squeue_options: SqueueOptions,
+    /// Column titles replacing the saved layout
+    columns: Option<Vec<String>>,
+    help: bool,
+}
+
+impl ListArgs {
+    fn parse(args: &[String]) -> Result<Self, CliError> {
+        let mut list_args = ListArgs {
+            format: OutputFormat::default(),
+            squeue_options: SqueueOptions {
+                user: Some(get_username()),
+                ..Default::default()
+            },
+            columns: None,
+            help: false,
+        };
+        let options = &mut list_args.squeue_options;
+
+        let mut parser = ArgParser::new(args);
+        while let Some(flag) = parser.next_arg() {
+            match flag {
+                "-f" | "--format" => {
+                    list_args.format = parser.value(flag)?.parse().map_err(CliError::Usage)?;
+                }
+                "-u" | "--user" => options.user = Some(parser.value(flag)?),
+                "-a" | "--all-users" => options.user = None,
+                "-s" | "--state" => {
+                    options.states = split_list(&parser.value(flag)?)
+                        .iter()
+                        .map(|state| match state.parse() {
+                            Ok(JobState::Other) | Err(_) => {
+                                Err(CliError::Usage(format!("Unknown job state: {}", state)))
+                            }
+                            Ok(state) => Ok(state),
+                        })
+                        .collect::<Result<_, _>>()?;
+                }
+                "-p" | "--partition" => options.partitions = split_list(&parser.value(flag)?),
+                "-q" | "--qos" => options.qos = split_list(&parser.value(flag)?),
+                "-n" | "--name" => options.name_filter = Some(parser.value(flag)?),
+                "--node" => options.node_filter = Some(parser.value(flag)?),
+                "--min-time" => {
+                    let min_time = parser
+                        .value(flag)?
+                        .parse::<SlurmDuration>()
+                        .map_err(CliError::Usage)?;
+                    options.min_time = Some(min_time);
+                }
+                "-c" | "--columns" => list_args.columns = Some(split_list(&parser.value(flag)?)),
+                "-h" | "--help" => list_args.help = true,
+                _ => return Err(CliError::Usage(format!("Unknown option: {}", flag))),
+            }
+        }
+
+        Ok(list_args)
+    }
+}
+
+/// `slurmer list`: query squeue like the TUI does and print the jobs once
+fn list(args: &[String]) -> Result<(), CliError> {
+    let ListArgs {
+        format,
+        mut squeue_options,
+        columns,
+        help,
+    } = ListArgs::parse(args)?;
+    if help {
+        println!("{}", USAGE);
+        return Ok(());
+    }
+
+    let config = Config::load().map_err(|e| CliError::Usage(format!("{:#}", e)))?;
+    register_custom_columns(config.columns.clone());
+
+    let (mut selected_columns, sort_columns) = layout_columns(&config.layout);
+    if let Some(titles) = columns {
+        selected_columns = titles
+            .iter()
+            .map(|title| {
+                JobColumn::from_title(title)
+                    .ok_or_else(|| CliError::Usage(format!("Unknown column: {}", title)))
+            })
+            .collect::<Result<_, _>>()?;
+        if selected_columns.is_empty() {
+            return Err(CliError::Usage("No columns given".to_string()));
+        }
+    }
+
+    for column in squeue_options.set_columns(&selected_columns) {
+        eprintln!(
+            "Warning: column {} only has a % code and stays empty, another column needs --Format",
+            column.title()
+        );
+    }
+    let runtime = Runtime::new().map_err(|e| CliError::Slurm(e.to_string()))?;
+    let jobs = query_jobs(&runtime, &squeue_options, &sort_columns)?;
+
+    let output = format_jobs(&jobs, &selected_columns, format, config.display.time_format);
+    // A closed pipe, e.g. into `head`, is not an error
+    let _ = io::stdout().lock().write_all(output.as_bytes());
+    Ok(())
+}
+
+/// Run squeue and apply the client-side filters and sort keys
+fn query_jobs(
+    runtime: &Runtime,
+    options: &SqueueOptions,
+    sort_columns: &[SortColumn],
+) -> Result<Vec<Job>, CliError> {
+    let mut jobs = runtime
+        .block_on(async { run_squeue(options).await })
+        .map_err(|e| CliError::Slurm(format!("{:#}", e)))?;
+
+    let report = options.filter_jobs(&mut jobs);
+    if let Some(error) = report.errors.into_iter().next() {
+        return Err(CliError::Usage(error));
+    }
+
+    jobs.sort_by(|a, b| compare_jobs(a, b, sort_columns));
+    Ok(jobs)
+}
+
+/// Options of `slurmer wait`
+struct WaitArgs {
+    job_ids: Vec<String>,
+    arrays: Vec<String>,
+    squeue_options: SqueueOptions,
+    timeout: Option<Duration>,
+    interval: Duration,
+    quiet: bool,
+    help: bool,
+}
+
+impl WaitArgs {
+    fn parse(args: &[String]) -> Result<Self, CliError> {
+        let mut wait_args = WaitArgs {
+            job_ids: Vec::new(),
+            arrays: Vec::new(),
+            squeue_options: SqueueOptions {
+                user: Some(get_username()),
+                ..Default::default()
+            },
+            timeout: None,
+            interval: DEFAULT_WAIT_INTERVAL,
+            quiet: false,
+            help: false,
+        };
+        let options = &mut wait_args.squeue_options;
+        let mut user_given = false;
+
+        let mut parser = ArgParser::new(args);
+        while let Some(flag) = parser.next_arg() {
+            match flag {
+                "-n" | "--name" => options.name_filter = Some(parser.value(flag)?),
+                "--array" => wait_args.arrays.extend(split_list(&parser.value(flag)?)),
+                "-u" | "--user" => {
+                    options.user = Some(parser.value(flag)?);
+                    user_given = true;
+                }
+                "-a" | "--all-users" => {
+                    options.user = None;
+                    user_given = true;
+                }
+                "-t" | "--timeout" => {
+                    let timeout = parser
+                        .value(flag)?
+                        .parse::<SlurmDuration>()
+                        .map_err(CliError::Usage)?;
+                    wait_args.timeout = timeout.as_secs().map(Duration::from_secs);
+                }
+                "-i" | "--interval" => {
+                    let value = parser.value(flag)?;
+                    let secs = value
+                        .parse::<u64>()
+                        .ok()
+                        .filter(|secs| *secs > 0)
+                        .ok_or_else(|| CliError::Usage(format!("Invalid interval: {}", value)))?;
+                    wait_args.interval = Duration::from_secs(secs);
+                }
+                "-q" | "--quiet" => wait_args.quiet = true,
+                "-h" | "--help" => wait_args.help = true,
+                _ if flag.starts_with('-') => {
+                    return Err(CliError::Usage(format!("Unknown option: {}", flag)))
+                }
+                _ => wait_args.job_ids.extend(split_list(flag)),
+            }
+        }
+
+        // Jobs given by ID are queried directly, whoever submitted them
+        if !wait_args.job_ids.is_empty() || !wait_args.arrays.is_empty() {
+            let options = &mut wait_args.squeue_options;
+            options.jobs = wait_args
+                .job_ids
+                .iter()
+                .chain(&wait_args.arrays)
+                .cloned()
+                .collect();
+            if !user_given {
+                options.user = None;
+            }
+        }
+
+        Ok(wait_args)
+    }
+
+    /// Whether a job from squeue is one of those waited for. Without IDs or
+    /// arrays, the name filter selects the jobs.
+    fn matches(&self, job: &Job) -> bool {
+        (self.job_ids.is_empty() && self.arrays.is_empty())
+            || self.job_ids.contains(&job.id)
+            || self.arrays.iter().any(|array| {
+                job.id == *array
+                    || job
+                        .id
+                        .strip_prefix(array.as_str())
+                        .is_some_and(|task| task.starts_with('_'))
+            })
+    }
+}
+
+/// `slurmer wait`: poll squeue until the matching jobs have ended, then
+/// print their final states
+fn wait(args: &[String]) -> Result<(), CliError> {
+    let mut wait_args = WaitArgs::parse(args)?;
+    if wait_args.help {
+        println!("{}", USAGE);
+        return Ok(());
+    }
+    if wait_args.job_ids.is_empty()
+        && wait_args.arrays.is_empty()
+        && wait_args.squeue_options.name_filter.is_none()
+    {
+        return Err(CliError::Usage(
+            "Give job IDs, --name or --array to wait for".to_string(),
+        ));
+    }
+
+    wait_args
+        .squeue_options
+        .set_columns(&[JobColumn::Id, JobColumn::Name, JobColumn::State]);
+    let runtime = Runtime::new().map_err(|e| CliError::Slurm(e.to_string()))?;
+    let started = Instant::now();
+
+    let mut waiter = JobWaiter::default();
+    let mut jobs = query_jobs(&runtime, &wait_args.squeue_options, &[])?;
+    jobs.retain(|job| wait_args.matches(job));
+    waiter.update(&jobs);
+    // Jobs that ended before the wait started are only in the accounting database
+    waiter.add_finished(&wait_args.squeue_options.jobs);
+    if waiter.is_empty() {
+        return Err(CliError::Usage("No matching jobs found".to_string()));
+    }
+
+    let mut progress = Progress::new(!wait_args.quiet);
+    let mut poll_error: Option<String> = None;
+    while !waiter.all_done() {
+        let elapsed = started.elapsed();
+        let remaining = match wait_args.timeout {
+            Some(timeout) if elapsed >= timeout => break,
+            Some(timeout) => timeout - elapsed,
+            None => wait_args.interval,
+        };
+
+        let mut status = waiter.progress();
+        if let Some(error) = &poll_error {
+            status.push_str(&format!(" (retrying: {})", error));
+        }
+        progress.show(status, elapsed);
+
+        thread::sleep(wait_args.interval.min(remaining));
+        match query_jobs(&runtime, &wait_args.squeue_options, &[]) {
+            Ok(mut jobs) => {
+                jobs.retain(|job| wait_args.matches(job));
+                waiter.update(&jobs);
+                poll_error = None;
+            }
+            // A busy controller shouldn't end a long wait
+            Err(e) => poll_error = Some(e.to_string()),
+        }
+    }
+    progress.clear();
+
+    let jobs = waiter.jobs();
+    let header = ["ID", "Name", "State"].map(str::to_string);
+    let rows: Vec<Vec<String>> = jobs
+        .iter()
+        .map(|job| vec![job.id.clone(), job.name.clone(), job.state_name.clone()])
+        .collect();
+    let _ = io::stdout()
+        .lock()
+        .write_all(format_table(&header, &rows).as_bytes());
+
+    if !waiter.all_done() {
+        let unfinished = jobs.iter().filter(|job| !job.done).count();
+        return Err(CliError::Timeout(format!(
+            "Timed out after {} with {} of {} jobs still queued",
+            format_duration(started.elapsed().as_secs()),
+            unfinished,
+            jobs.len()
+        )));
+    }
+    let failed = jobs.iter().filter(|job| !job.succeeded()).count();
+    if failed > 0 {
+        return Err(CliError::JobFailed(format!(
+            "{} of {} jobs did not complete",
+            failed,
+            jobs.len()
+        )));
+    }
+    Ok(())
+}
+
+/// Progress line of `slurmer wait` on stderr, redrawn in place on a terminal
+/// and printed when it changes otherwise
+struct Progress {
+    enabled: bool,
+    terminal: bool,
+    last: String,
+}
+
+impl Progress {
+    fn new(enabled: bool) -> Self {
+        Self {
+            enabled,
+            terminal: io::stderr().is_terminal(),
+            last: String::new(),
+        }
+    }
+
+    fn show(&mut self, status: String, elapsed: Duration) {
+        if !self.enabled {
+            return;
+        }
+        let elapsed = format_duration(elapsed.as_secs());
+        if self.terminal {
+            eprint!("\r\x1b[2KWaiting: {} [{}]", status, elapsed);
+        } else if status != self.last {
+            eprintln!("[{}] {}", elapsed, status);
+        }
+        self.last = status;
+    }
+
+    /// Remove the progress line before the summary
+    fn clear(&self) {
+        if self.enabled && self.terminal && !self.last.is_empty() {
+            eprint!("\r\x1b[2K");
+        }
+    }
+}
+
+/// Split a comma-separated list, dropping empty items
+fn split_list(value: &str) -> Vec<String> {
+    value
+        .split(',')
+        .map(str::trim)
+        .filter(|item| !item.is_empty())
+        .map(str::to_string)
+        .collect()
+}
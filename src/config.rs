@@ -0,0 +1,277 @@
+use color_eyre::{
+    eyre::{eyre, WrapErr},
+    Result,
+};
+use serde::{Deserialize, Serialize};
+use std::{collections::BTreeMap, fs, path::PathBuf};
+use toml_edit::{DocumentMut, Item};
+
+use crate::slurm::{columns::CustomColumn, fields::TimeFormat};
+use crate::ui::highlight::Theme;
+
+/// User configuration, read from `$XDG_CONFIG_HOME/slurmer/config.toml`
+/// (or `~/.config/slurmer/config.toml`). Every field is optional.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct Config {
+    /// Display settings
+    pub display: DisplayConfig,
+    /// User-defined columns, offered alongside the built-in ones
+    pub columns: Vec<CustomColumn>,
+    /// Saved jobs list layout, written by the columns popup
+    pub layout: LayoutConfig,
+    /// Log view settings
+    pub log: LogConfig,
+    /// Notifications on job state changes
+    pub notifications: NotificationConfig,
+    /// Commands run on job events
+    pub hooks: Vec<HookConfig>,
+    /// Copying to the system clipboard
+    pub clipboard: ClipboardConfig,
+}
+
+/// Display settings
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct DisplayConfig {
+    /// How submit/start/end times are shown: "absolute" or "relative"
+    pub time_format: TimeFormat,
+    /// Fit column widths to the content of the visible rows
+    pub auto_fit_columns: bool,
+    /// Colors of highlighted job scripts: "dark" or "light" terminal background
+    pub theme: Theme,
+}
+
+impl Default for DisplayConfig {
+    fn default() -> Self {
+        Self {
+            time_format: TimeFormat::default(),
+            auto_fit_columns: true,
+            theme: Theme::default(),
+        }
+    }
+}
+
+/// Log view settings
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct LogConfig {
+    /// Memory kept for the end of a log, in megabytes; the rest is read from disk
+    pub max_retained_mb: usize,
+    /// Regexes flagging a line as an error
+    pub error_patterns: Vec<String>,
+    /// Regexes flagging a line as a warning
+    pub warning_patterns: Vec<String>,
+}
+
+impl Default for LogConfig {
+    fn default() -> Self {
+        Self {
+            max_retained_mb: 16,
+            error_patterns: [
+                r"(?i)\berror\b",
+                r"Traceback \(most recent call last\)",
+                r"Segmentation fault",
+                r"\bOOM\b|oom-kill|Out Of Memory",
+                r"CUDA out of memory",
+                r"slurmstepd: error",
+                r"DUE TO TIME LIMIT",
+            ]
+            .map(String::from)
+            .to_vec(),
+            warning_patterns: [r"(?i)\bwarn(ing)?\b", r"[A-Za-z]+Warning:"]
+                .map(String::from)
+                .to_vec(),
+        }
+    }
+}
+
+/// How notifications reach the desktop
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum DesktopNotification {
+    #[default]
+    Off,
+    /// OSC 9, understood by iTerm2, WezTerm, kitty and Windows Terminal
+    Osc9,
+    /// OSC 777, understood by VTE based terminals, foot and urxvt
+    Osc777,
+}
+
+/// Notifications on job state changes
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct NotificationConfig {
+    pub enabled: bool,
+    /// Ring the terminal bell
+    pub bell: bool,
+    /// Desktop notification escape sequence to send
+    pub desktop: DesktopNotification,
+    /// Shell command to run, with the job in `SLURMER_*` environment variables
+    pub command: Option<String>,
+    /// New states to notify about, e.g. `FAILED` or `GONE` for jobs that left
+    /// the queue with an unknown state; empty for all
+    pub states: Vec<String>,
+    /// Only notify about jobs whose name matches this regex
+    pub name_filter: Option<String>,
+}
+
+impl Default for NotificationConfig {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            bell: true,
+            desktop: DesktopNotification::default(),
+            command: None,
+            states: Vec::new(),
+            name_filter: None,
+        }
+    }
+}
+
+/// Whether copied text is sent to the terminal with OSC 52
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Osc52 {
+    /// Unless the terminal is known not to support it
+    #[default]
+    Auto,
+    Always,
+    /// Only keep copied text in slurmer's register
+    Never,
+}
+
+/// Copying to the system clipboard
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct ClipboardConfig {
+    pub osc52: Osc52,
+}
+
+/// Job events hooks can run on
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum HookEvent {
+    /// A job started running
+    Started,
+    /// A job reached a final state, or left the queue
+    Finished,
+    /// A job has been pending for `pending_minutes`
+    PendingTooLong,
+    /// A node of a running job is down
+    NodeDown,
+}
+
+impl HookEvent {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            HookEvent::Started => "started",
+            HookEvent::Finished => "finished",
+            HookEvent::PendingTooLong => "pending_too_long",
+            HookEvent::NodeDown => "node_down",
+        }
+    }
+}
+
+/// A shell command run on a job event, with the job in `SLURMER_*`
+/// environment variables
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct HookConfig {
+    pub event: HookEvent,
+    pub command: String,
+    /// Final states that trigger a `finished` hook, e.g. `FAILED`; empty for all
+    #[serde(default)]
+    pub states: Vec<String>,
+    /// Only run for jobs whose name matches this regex
+    #[serde(default)]
+    pub name_filter: Option<String>,
+    /// Minutes a job must be pending for a `pending_too_long` hook
+    #[serde(default = "default_pending_minutes")]
+    pub pending_minutes: u64,
+    /// Seconds after which the command is killed
+    #[serde(default = "default_hook_timeout")]
+    pub timeout_secs: u64,
+}
+
+fn default_pending_minutes() -> u64 {
+    60
+}
+
+fn default_hook_timeout() -> u64 {
+    60
+}
+
+/// Jobs list layout. Columns are referred to by their header title.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct LayoutConfig {
+    /// Displayed columns, in order; empty for the defaults
+    pub selected: Vec<String>,
+    /// Sort keys, highest priority first
+    pub sort: Vec<SortConfig>,
+    /// Column widths set with `<`/`>`
+    pub widths: BTreeMap<String, u16>,
+}
+
+/// A saved sort key
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SortConfig {
+    pub column: String,
+    #[serde(default)]
+    pub descending: bool,
+}
+
+impl Config {
+    /// Path of the configuration file
+    pub fn path() -> Option<PathBuf> {
+        let config_dir = std::env::var_os("XDG_CONFIG_HOME")
+            .filter(|dir| !dir.is_empty())
+            .map(PathBuf::from)
+            .or_else(|| std::env::var_os("HOME").map(|home| PathBuf::from(home).join(".config")))?;
+        Some(config_dir.join("slurmer").join("config.toml"))
+    }
+
+    /// Load the configuration, falling back to defaults if there is no config file
+    pub fn load() -> Result<Self> {
+        let Some(path) = Self::path() else {
+            return Ok(Self::default());
+        };
+        if !path.exists() {
+            return Ok(Self::default());
+        }
+
+        let content = fs::read_to_string(&path)
+            .wrap_err_with(|| format!("Failed to read config file {}", path.display()))?;
+        toml::from_str(&content)
+            .wrap_err_with(|| format!("Failed to parse config file {}", path.display()))
+    }
+
+    /// Write the layout to the `[layout]` table of the config file, leaving
+    /// the rest of the file, comments included, as it was
+    pub fn save_layout(&self) -> Result<()> {
+        let path = Self::path().ok_or_else(|| eyre!("Cannot determine the config directory"))?;
+        if let Some(dir) = path.parent() {
+            fs::create_dir_all(dir)
+                .wrap_err_with(|| format!("Failed to create {}", dir.display()))?;
+        }
+
+        let content = if path.exists() {
+            fs::read_to_string(&path)
+                .wrap_err_with(|| format!("Failed to read config file {}", path.display()))?
+        } else {
+            String::new()
+        };
+        let mut document: DocumentMut = content
+            .parse()
+            .wrap_err_with(|| format!("Failed to parse config file {}", path.display()))?;
+
+        let layout: DocumentMut = toml::to_string(&self.layout)
+            .wrap_err("Failed to serialize layout")?
+            .parse()
+            .wrap_err("Failed to serialize layout")?;
+        document["layout"] = Item::Table(layout.as_table().clone());
+
+        fs::write(&path, document.to_string())
+            .wrap_err_with(|| format!("Failed to write config file {}", path.display()))
+    }
+}
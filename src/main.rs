@@ -8,6 +8,8 @@ use ratatui::{backend::CrosstermBackend, Terminal};
 use std::io;
 
 mod app;
+mod cli;
+mod config;
 mod slurm;
 mod ui;
 mod utils;
@@ -15,6 +17,12 @@ mod utils;
 use app::App;
 
 fn main() -> Result<()> {
+    // Subcommands print their output and exit without taking over the terminal
+    let args: Vec<String> = std::env::args().skip(1).collect();
+    if !args.is_empty() {
+        std::process::exit(cli::run(&args));
+    }
+
     // Setup terminal
     enable_raw_mode()?;
     let mut stdout = io::stdout();
@@ -22,9 +30,9 @@ fn main() -> Result<()> {
     let backend = CrosstermBackend::new(stdout);
     let mut terminal = Terminal::new(backend)?;
 
-    // Create app and run it
-    let mut app = App::new()?;
-    let result = app.run(&mut terminal);
+    // Create app and run it. Errors loading the config are reported once
+    // the terminal is restored.
+    let result = App::new().and_then(|mut app| app.run(&mut terminal));
 
     // Restore terminal
     disable_raw_mode()?;
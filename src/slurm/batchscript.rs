@@ -0,0 +1,81 @@
+use std::{fmt, process::Command};
+
+/// Where the content of a batch script came from
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ScriptSource {
+    /// The copy kept by the controller (`scontrol write batch_script`)
+    Controller,
+    /// The copy stored in the accounting database (`sacct --batch-script`)
+    Accounting,
+    /// The script file on disk, which may have changed since submission
+    File,
+}
+
+impl fmt::Display for ScriptSource {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let source = match self {
+            ScriptSource::Controller => "scontrol",
+            ScriptSource::Accounting => "sacct",
+            ScriptSource::File => "file",
+        };
+        write!(f, "{}", source)
+    }
+}
+
+/// Get the batch script a job was submitted with.
+///
+/// The copies kept by Slurm are preferred, since the file may have been edited
+/// or deleted after submission: the controller's while it knows the job, then
+/// the accounting database's (Slurm ≥ 21.08 with
+/// `AccountingStoreFlags=job_script`). The file at `script_path` is the last resort.
+pub fn fetch_batch_script(
+    job_id: &str,
+    script_path: Option<&str>,
+) -> Option<(String, ScriptSource)> {
+    from_controller(job_id)
+        .map(|script| (script, ScriptSource::Controller))
+        .or_else(|| from_accounting(job_id).map(|script| (script, ScriptSource::Accounting)))
+        .or_else(|| {
+            let script = std::fs::read_to_string(script_path?).ok()?;
+            Some((script, ScriptSource::File))
+        })
+}
+
+fn from_controller(job_id: &str) -> Option<String> {
+    let output = Command::new("scontrol")
+        .args(["write", "batch_script", job_id, "-"])
+        .output()
+        .ok()?;
+    if !output.status.success() {
+        return None;
+    }
+
+    let script = String::from_utf8_lossy(&output.stdout).to_string();
+    (!script.trim().is_empty()).then_some(script)
+}
+
+fn from_accounting(job_id: &str) -> Option<String> {
+    let output = Command::new("sacct")
+        .args(["--batch-script", "-j", job_id])
+        .output()
+        .ok()?;
+    if !output.status.success() {
+        return None;
+    }
+
+    // Skip the "Batch Script for <id>" header and its separator
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    let script = match stdout.find("#!") {
+        Some(start) => &stdout[start..],
+        None => stdout
+            .trim_start()
+            .strip_prefix("Batch Script for")
+            .and_then(|rest| rest.split_once('\n'))
+            .map(|(_, rest)| rest.trim_start_matches(['-', '\n']))
+            .unwrap_or(&stdout),
+    };
+
+    // Jobs whose script was not stored are listed as NONE
+    let trimmed = script.trim();
+    (!trimmed.is_empty() && trimmed != "NONE").then(|| script.to_string())
+}
@@ -0,0 +1,691 @@
+use ratatui::layout::Constraint;
+use serde::{Deserialize, Serialize};
+use std::cmp::Ordering;
+use std::str::FromStr;
+use std::sync::OnceLock;
+
+use super::fields::TimeFormat;
+use super::{Job, JobState};
+use crate::utils::job_id_sort_key;
+
+/// Available columns for display in job list
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum JobColumn {
+    Id,
+    Name,
+    User,
+    State,
+    Partition,
+    QoS,
+    Nodes,
+    Node,
+    CPUs,
+    Time,
+    Memory,
+    Account,
+    Priority,
+    WorkDir,
+    SubmitTime,
+    StartTime,
+    EndTime,
+    PReason,
+    TimeLimit,
+    TimeLeft,
+    Gres,
+    Comment,
+    Dependency,
+    Reason,
+    Command,
+    Nice,
+    ArrayJobId,
+    ArrayTaskId,
+    /// A user-defined column, indexing into the registered custom columns
+    Custom(usize),
+}
+
+/// A user-defined column declared in the config file.
+/// Exactly one of `code` (a squeue `%` code) or `field` (a `--Format` field name)
+/// should be set; values are stored in `Job::extra` under the column title.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct CustomColumn {
+    /// Header title, also the key in `Job::extra`
+    pub title: String,
+    /// squeue `%` format code, e.g. `%W` for licenses
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub code: Option<String>,
+    /// squeue `--Format` field name, e.g. `Reservation`
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub field: Option<String>,
+    /// Column width in characters
+    #[serde(default = "default_custom_width")]
+    pub width: u16,
+}
+
+fn default_custom_width() -> u16 {
+    12
+}
+
+static CUSTOM_COLUMNS: OnceLock<Vec<CustomColumn>> = OnceLock::new();
+
+/// Register the user-defined columns. Only the first call has an effect,
+/// so this is done once at startup from the config.
+pub fn register_custom_columns(columns: Vec<CustomColumn>) {
+    let _ = CUSTOM_COLUMNS.set(columns);
+}
+
+/// All registered user-defined columns
+pub fn custom_columns() -> &'static [CustomColumn] {
+    CUSTOM_COLUMNS.get().map(Vec::as_slice).unwrap_or(&[])
+}
+
+/// Everything known about a column: the squeue format code that fetches it,
+/// how it is shown, and which `Job` field it is parsed into and sorted by
+pub struct ColumnSpec {
+    pub column: JobColumn,
+    /// squeue `%` format code
+    pub code: &'static str,
+    /// squeue `--Format` field name, used when a custom column needs `--Format`
+    pub field: &'static str,
+    /// Header title
+    pub title: &'static str,
+    /// Default width constraint in the jobs list
+    pub width: Constraint,
+    /// Store a squeue value (already trimmed, never empty or `N/A`) on the job
+    pub parse: fn(&mut Job, &str),
+    /// Render the job's value for this column
+    pub display: fn(&Job, TimeFormat) -> String,
+    /// Compare two jobs by this column, missing values first
+    pub compare: fn(&Job, &Job) -> Ordering,
+}
+
+/// Registry of all built-in columns, in the order they are offered to the user
+pub static COLUMNS: &[ColumnSpec] = &[
+    ID_SPEC,
+    NAME_SPEC,
+    USER_SPEC,
+    STATE_SPEC,
+    PARTITION_SPEC,
+    QOS_SPEC,
+    NODES_SPEC,
+    NODE_SPEC,
+    CPUS_SPEC,
+    TIME_SPEC,
+    MEMORY_SPEC,
+    ACCOUNT_SPEC,
+    PRIORITY_SPEC,
+    WORK_DIR_SPEC,
+    SUBMIT_TIME_SPEC,
+    START_TIME_SPEC,
+    END_TIME_SPEC,
+    PREASON_SPEC,
+    TIME_LIMIT_SPEC,
+    TIME_LEFT_SPEC,
+    GRES_SPEC,
+    COMMENT_SPEC,
+    DEPENDENCY_SPEC,
+    REASON_SPEC,
+    COMMAND_SPEC,
+    NICE_SPEC,
+    ARRAY_JOB_ID_SPEC,
+    ARRAY_TASK_ID_SPEC,
+];
+
+const ID_SPEC: ColumnSpec = ColumnSpec {
+    column: JobColumn::Id,
+    code: "%i",
+    field: "JobArrayID",
+    title: "ID",
+    width: Constraint::Length(10),
+    parse: |job, v| job.id = v.to_string(),
+    display: |job, _| job.id.clone(),
+    compare: |a, b| job_id_sort_key(&a.id).cmp(&job_id_sort_key(&b.id)),
+};
+
+const NAME_SPEC: ColumnSpec = ColumnSpec {
+    column: JobColumn::Name,
+    code: "%j",
+    field: "Name",
+    title: "Name",
+    width: Constraint::Percentage(20),
+    parse: |job, v| job.name = v.to_string(),
+    display: |job, _| {
+        // Truncate name if too long
+        if job.name.chars().count() > 30 {
+            format!("{}...", job.name.chars().take(27).collect::<String>())
+        } else {
+            job.name.clone()
+        }
+    },
+    compare: |a, b| a.name.cmp(&b.name),
+};
+
+const USER_SPEC: ColumnSpec = ColumnSpec {
+    column: JobColumn::User,
+    code: "%u",
+    field: "UserName",
+    title: "User",
+    width: Constraint::Length(10),
+    parse: |job, v| job.user = v.to_string(),
+    display: |job, _| job.user.clone(),
+    compare: |a, b| a.user.cmp(&b.user),
+};
+
+const STATE_SPEC: ColumnSpec = ColumnSpec {
+    column: JobColumn::State,
+    code: "%T",
+    field: "State",
+    title: "State",
+    width: Constraint::Length(12),
+    parse: |job, v| job.state = JobState::from_str(v).unwrap_or(JobState::Other),
+    display: |job, _| job.state.to_string(),
+    compare: |a, b| a.state.to_string().cmp(&b.state.to_string()),
+};
+
+const PARTITION_SPEC: ColumnSpec = ColumnSpec {
+    column: JobColumn::Partition,
+    code: "%P",
+    field: "Partition",
+    title: "Partition",
+    width: Constraint::Length(12),
+    parse: |job, v| job.partition = v.to_string(),
+    display: |job, _| job.partition.clone(),
+    compare: |a, b| a.partition.cmp(&b.partition),
+};
+
+const QOS_SPEC: ColumnSpec = ColumnSpec {
+    column: JobColumn::QoS,
+    code: "%q",
+    field: "QOS",
+    title: "QoS",
+    width: Constraint::Length(10),
+    parse: |job, v| job.qos = v.to_string(),
+    display: |job, _| job.qos.clone(),
+    compare: |a, b| a.qos.cmp(&b.qos),
+};
+
+const NODES_SPEC: ColumnSpec = ColumnSpec {
+    column: JobColumn::Nodes,
+    code: "%D",
+    field: "NumNodes",
+    title: "Nodes",
+    width: Constraint::Length(7),
+    parse: |job, v| job.nodes = v.parse().unwrap_or(0),
+    display: |job, _| job.nodes.to_string(),
+    compare: |a, b| a.nodes.cmp(&b.nodes),
+};
+
+const NODE_SPEC: ColumnSpec = ColumnSpec {
+    column: JobColumn::Node,
+    code: "%N",
+    field: "NodeList",
+    title: "Node",
+    width: Constraint::Percentage(12), // Node list can be long
+    parse: |job, v| job.node = Some(v.to_string()),
+    display: |job, _| or_dash(&job.node),
+    compare: |a, b| a.node.cmp(&b.node),
+};
+
+const CPUS_SPEC: ColumnSpec = ColumnSpec {
+    column: JobColumn::CPUs,
+    code: "%C",
+    field: "NumCPUs",
+    title: "CPUs",
+    width: Constraint::Length(6),
+    parse: |job, v| job.cpus = v.parse().unwrap_or(0),
+    display: |job, _| job.cpus.to_string(),
+    compare: |a, b| a.cpus.cmp(&b.cpus),
+};
+
+const TIME_SPEC: ColumnSpec = ColumnSpec {
+    column: JobColumn::Time,
+    code: "%M", // Elapsed time
+    field: "TimeUsed",
+    title: "Time",
+    width: Constraint::Length(12),
+    parse: |job, v| job.time = v.parse().ok(),
+    display: |job, _| to_string_or_dash(&job.time),
+    compare: |a, b| a.time.cmp(&b.time),
+};
+
+const MEMORY_SPEC: ColumnSpec = ColumnSpec {
+    column: JobColumn::Memory,
+    code: "%m",
+    field: "MinMemory",
+    title: "Memory",
+    width: Constraint::Length(10),
+    parse: |job, v| job.memory = v.parse().ok(),
+    display: |job, _| to_string_or_dash(&job.memory),
+    // Per-CPU and per-node amounts only compare as what the whole job gets
+    compare: |a, b| {
+        let total = |job: &Job| job.memory.map(|m| m.total_mb(job.cpus, job.nodes));
+        total(a).cmp(&total(b))
+    },
+};
+
+const ACCOUNT_SPEC: ColumnSpec = ColumnSpec {
+    column: JobColumn::Account,
+    code: "%a",
+    field: "Account",
+    title: "Account",
+    width: Constraint::Length(12),
+    parse: |job, v| job.account = Some(v.to_string()),
+    display: |job, _| or_dash(&job.account),
+    compare: |a, b| a.account.cmp(&b.account),
+};
+
+const PRIORITY_SPEC: ColumnSpec = ColumnSpec {
+    column: JobColumn::Priority,
+    code: "%Q",
+    field: "PriorityLong",
+    title: "Priority",
+    width: Constraint::Length(10),
+    parse: |job, v| job.priority = v.parse().ok(),
+    display: |job, _| to_string_or_dash(&job.priority),
+    compare: |a, b| a.priority.cmp(&b.priority),
+};
+
+const WORK_DIR_SPEC: ColumnSpec = ColumnSpec {
+    column: JobColumn::WorkDir,
+    code: "%Z",
+    field: "WorkDir",
+    title: "WorkDir",
+    width: Constraint::Percentage(15),
+    parse: |job, v| job.work_dir = Some(v.to_string()),
+    display: |job, _| or_dash(&job.work_dir),
+    compare: |a, b| a.work_dir.cmp(&b.work_dir),
+};
+
+const SUBMIT_TIME_SPEC: ColumnSpec = ColumnSpec {
+    column: JobColumn::SubmitTime,
+    code: "%V",
+    field: "SubmitTime",
+    title: "Submit",
+    width: Constraint::Length(19),
+    parse: |job, v| job.submit_time = v.parse().ok(),
+    display: |job, format| {
+        job.submit_time
+            .map(|t| t.display(format))
+            .unwrap_or_else(|| "-".to_string())
+    },
+    compare: |a, b| a.submit_time.cmp(&b.submit_time),
+};
+
+const START_TIME_SPEC: ColumnSpec = ColumnSpec {
+    column: JobColumn::StartTime,
+    code: "%S",
+    field: "StartTime",
+    title: "Start",
+    width: Constraint::Length(19),
+    parse: |job, v| job.start_time = v.parse().ok(),
+    display: |job, format| {
+        job.start_time
+            .map(|t| t.display(format))
+            .unwrap_or_else(|| "-".to_string())
+    },
+    compare: |a, b| a.start_time.cmp(&b.start_time),
+};
+
+const END_TIME_SPEC: ColumnSpec = ColumnSpec {
+    column: JobColumn::EndTime,
+    code: "%e",
+    field: "EndTime",
+    title: "End",
+    width: Constraint::Length(19),
+    parse: |job, v| job.end_time = v.parse().ok(),
+    display: |job, format| {
+        job.end_time
+            .map(|t| t.display(format))
+            .unwrap_or_else(|| "-".to_string())
+    },
+    compare: |a, b| a.end_time.cmp(&b.end_time),
+};
+
+const PREASON_SPEC: ColumnSpec = ColumnSpec {
+    column: JobColumn::PReason,
+    code: "%R", // Pending reason, or node list once running
+    field: "ReasonList",
+    title: "NodeList(Reason)",
+    width: Constraint::Percentage(20),
+    parse: |job, v| job.pending_reason = Some(v.to_string()),
+    display: |job, _| or_dash(&job.pending_reason),
+    compare: |a, b| a.pending_reason.cmp(&b.pending_reason),
+};
+
+const TIME_LIMIT_SPEC: ColumnSpec = ColumnSpec {
+    column: JobColumn::TimeLimit,
+    code: "%l",
+    field: "TimeLimit",
+    title: "TimeLimit",
+    width: Constraint::Length(12),
+    parse: |job, v| job.time_limit = v.parse().ok(),
+    display: |job, _| to_string_or_dash(&job.time_limit),
+    compare: |a, b| a.time_limit.cmp(&b.time_limit),
+};
+
+const TIME_LEFT_SPEC: ColumnSpec = ColumnSpec {
+    column: JobColumn::TimeLeft,
+    code: "%L",
+    field: "TimeLeft",
+    title: "TimeLeft",
+    width: Constraint::Length(12),
+    parse: |job, v| job.time_left = v.parse().ok(),
+    display: |job, _| to_string_or_dash(&job.time_left),
+    compare: |a, b| a.time_left.cmp(&b.time_left),
+};
+
+const GRES_SPEC: ColumnSpec = ColumnSpec {
+    column: JobColumn::Gres,
+    code: "%b",
+    field: "tres-per-node",
+    title: "GRES",
+    width: Constraint::Length(14),
+    parse: |job, v| job.gres = Some(v.to_string()),
+    display: |job, _| or_dash(&job.gres),
+    compare: |a, b| a.gres.cmp(&b.gres),
+};
+
+const COMMENT_SPEC: ColumnSpec = ColumnSpec {
+    column: JobColumn::Comment,
+    code: "%k",
+    field: "Comment",
+    title: "Comment",
+    width: Constraint::Min(15),
+    parse: |job, v| job.comment = Some(v.to_string()),
+    display: |job, _| or_dash(&job.comment),
+    compare: |a, b| a.comment.cmp(&b.comment),
+};
+
+const DEPENDENCY_SPEC: ColumnSpec = ColumnSpec {
+    column: JobColumn::Dependency,
+    code: "%E",
+    field: "Dependency",
+    title: "Dependency",
+    width: Constraint::Length(20),
+    parse: |job, v| job.dependency = Some(v.to_string()),
+    display: |job, _| or_dash(&job.dependency),
+    compare: |a, b| a.dependency.cmp(&b.dependency),
+};
+
+const REASON_SPEC: ColumnSpec = ColumnSpec {
+    column: JobColumn::Reason,
+    code: "%r",
+    field: "Reason",
+    title: "Reason",
+    width: Constraint::Length(16),
+    parse: |job, v| job.reason = Some(v.to_string()),
+    display: |job, _| or_dash(&job.reason),
+    compare: |a, b| a.reason.cmp(&b.reason),
+};
+
+const COMMAND_SPEC: ColumnSpec = ColumnSpec {
+    column: JobColumn::Command,
+    code: "%o",
+    field: "Command",
+    title: "Command",
+    width: Constraint::Min(20),
+    parse: |job, v| job.command = Some(v.to_string()),
+    display: |job, _| or_dash(&job.command),
+    compare: |a, b| a.command.cmp(&b.command),
+};
+
+const NICE_SPEC: ColumnSpec = ColumnSpec {
+    column: JobColumn::Nice,
+    code: "%y",
+    field: "Nice",
+    title: "Nice",
+    width: Constraint::Length(6),
+    parse: |job, v| job.nice = v.parse().ok(),
+    display: |job, _| to_string_or_dash(&job.nice),
+    compare: |a, b| a.nice.cmp(&b.nice),
+};
+
+const ARRAY_JOB_ID_SPEC: ColumnSpec = ColumnSpec {
+    column: JobColumn::ArrayJobId,
+    code: "%F",
+    field: "ArrayJobID",
+    title: "ArrayJob",
+    width: Constraint::Length(10),
+    parse: |job, v| job.array_job_id = Some(v.to_string()),
+    display: |job, _| or_dash(&job.array_job_id),
+    compare: |a, b| {
+        let key = |id: &Option<String>| id.as_deref().map(job_id_sort_key);
+        key(&a.array_job_id).cmp(&key(&b.array_job_id))
+    },
+};
+
+const ARRAY_TASK_ID_SPEC: ColumnSpec = ColumnSpec {
+    column: JobColumn::ArrayTaskId,
+    code: "%K",
+    field: "ArrayTaskID",
+    title: "ArrayTask",
+    width: Constraint::Length(10),
+    parse: |job, v| job.array_task_id = Some(v.to_string()),
+    display: |job, _| or_dash(&job.array_task_id),
+    compare: |a, b| {
+        // Started tasks are plain numbers, pending ones are ranges like `[3-10%2]`
+        let key = |id: &Option<String>| {
+            id.as_deref()
+                .map(|t| (t.parse::<u64>().unwrap_or(u64::MAX), t.to_string()))
+        };
+        key(&a.array_task_id).cmp(&key(&b.array_task_id))
+    },
+};
+
+impl JobColumn {
+    /// Get the registry entry for a built-in column, or `None` for custom
+    /// columns, which have no static entry
+    pub fn spec(&self) -> Option<&'static ColumnSpec> {
+        let spec = match self {
+            JobColumn::Id => &ID_SPEC,
+            JobColumn::Name => &NAME_SPEC,
+            JobColumn::User => &USER_SPEC,
+            JobColumn::State => &STATE_SPEC,
+            JobColumn::Partition => &PARTITION_SPEC,
+            JobColumn::QoS => &QOS_SPEC,
+            JobColumn::Nodes => &NODES_SPEC,
+            JobColumn::Node => &NODE_SPEC,
+            JobColumn::CPUs => &CPUS_SPEC,
+            JobColumn::Time => &TIME_SPEC,
+            JobColumn::Memory => &MEMORY_SPEC,
+            JobColumn::Account => &ACCOUNT_SPEC,
+            JobColumn::Priority => &PRIORITY_SPEC,
+            JobColumn::WorkDir => &WORK_DIR_SPEC,
+            JobColumn::SubmitTime => &SUBMIT_TIME_SPEC,
+            JobColumn::StartTime => &START_TIME_SPEC,
+            JobColumn::EndTime => &END_TIME_SPEC,
+            JobColumn::PReason => &PREASON_SPEC,
+            JobColumn::TimeLimit => &TIME_LIMIT_SPEC,
+            JobColumn::TimeLeft => &TIME_LEFT_SPEC,
+            JobColumn::Gres => &GRES_SPEC,
+            JobColumn::Comment => &COMMENT_SPEC,
+            JobColumn::Dependency => &DEPENDENCY_SPEC,
+            JobColumn::Reason => &REASON_SPEC,
+            JobColumn::Command => &COMMAND_SPEC,
+            JobColumn::Nice => &NICE_SPEC,
+            JobColumn::ArrayJobId => &ARRAY_JOB_ID_SPEC,
+            JobColumn::ArrayTaskId => &ARRAY_TASK_ID_SPEC,
+            JobColumn::Custom(_) => return None,
+        };
+        Some(spec)
+    }
+
+    /// Get the definition of a custom column
+    fn custom(&self) -> Option<&'static CustomColumn> {
+        match self {
+            JobColumn::Custom(i) => custom_columns().get(*i),
+            _ => None,
+        }
+    }
+
+    /// Find the column fetched by a squeue `%` code or `--Format` field name
+    pub fn from_code(code: &str) -> Option<JobColumn> {
+        if code == "%A" {
+            // Older format strings used %A for the job ID
+            return Some(JobColumn::Id);
+        }
+
+        COLUMNS
+            .iter()
+            .find(|spec| spec.code == code || spec.field.eq_ignore_ascii_case(code))
+            .map(|spec| spec.column)
+            .or_else(|| {
+                custom_columns()
+                    .iter()
+                    .position(|custom| {
+                        custom.code.as_deref() == Some(code)
+                            || custom
+                                .field
+                                .as_deref()
+                                .is_some_and(|field| field.eq_ignore_ascii_case(code))
+                    })
+                    .map(JobColumn::Custom)
+            })
+    }
+
+    /// Look up a column by its header title, as used in the config file
+    pub fn from_title(title: &str) -> Option<JobColumn> {
+        JobColumn::all()
+            .into_iter()
+            .find(|column| column.title().eq_ignore_ascii_case(title))
+    }
+
+    /// Whether fetching this column requires squeue's `--Format` option
+    pub fn needs_long_format(&self) -> bool {
+        self.custom()
+            .is_some_and(|custom| custom.code.is_none() && custom.field.is_some())
+    }
+
+    /// Get the code that fetches this column, either a `%` code or, in
+    /// `--Format` mode, a field name. Custom columns that only have a `%` code
+    /// cannot be fetched in `--Format` mode.
+    pub fn query_code(&self, long_format: bool) -> Option<&'static str> {
+        match (self.spec(), long_format) {
+            (Some(spec), false) => Some(spec.code),
+            (Some(spec), true) => Some(spec.field),
+            (None, false) => self.custom()?.code.as_deref(),
+            (None, true) => self.custom()?.field.as_deref(),
+        }
+    }
+
+    /// Get the title for this column
+    pub fn title(&self) -> &'static str {
+        match self.spec() {
+            Some(spec) => spec.title,
+            None => self.custom().map_or("?", |custom| custom.title.as_str()),
+        }
+    }
+
+    /// Get the default width constraint for this column
+    pub fn default_width(&self) -> Constraint {
+        match self.spec() {
+            Some(spec) => spec.width,
+            None => Constraint::Length(self.custom().map_or(1, |custom| custom.width)),
+        }
+    }
+
+    /// Store a squeue value for this column on the job
+    pub fn parse(&self, job: &mut Job, value: &str) {
+        match self.spec() {
+            Some(spec) => (spec.parse)(job, value),
+            None => {
+                job.extra
+                    .insert(self.title().to_string(), value.to_string());
+            }
+        }
+    }
+
+    /// Render a job's value for this column
+    pub fn display(&self, job: &Job, time_format: TimeFormat) -> String {
+        match self.spec() {
+            Some(spec) => (spec.display)(job, time_format),
+            None => job
+                .extra
+                .get(self.title())
+                .cloned()
+                .unwrap_or_else(|| "-".to_string()),
+        }
+    }
+
+    /// Compare two jobs by the value of this column, using typed comparisons
+    /// for IDs, durations, memory and timestamps instead of plain strings.
+    /// Custom columns compare numerically when both values are numbers.
+    /// Missing values sort first.
+    pub fn compare(&self, a: &Job, b: &Job) -> Ordering {
+        match self.spec() {
+            Some(spec) => (spec.compare)(a, b),
+            None => compare_custom_values(a.extra.get(self.title()), b.extra.get(self.title())),
+        }
+    }
+
+    /// Get all available columns, built-in first, then custom columns
+    pub fn all() -> Vec<JobColumn> {
+        COLUMNS
+            .iter()
+            .map(|spec| spec.column)
+            .chain((0..custom_columns().len()).map(JobColumn::Custom))
+            .collect()
+    }
+
+    /// Default columns to display
+    pub fn defaults() -> Vec<JobColumn> {
+        // These MUST match the defaults in SqueueOptions::default()
+        // "%i|%j|%u|%T|%M|%N|%C|%m|%P|%q".to_string(), // JobID|Name|User|State|Time|Nodes|CPUs|Memory|Partition|QOS
+        vec![
+            JobColumn::Id,
+            JobColumn::Name,
+            JobColumn::User,
+            JobColumn::State,
+            JobColumn::Time,
+            JobColumn::Node,
+            JobColumn::CPUs,
+            JobColumn::Memory,
+            JobColumn::Partition,
+            JobColumn::QoS,
+        ]
+    }
+}
+
+/// Order values of a custom column: numbers by value first, then the other
+/// values as text. A single key keeps the order total, as sorting requires.
+fn compare_custom_values(a: Option<&String>, b: Option<&String>) -> Ordering {
+    let number = |v: Option<&String>| v.and_then(|v| v.parse::<f64>().ok());
+    let (x, y) = (number(a), number(b));
+    x.is_none()
+        .cmp(&y.is_none())
+        .then_with(|| match (x, y) {
+            (Some(x), Some(y)) => x.total_cmp(&y),
+            _ => Ordering::Equal,
+        })
+        .then_with(|| a.cmp(&b))
+}
+
+/// Display an optional string, or `-` when missing
+fn or_dash(value: &Option<String>) -> String {
+    value.clone().unwrap_or_else(|| "-".to_string())
+}
+
+/// Display an optional value, or `-` when missing
+fn to_string_or_dash<T: ToString>(value: &Option<T>) -> String {
+    value
+        .as_ref()
+        .map(|v| v.to_string())
+        .unwrap_or_else(|| "-".to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn orders_custom_values_numbers_first() {
+        let values = ["1a", "10", "", "9", "-", "2.5"].map(String::from);
+        let mut sorted: Vec<Option<&String>> = values.iter().map(Some).collect();
+        sorted.push(None);
+        sorted.sort_by(|a, b| compare_custom_values(*a, *b));
+        let sorted: Vec<&str> = sorted
+            .iter()
+            .map(|v| v.map_or("<none>", |v| v.as_str()))
+            .collect();
+        assert_eq!(sorted, vec!["2.5", "9", "10", "<none>", "", "-", "1a"]);
+    }
+}
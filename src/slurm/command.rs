@@ -101,3 +101,53 @@ pub async fn get_qos() -> Result<Vec<String>> {
         Ok(qos_list)
     }
 }
+
+/// Parse the `Key=Value` pairs of `scontrol show job -o` output
+pub fn parse_scontrol_output(output: &str) -> HashMap<String, String> {
+    let mut result = HashMap::new();
+
+    for part in output.split_whitespace() {
+        if let Some(index) = part.find('=') {
+            let key = &part[0..index];
+            let value = &part[(index + 1)..];
+            result.insert(key.to_string(), value.to_string());
+        }
+    }
+
+    result
+}
+
+/// Fields of a job in `scontrol show job -o` output. Arrays print one line per
+/// task, the requested one is used if listed, otherwise the first line.
+pub fn find_scontrol_job(output: &str, job_id: &str) -> Option<HashMap<String, String>> {
+    output
+        .lines()
+        .map(parse_scontrol_output)
+        .find(|fields| {
+            let task = match (fields.get("ArrayJobId"), fields.get("ArrayTaskId")) {
+                (Some(array), Some(task)) => Some(format!("{}_{}", array, task)),
+                _ => None,
+            };
+            fields.get("JobId").map(String::as_str) == Some(job_id)
+                || task.as_deref() == Some(job_id)
+        })
+        .or_else(|| output.lines().next().map(parse_scontrol_output))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const ARRAY: &str = "JobId=501 ArrayJobId=500 ArrayTaskId=1 JobName=sweep\n\
+                         JobId=502 ArrayJobId=500 ArrayTaskId=2 JobName=sweep\n";
+
+    #[test]
+    fn finds_the_requested_array_task() {
+        let job_id = |id: &str| find_scontrol_job(ARRAY, id).unwrap()["JobId"].clone();
+        assert_eq!(job_id("500_2"), "502");
+        assert_eq!(job_id("502"), "502");
+        // The array job itself is shown as its first task
+        assert_eq!(job_id("500"), "501");
+        assert_eq!(find_scontrol_job("", "500"), None);
+    }
+}
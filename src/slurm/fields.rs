@@ -0,0 +1,299 @@
+use chrono::{Local, NaiveDateTime, TimeZone};
+use serde::{Deserialize, Serialize};
+use std::fmt;
+use std::str::FromStr;
+
+use crate::utils::{format_duration, format_memory, format_relative_time};
+
+/// Format of timestamps as printed by Slurm
+const SLURM_TIME_FORMAT: &str = "%Y-%m-%dT%H:%M:%S";
+
+/// A Slurm duration, such as elapsed time or a time limit
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub enum SlurmDuration {
+    /// A finite duration in seconds
+    Finite(u64),
+    /// No limit (`UNLIMITED`/`INFINITE`), ordered after every finite duration
+    Unlimited,
+}
+
+impl SlurmDuration {
+    /// Get the duration in seconds, if finite
+    pub fn as_secs(&self) -> Option<u64> {
+        match self {
+            SlurmDuration::Finite(secs) => Some(*secs),
+            SlurmDuration::Unlimited => None,
+        }
+    }
+}
+
+impl FromStr for SlurmDuration {
+    type Err = String;
+
+    /// Parse `MM:SS`, `HH:MM:SS`, `D-HH`, `D-HH:MM`, `D-HH:MM:SS`, a bare number
+    /// of minutes, or `UNLIMITED`
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let s = s.trim();
+        if s.eq_ignore_ascii_case("UNLIMITED") || s.eq_ignore_ascii_case("INFINITE") {
+            return Ok(SlurmDuration::Unlimited);
+        }
+
+        let invalid = || format!("Invalid duration: {}", s);
+
+        let (days, rest) = match s.split_once('-') {
+            Some((d, rest)) => (Some(d.parse::<u64>().map_err(|_| invalid())?), rest),
+            None => (None, s),
+        };
+
+        let parts = rest
+            .split(':')
+            .map(|p| p.parse::<u64>())
+            .collect::<Result<Vec<u64>, _>>()
+            .map_err(|_| invalid())?;
+
+        // With a day prefix the fields are HH[:MM[:SS]], otherwise [[HH:]MM:]SS
+        // except for a lone number, which Slurm reads as minutes
+        let (hours, minutes, seconds) = match (days.is_some(), parts.as_slice()) {
+            (true, [h]) => (*h, 0, 0),
+            (true, [h, m]) => (*h, *m, 0),
+            (_, [h, m, sec]) => (*h, *m, *sec),
+            (false, [m]) => (0, *m, 0),
+            (false, [m, sec]) => (0, *m, *sec),
+            _ => return Err(invalid()),
+        };
+
+        Ok(SlurmDuration::Finite(
+            days.unwrap_or(0) * 24 * 3600 + hours * 3600 + minutes * 60 + seconds,
+        ))
+    }
+}
+
+impl fmt::Display for SlurmDuration {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            SlurmDuration::Finite(secs) => write!(f, "{}", format_duration(*secs)),
+            SlurmDuration::Unlimited => write!(f, "UNLIMITED"),
+        }
+    }
+}
+
+/// Whether a memory request applies to each node or to each CPU
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum MemoryScope {
+    PerNode,
+    PerCpu,
+}
+
+/// A memory amount as reported by Slurm
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Memory {
+    /// Amount in megabytes
+    pub mb: u64,
+    pub scope: MemoryScope,
+}
+
+impl Memory {
+    /// Total memory in megabytes for a job with the given CPU and node counts
+    pub fn total_mb(&self, cpus: u32, nodes: u32) -> u64 {
+        match self.scope {
+            MemoryScope::PerNode => self.mb * nodes.max(1) as u64,
+            MemoryScope::PerCpu => self.mb * cpus.max(1) as u64,
+        }
+    }
+}
+
+impl FromStr for Memory {
+    type Err = String;
+
+    /// Parse `4000M`, `4G`, `512K`, `1T` or a bare number of megabytes, optionally
+    /// followed by a per-CPU (`c`, `/cpu`, `per-cpu`) or per-node (`n`, `/node`) suffix
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let invalid = || format!("Invalid memory: {}", s);
+
+        let lower = s.trim().to_ascii_lowercase();
+        let (amount, scope) = if let Some(amount) = lower
+            .strip_suffix("per-cpu")
+            .or_else(|| lower.strip_suffix("/cpu"))
+            .or_else(|| lower.strip_suffix('c'))
+        {
+            (amount.trim_end_matches(['-', ' ']), MemoryScope::PerCpu)
+        } else if let Some(amount) = lower
+            .strip_suffix("per-node")
+            .or_else(|| lower.strip_suffix("/node"))
+            .or_else(|| lower.strip_suffix('n'))
+        {
+            (amount.trim_end_matches(['-', ' ']), MemoryScope::PerNode)
+        } else {
+            (lower.as_str(), MemoryScope::PerNode)
+        };
+
+        let digits_end = amount
+            .find(|c: char| !c.is_ascii_digit() && c != '.')
+            .unwrap_or(amount.len());
+        let value = amount[..digits_end].parse::<f64>().map_err(|_| invalid())?;
+
+        let factor = match &amount[digits_end..] {
+            "" | "m" => 1.0,
+            "k" => 1.0 / 1024.0,
+            "g" => 1024.0,
+            "t" => 1024.0 * 1024.0,
+            _ => return Err(invalid()),
+        };
+
+        Ok(Memory {
+            mb: (value * factor).round() as u64,
+            scope,
+        })
+    }
+}
+
+impl fmt::Display for Memory {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self.scope {
+            MemoryScope::PerNode => write!(f, "{}", format_memory(self.mb)),
+            MemoryScope::PerCpu => write!(f, "{}/cpu", format_memory(self.mb)),
+        }
+    }
+}
+
+/// How timestamps are shown in the jobs list
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum TimeFormat {
+    /// As printed by Slurm, e.g. `2024-05-01T12:00:00`
+    #[default]
+    Absolute,
+    /// Relative to now, e.g. `3h ago` or `in 20m`
+    Relative,
+}
+
+impl TimeFormat {
+    /// Switch between absolute and relative timestamps
+    pub fn toggle(&self) -> Self {
+        match self {
+            TimeFormat::Absolute => TimeFormat::Relative,
+            TimeFormat::Relative => TimeFormat::Absolute,
+        }
+    }
+}
+
+/// A Slurm timestamp, in the cluster's local time
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub struct Timestamp(pub NaiveDateTime);
+
+impl Timestamp {
+    /// Seconds elapsed since this timestamp (negative if it is in the future)
+    pub fn seconds_ago(&self) -> Option<i64> {
+        let local = Local.from_local_datetime(&self.0).earliest()?;
+        Some(Local::now().signed_duration_since(local).num_seconds())
+    }
+
+    /// Format the timestamp according to the display setting
+    pub fn display(&self, format: TimeFormat) -> String {
+        match (format, self.seconds_ago()) {
+            (TimeFormat::Relative, Some(secs)) => format_relative_time(secs),
+            _ => self.to_string(),
+        }
+    }
+}
+
+impl FromStr for Timestamp {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        NaiveDateTime::parse_from_str(s.trim(), SLURM_TIME_FORMAT)
+            .map(Timestamp)
+            .map_err(|_| format!("Invalid timestamp: {}", s))
+    }
+}
+
+impl fmt::Display for Timestamp {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.0.format(SLURM_TIME_FORMAT))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn secs(s: &str) -> Option<u64> {
+        s.parse::<SlurmDuration>().ok()?.as_secs()
+    }
+
+    #[test]
+    fn parses_every_duration_form() {
+        assert_eq!(secs("05:30"), Some(5 * 60 + 30));
+        assert_eq!(secs("1:02:03"), Some(3600 + 2 * 60 + 3));
+        assert_eq!(secs("2-03"), Some(2 * 86400 + 3 * 3600));
+        assert_eq!(secs("2-03:04"), Some(2 * 86400 + 3 * 3600 + 4 * 60));
+        assert_eq!(secs("2-03:04:05"), Some(2 * 86400 + 3 * 3600 + 4 * 60 + 5));
+        // A bare number is minutes
+        assert_eq!(secs("90"), Some(90 * 60));
+        assert_eq!(secs(" 0:00 "), Some(0));
+    }
+
+    #[test]
+    fn parses_unlimited_and_rejects_invalid_durations() {
+        assert_eq!("UNLIMITED".parse(), Ok(SlurmDuration::Unlimited));
+        assert_eq!("infinite".parse(), Ok(SlurmDuration::Unlimited));
+        for invalid in [
+            "INVALID",
+            "",
+            "1:2:3:4",
+            "x-01",
+            "1-2-3",
+            "-5",
+            "1-01:02:03:04",
+        ] {
+            assert!(invalid.parse::<SlurmDuration>().is_err(), "{}", invalid);
+        }
+        assert!(SlurmDuration::Finite(u64::MAX) < SlurmDuration::Unlimited);
+    }
+
+    fn memory(s: &str) -> Option<(u64, MemoryScope)> {
+        s.parse::<Memory>().ok().map(|m| (m.mb, m.scope))
+    }
+
+    #[test]
+    fn parses_memory_units_and_scopes() {
+        use MemoryScope::*;
+        assert_eq!(memory("4000M"), Some((4000, PerNode)));
+        assert_eq!(memory("4000"), Some((4000, PerNode)));
+        assert_eq!(memory("4G"), Some((4096, PerNode)));
+        assert_eq!(memory("1.5g"), Some((1536, PerNode)));
+        assert_eq!(memory("2048K"), Some((2, PerNode)));
+        assert_eq!(memory("1T"), Some((1024 * 1024, PerNode)));
+        assert_eq!(memory("4000Mc"), Some((4000, PerCpu)));
+        assert_eq!(memory("2Gn"), Some((2048, PerNode)));
+        assert_eq!(memory("1G/cpu"), Some((1024, PerCpu)));
+        assert_eq!(memory("1G per-cpu"), Some((1024, PerCpu)));
+        assert_eq!(memory("8G/node"), Some((8192, PerNode)));
+        for invalid in ["", "G", "4X", "c", "N/A"] {
+            assert_eq!(memory(invalid), None, "{}", invalid);
+        }
+    }
+
+    #[test]
+    fn totals_memory_by_scope() {
+        let per_cpu: Memory = "1G/cpu".parse().unwrap();
+        assert_eq!(per_cpu.total_mb(4, 2), 4096);
+        assert_eq!(per_cpu.to_string(), format!("{}/cpu", format_memory(1024)));
+        let per_node: Memory = "1G".parse().unwrap();
+        assert_eq!(per_node.total_mb(4, 2), 2048);
+        assert_eq!(per_node.total_mb(0, 0), 1024);
+    }
+
+    #[test]
+    fn parses_and_formats_timestamps() {
+        let time: Timestamp = " 2024-05-01T12:34:56 ".parse().unwrap();
+        assert_eq!(time.to_string(), "2024-05-01T12:34:56");
+        assert_eq!(time.display(TimeFormat::Absolute), "2024-05-01T12:34:56");
+        assert!(time.seconds_ago().is_some_and(|secs| secs > 0));
+        for invalid in ["Unknown", "N/A", "2024-05-01", ""] {
+            assert!(invalid.parse::<Timestamp>().is_err(), "{}", invalid);
+        }
+        assert_eq!(TimeFormat::Absolute.toggle(), TimeFormat::Relative);
+        assert_eq!(TimeFormat::Relative.toggle(), TimeFormat::Absolute);
+    }
+}
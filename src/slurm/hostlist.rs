@@ -0,0 +1,86 @@
+/// Expand a Slurm host list such as `node[01-03,07],gpu05` into host names.
+/// Bracketed ranges keep their zero-padding.
+pub fn expand_hostlist(nodes: &str) -> Vec<String> {
+    split_top_level(nodes)
+        .into_iter()
+        .filter(|host| !host.is_empty())
+        .flat_map(expand_host)
+        .collect()
+}
+
+/// Split a host list at the commas outside of brackets
+fn split_top_level(nodes: &str) -> Vec<&str> {
+    let mut parts = Vec::new();
+    let mut depth = 0;
+    let mut start = 0;
+    for (i, c) in nodes.char_indices() {
+        match c {
+            '[' => depth += 1,
+            ']' => depth -= 1,
+            ',' if depth == 0 => {
+                parts.push(&nodes[start..i]);
+                start = i + 1;
+            }
+            _ => {}
+        }
+    }
+    parts.push(&nodes[start..]);
+    parts
+}
+
+/// Expand the first bracketed range of a host, then the rest of it
+fn expand_host(host: &str) -> Vec<String> {
+    let Some((prefix, rest)) = host.split_once('[') else {
+        return vec![host.to_string()];
+    };
+    let Some((ranges, suffix)) = rest.split_once(']') else {
+        return vec![host.to_string()];
+    };
+
+    let mut hosts = Vec::new();
+    for range in ranges.split(',') {
+        let (first, last) = range.split_once('-').unwrap_or((range, range));
+        let (Ok(start), Ok(end)) = (first.parse::<u64>(), last.parse::<u64>()) else {
+            continue;
+        };
+        for index in start..=end {
+            let name = format!("{}{:0>width$}", prefix, index, width = first.len());
+            hosts.extend(
+                expand_host(suffix)
+                    .into_iter()
+                    .map(|tail| format!("{}{}", name, tail)),
+            );
+        }
+    }
+    hosts
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn expands_ranges_keeping_zero_padding() {
+        assert_eq!(
+            expand_hostlist("node[01-03,07],gpu05"),
+            vec!["node01", "node02", "node03", "node07", "gpu05"]
+        );
+        assert_eq!(expand_hostlist("n[8-10]"), vec!["n8", "n9", "n10"]);
+    }
+
+    #[test]
+    fn expands_every_bracketed_range() {
+        assert_eq!(
+            expand_hostlist("rack[1-2]-n[1-2]"),
+            vec!["rack1-n1", "rack1-n2", "rack2-n1", "rack2-n2"]
+        );
+    }
+
+    #[test]
+    fn skips_empty_and_invalid_parts() {
+        assert_eq!(expand_hostlist("a,,b"), vec!["a", "b"]);
+        assert_eq!(expand_hostlist(""), Vec::<String>::new());
+        assert_eq!(expand_hostlist("node[a-b]"), Vec::<String>::new());
+        assert_eq!(expand_hostlist("node[1-2"), vec!["node[1-2"]);
+    }
+}
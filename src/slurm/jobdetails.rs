@@ -0,0 +1,80 @@
+use std::process::Command;
+
+use super::batchscript::{fetch_batch_script, ScriptSource};
+use super::command::find_scontrol_job;
+
+/// Fields of a job as `Key=Value` pairs, sorted by key: from `scontrol show job`
+/// while the controller knows the job, otherwise from `sacct --long`
+pub fn job_fields(job_id: &str) -> Option<Vec<(String, String)>> {
+    from_scontrol(job_id).or_else(|| from_sacct(job_id))
+}
+
+/// The batch script of a job, with the source it was read from
+pub fn job_script(job_id: &str) -> Option<(String, ScriptSource)> {
+    let script_path = from_scontrol(job_id).and_then(|fields| {
+        fields
+            .into_iter()
+            .find(|(key, _)| key == "Command")
+            .map(|(_, path)| path)
+    });
+    fetch_batch_script(job_id, script_path.as_deref())
+}
+
+/// Environment a job was submitted with, one `NAME=value` per line, as stored
+/// by the accounting database (Slurm ≥ 23.02 with `AccountingStoreFlags=job_env`)
+pub fn submission_environment(job_id: &str) -> Option<String> {
+    let output = Command::new("sacct")
+        .args(["--env-vars", "-j", job_id])
+        .output()
+        .ok()?;
+    if !output.status.success() {
+        return None;
+    }
+
+    // Skip the "Batch Environment for <id>" header and its separator
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    let environment = stdout
+        .lines()
+        .skip_while(|line| !line.contains('='))
+        .collect::<Vec<_>>()
+        .join("\n");
+
+    (!environment.trim().is_empty()).then_some(environment)
+}
+
+fn from_scontrol(job_id: &str) -> Option<Vec<(String, String)>> {
+    let output = Command::new("scontrol")
+        .args(["show", "job", job_id, "-o"])
+        .output()
+        .ok()?;
+    if !output.status.success() {
+        return None;
+    }
+
+    let fields = find_scontrol_job(&String::from_utf8_lossy(&output.stdout), job_id)?;
+    let mut fields: Vec<(String, String)> = fields.into_iter().collect();
+    fields.sort();
+    Some(fields)
+}
+
+fn from_sacct(job_id: &str) -> Option<Vec<(String, String)>> {
+    let output = Command::new("sacct")
+        .args(["-j", job_id, "-X", "-P", "--long"])
+        .output()
+        .ok()?;
+    if !output.status.success() {
+        return None;
+    }
+
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    let mut lines = stdout.lines();
+    let header = lines.next()?;
+    let values = lines.next()?;
+    let mut fields: Vec<(String, String)> = header
+        .split('|')
+        .zip(values.split('|'))
+        .map(|(key, value)| (key.to_string(), value.to_string()))
+        .collect();
+    fields.sort();
+    Some(fields)
+}
@@ -0,0 +1,335 @@
+use std::{path::Path, process::Command};
+
+use super::batchscript::fetch_batch_script;
+use super::command::find_scontrol_job;
+use super::hostlist::expand_hostlist;
+use super::sbatch::parse_directives;
+
+/// Array task ID Slurm substitutes for `%a` in jobs that are not arrays
+const NO_ARRAY_TASK: &str = "4294967294";
+
+/// Resolved stdout and stderr files of a job
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct LogPaths {
+    pub stdout: Option<String>,
+    pub stderr: Option<String>,
+}
+
+/// What is needed to work out where a job writes its output
+#[derive(Debug, Clone, Default)]
+struct JobLogInfo {
+    job_id: String,
+    array_job_id: Option<String>,
+    array_task_id: Option<String>,
+    name: String,
+    user: String,
+    /// Node list of the job, the first node runs the batch script
+    nodes: Option<String>,
+    work_dir: Option<String>,
+    /// Path of the batch script, while the job is known to the controller
+    script_path: Option<String>,
+    stdout_pattern: Option<String>,
+    stderr_pattern: Option<String>,
+}
+
+/// Work out the log files of a job, whether it is pending, running or finished.
+///
+/// Output patterns come from `scontrol` while the controller knows the job,
+/// otherwise from `sacct`, otherwise from the `#SBATCH --output/--error` lines of
+/// the batch script. Without any, Slurm's default `slurm-%j.out` (or
+/// `slurm-%A_%a.out` for arrays) in the working directory is used, and stderr
+/// goes to the same file as stdout.
+pub fn resolve_log_paths(job_id: &str) -> LogPaths {
+    let Some(mut info) = from_scontrol(job_id).or_else(|| from_sacct(job_id)) else {
+        return LogPaths::default();
+    };
+
+    if info.stdout_pattern.is_none() || info.stderr_pattern.is_none() {
+        if let Some(script) = batch_script(&info) {
+            let (output, error) = sbatch_output_options(&script);
+            info.stdout_pattern = info.stdout_pattern.or(output);
+            info.stderr_pattern = info.stderr_pattern.or(error);
+        }
+    }
+
+    let stdout_pattern = info.stdout_pattern.clone().unwrap_or_else(|| {
+        if info.array_task_id.is_some() {
+            "slurm-%A_%a.out".to_string()
+        } else {
+            "slurm-%j.out".to_string()
+        }
+    });
+    let stderr_pattern = info
+        .stderr_pattern
+        .clone()
+        .unwrap_or_else(|| stdout_pattern.clone());
+
+    LogPaths {
+        stdout: Some(expand_pattern(&stdout_pattern, &info)),
+        stderr: Some(expand_pattern(&stderr_pattern, &info)),
+    }
+}
+
+/// Job details from the controller, available until the job is purged
+fn from_scontrol(job_id: &str) -> Option<JobLogInfo> {
+    let output = Command::new("scontrol")
+        .args(["show", "job", job_id, "-o"])
+        .output()
+        .ok()?;
+    if !output.status.success() {
+        return None;
+    }
+
+    let fields = find_scontrol_job(&String::from_utf8_lossy(&output.stdout), job_id)?;
+
+    let get = |key: &str| {
+        fields
+            .get(key)
+            .filter(|v| !v.is_empty() && *v != "(null)")
+            .cloned()
+    };
+
+    Some(JobLogInfo {
+        job_id: get("JobId").unwrap_or_else(|| job_id.to_string()),
+        array_job_id: get("ArrayJobId"),
+        array_task_id: get("ArrayTaskId"),
+        name: get("JobName").unwrap_or_default(),
+        // UserId=alice(1000)
+        user: get("UserId")
+            .map(|user| user.split('(').next().unwrap_or_default().to_string())
+            .unwrap_or_default(),
+        nodes: get("BatchHost").or_else(|| get("NodeList")),
+        work_dir: get("WorkDir"),
+        script_path: get("Command"),
+        stdout_pattern: get("StdOut"),
+        stderr_pattern: get("StdErr"),
+    })
+}
+
+/// Job details from the accounting database, for jobs that have left the
+/// controller. Older Slurm versions have no StdOut/StdErr fields.
+fn from_sacct(job_id: &str) -> Option<JobLogInfo> {
+    let with_patterns = "JobID,JobIDRaw,JobName,User,WorkDir,NodeList,StdOut,StdErr";
+    let without_patterns = "JobID,JobIDRaw,JobName,User,WorkDir,NodeList";
+
+    let output = [with_patterns, without_patterns]
+        .iter()
+        .find_map(|format| {
+            let output = Command::new("sacct")
+                .args(["-j", job_id, "-X", "-n", "-P", "-o", format])
+                .output()
+                .ok()?;
+            output.status.success().then_some(output)
+        })?;
+
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    let rows: Vec<Vec<&str>> = stdout
+        .lines()
+        .map(|line| line.split('|').collect())
+        .collect();
+    let row = rows
+        .iter()
+        .find(|row| row.first() == Some(&job_id) || row.get(1) == Some(&job_id))
+        .or_else(|| rows.first())?;
+
+    let get = |index: usize| {
+        row.get(index)
+            .map(|v| v.trim())
+            .filter(|v| !v.is_empty() && *v != "None assigned" && *v != "(null)")
+            .map(str::to_string)
+    };
+
+    // Array tasks are listed as <array job>_<task>
+    let display_id = get(0).unwrap_or_else(|| job_id.to_string());
+    let (array_job_id, array_task_id) = match display_id.split_once('_') {
+        Some((array, task)) if task.chars().all(|c| c.is_ascii_digit()) => {
+            (Some(array.to_string()), Some(task.to_string()))
+        }
+        _ => (None, None),
+    };
+
+    Some(JobLogInfo {
+        job_id: get(1).unwrap_or(display_id),
+        array_job_id,
+        array_task_id,
+        name: get(2).unwrap_or_default(),
+        user: get(3).unwrap_or_default(),
+        work_dir: get(4),
+        nodes: get(5),
+        script_path: None,
+        stdout_pattern: get(6),
+        stderr_pattern: get(7),
+    })
+}
+
+/// Get the batch script kept by Slurm, or from disk
+fn batch_script(info: &JobLogInfo) -> Option<String> {
+    fetch_batch_script(&info.job_id, info.script_path.as_deref()).map(|(script, _)| script)
+}
+
+/// Find the `--output` and `--error` options in the `#SBATCH` lines of a
+/// script, the last one winning
+fn sbatch_output_options(script: &str) -> (Option<String>, Option<String>) {
+    let directives = parse_directives(script);
+    let last = |option: &str| {
+        directives
+            .iter()
+            .rev()
+            .find(|directive| directive.option == option)
+            .and_then(|directive| directive.value.clone())
+    };
+    (last("output"), last("error"))
+}
+
+/// Expand a Slurm filename pattern (`%j`, `%A`, `%a`, `%x`, `%u`, `%N`, `%s`,
+/// `%n`, `%t`, `%%`, with optional zero-padding of numbers such as `%4a`) and
+/// make it absolute relative to the working directory
+fn expand_pattern(pattern: &str, info: &JobLogInfo) -> String {
+    // A backslash anywhere disables substitution
+    let expanded = if pattern.contains('\\') {
+        pattern.replace('\\', "")
+    } else {
+        let mut expanded = String::with_capacity(pattern.len());
+        let mut chars = pattern.chars().peekable();
+        while let Some(c) = chars.next() {
+            if c != '%' {
+                expanded.push(c);
+                continue;
+            }
+
+            let mut width = String::new();
+            while let Some(digit) = chars.next_if(char::is_ascii_digit) {
+                width.push(digit);
+            }
+            let width: usize = width.parse().unwrap_or(0);
+
+            let spec = chars.next();
+            let value = match spec {
+                Some('%') => Some("%".to_string()),
+                Some('j') | Some('J') => Some(info.job_id.clone()),
+                Some('A') => Some(info.array_job_id.clone().unwrap_or(info.job_id.clone())),
+                Some('a') => Some(
+                    info.array_task_id
+                        .clone()
+                        .unwrap_or(NO_ARRAY_TASK.to_string()),
+                ),
+                Some('x') => Some(info.name.clone()),
+                Some('u') => Some(info.user.clone()),
+                Some('N') => info.nodes.as_deref().map(first_host),
+                Some('s') => Some("batch".to_string()),
+                Some('n') | Some('t') => Some("0".to_string()),
+                _ => None,
+            };
+
+            match value {
+                // Empty values, such as an unknown user, stay empty
+                Some(value) if !value.is_empty() && value.chars().all(|c| c.is_ascii_digit()) => {
+                    expanded.push_str(&format!("{:0>width$}", value, width = width));
+                }
+                Some(value) => expanded.push_str(&value),
+                // Leave unknown or unavailable patterns as they are
+                None => {
+                    expanded.push('%');
+                    if width > 0 {
+                        expanded.push_str(&width.to_string());
+                    }
+                    expanded.extend(spec);
+                }
+            }
+        }
+        expanded
+    };
+
+    match &info.work_dir {
+        Some(work_dir) if Path::new(&expanded).is_relative() => Path::new(work_dir)
+            .join(expanded)
+            .to_string_lossy()
+            .to_string(),
+        _ => expanded,
+    }
+}
+
+/// First host of a Slurm host list, e.g. `node01` for `node[01-03],gpu05`
+fn first_host(nodes: &str) -> String {
+    expand_hostlist(nodes)
+        .into_iter()
+        .next()
+        .unwrap_or_default()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn info() -> JobLogInfo {
+        JobLogInfo {
+            job_id: "1234".to_string(),
+            name: "train".to_string(),
+            user: "alice".to_string(),
+            nodes: Some("node[07-09]".to_string()),
+            work_dir: Some("/home/alice/run".to_string()),
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn expands_every_pattern() {
+        let info = info();
+        assert_eq!(
+            expand_pattern("%x-%j-%u-%N-%s-%n-%t-100%%.out", &info),
+            "/home/alice/run/train-1234-alice-node07-batch-0-0-100%.out"
+        );
+        assert_eq!(
+            expand_pattern("/logs/%A_%a.out", &info),
+            "/logs/1234_4294967294.out"
+        );
+
+        let task = JobLogInfo {
+            job_id: "1240".to_string(),
+            array_job_id: Some("1234".to_string()),
+            array_task_id: Some("7".to_string()),
+            ..info
+        };
+        assert_eq!(
+            expand_pattern("/logs/%A_%a-%j.out", &task),
+            "/logs/1234_7-1240.out"
+        );
+    }
+
+    #[test]
+    fn pads_numbers_only() {
+        let info = JobLogInfo {
+            array_task_id: Some("7".to_string()),
+            ..info()
+        };
+        assert_eq!(
+            expand_pattern("/l/%8j-%3a-%10x.out", &info),
+            "/l/00001234-007-train.out"
+        );
+
+        // Empty values are not padded to zeros
+        let unknown = JobLogInfo {
+            user: String::new(),
+            ..info
+        };
+        assert_eq!(expand_pattern("/l/%4u.out", &unknown), "/l/.out");
+    }
+
+    #[test]
+    fn keeps_unknown_or_unavailable_patterns() {
+        let info = JobLogInfo {
+            nodes: None,
+            ..info()
+        };
+        assert_eq!(expand_pattern("/l/%N-%3q-%", &info), "/l/%N-%3q-%");
+    }
+
+    #[test]
+    fn a_backslash_disables_substitution() {
+        assert_eq!(expand_pattern("/l/\\%j-%x.out", &info()), "/l/%j-%x.out");
+        assert_eq!(
+            expand_pattern("out\\_%j", &info()),
+            "/home/alice/run/out_%j"
+        );
+    }
+}
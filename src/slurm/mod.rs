@@ -1,17 +1,32 @@
+pub mod batchscript;
+pub mod columns;
 pub mod command;
+pub mod fields;
+pub mod hostlist;
+pub mod jobdetails;
+pub mod logpaths;
+pub mod sbatch;
 pub mod squeue;
+pub mod submit;
+pub mod transitions;
+pub mod wait;
 
+use std::collections::HashMap;
 use std::fmt;
 use std::str::FromStr;
 
+use fields::{Memory, SlurmDuration, Timestamp};
+
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
 pub enum JobState {
     Pending,
     Running,
+    Completing,
     Completed,
     Failed,
     Cancelled,
     Timeout,
+    OutOfMemory,
     NodeFail,
     Preempted,
     Boot,
@@ -23,10 +38,12 @@ impl JobState {
         vec![
             JobState::Pending,
             JobState::Running,
+            JobState::Completing,
             JobState::Completed,
             JobState::Failed,
             JobState::Cancelled,
             JobState::Timeout,
+            JobState::OutOfMemory,
             JobState::NodeFail,
             JobState::Preempted,
             JobState::Boot,
@@ -39,10 +56,12 @@ impl fmt::Display for JobState {
         let state_str = match self {
             JobState::Pending => "PENDING",
             JobState::Running => "RUNNING",
+            JobState::Completing => "COMPLETING",
             JobState::Completed => "COMPLETED",
             JobState::Failed => "FAILED",
             JobState::Cancelled => "CANCELLED",
             JobState::Timeout => "TIMEOUT",
+            JobState::OutOfMemory => "OUT_OF_MEMORY",
             JobState::NodeFail => "NODE_FAIL",
             JobState::Preempted => "PREEMPTED",
             JobState::Boot => "BOOT_FAIL",
@@ -59,10 +78,12 @@ impl FromStr for JobState {
         match s.to_uppercase().as_str() {
             "PENDING" | "PD" => Ok(JobState::Pending),
             "RUNNING" | "R" => Ok(JobState::Running),
-            "COMPLETED" | "CD" | "COMPLETING" | "CG" => Ok(JobState::Completed),
+            "COMPLETING" | "CG" => Ok(JobState::Completing),
+            "COMPLETED" | "CD" => Ok(JobState::Completed),
             "FAILED" | "F" => Ok(JobState::Failed),
             "CANCELLED" | "CA" => Ok(JobState::Cancelled),
             "TIMEOUT" | "TO" => Ok(JobState::Timeout),
+            "OUT_OF_MEMORY" | "OOM" => Ok(JobState::OutOfMemory),
             "NODE_FAIL" | "NF" => Ok(JobState::NodeFail),
             "PREEMPTED" | "PR" => Ok(JobState::Preempted),
             "BOOT_FAIL" | "BF" => Ok(JobState::Boot),
@@ -77,20 +98,32 @@ pub struct Job {
     pub name: String,
     pub user: String,
     pub state: JobState,
-    pub time: String,
+    pub time: Option<SlurmDuration>,
     pub nodes: u32,
     pub node: Option<String>,
     pub cpus: u32,
-    pub memory: String,
+    pub memory: Option<Memory>,
     pub partition: String,
     pub qos: String,
     pub account: Option<String>,
     pub priority: Option<u32>,
     pub work_dir: Option<String>,
-    pub submit_time: Option<String>,
-    pub start_time: Option<String>,
-    pub end_time: Option<String>,
+    pub submit_time: Option<Timestamp>,
+    pub start_time: Option<Timestamp>,
+    pub end_time: Option<Timestamp>,
     pub pending_reason: Option<String>,
+    pub time_limit: Option<SlurmDuration>,
+    pub time_left: Option<SlurmDuration>,
+    pub gres: Option<String>,
+    pub comment: Option<String>,
+    pub dependency: Option<String>,
+    pub reason: Option<String>,
+    pub command: Option<String>,
+    pub nice: Option<i32>,
+    pub array_job_id: Option<String>,
+    pub array_task_id: Option<String>,
+    /// Values of user-defined columns, keyed by column title
+    pub extra: HashMap<String, String>,
 }
 
 impl Default for Job {
@@ -100,11 +133,11 @@ impl Default for Job {
             name: String::new(),
             user: String::new(),
             state: JobState::Other,
-            time: String::new(),
+            time: None,
             nodes: 0,
             node: None,
             cpus: 0,
-            memory: String::new(),
+            memory: None,
             partition: String::new(),
             qos: String::new(),
             account: None,
@@ -114,6 +147,17 @@ impl Default for Job {
             start_time: None,
             end_time: None,
             pending_reason: None,
+            time_limit: None,
+            time_left: None,
+            gres: None,
+            comment: None,
+            dependency: None,
+            reason: None,
+            command: None,
+            nice: None,
+            array_job_id: None,
+            array_task_id: None,
+            extra: HashMap::new(),
         }
     }
 }
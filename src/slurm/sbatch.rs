@@ -0,0 +1,482 @@
+use std::collections::HashMap;
+
+use super::fields::{Memory, MemoryScope, SlurmDuration};
+
+/// Long names of the sbatch options that have a single-letter form
+const SHORT_OPTIONS: &[(char, &str)] = &[
+    ('A', "account"),
+    ('a', "array"),
+    ('B', "extra-node-info"),
+    ('b', "begin"),
+    ('C', "constraint"),
+    ('c', "cpus-per-task"),
+    ('D', "chdir"),
+    ('d', "dependency"),
+    ('e', "error"),
+    ('F', "nodefile"),
+    ('G', "gpus"),
+    ('H', "hold"),
+    ('i', "input"),
+    ('J', "job-name"),
+    ('k', "no-kill"),
+    ('L', "licenses"),
+    ('M', "clusters"),
+    ('m', "distribution"),
+    ('N', "nodes"),
+    ('n', "ntasks"),
+    ('O', "overcommit"),
+    ('o', "output"),
+    ('p', "partition"),
+    ('Q', "quiet"),
+    ('q', "qos"),
+    ('s', "oversubscribe"),
+    ('t', "time"),
+    ('v', "verbose"),
+    ('W', "wait"),
+    ('w', "nodelist"),
+    ('x', "exclude"),
+];
+
+/// Options that take no value unless it is given with `=`
+const FLAGS: &[&str] = &[
+    "contiguous",
+    "exclusive",
+    "get-user-env",
+    "hold",
+    "ignore-pbs",
+    "no-kill",
+    "no-requeue",
+    "overcommit",
+    "oversubscribe",
+    "parsable",
+    "quiet",
+    "requeue",
+    "spread-job",
+    "test-only",
+    "use-min-nodes",
+    "verbose",
+    "wait",
+];
+
+/// An option set in an `#SBATCH` line
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Directive {
+    /// Long option name, without the leading dashes
+    pub option: String,
+    /// Value of the option, missing for flags
+    pub value: Option<String>,
+}
+
+/// Parse the `#SBATCH` lines of a batch script. Like sbatch, parsing stops at
+/// the first command; short options are reported by their long name.
+pub fn parse_directives(script: &str) -> Vec<Directive> {
+    let mut directives = Vec::new();
+
+    for line in script.lines().map(str::trim) {
+        if line.is_empty() || line.starts_with("#!") {
+            continue;
+        }
+        if !line.starts_with('#') {
+            break;
+        }
+        let Some(options) = line.strip_prefix("#SBATCH") else {
+            continue;
+        };
+        if !options.is_empty() && !options.starts_with(char::is_whitespace) {
+            continue;
+        }
+
+        let mut words = split_words(options).into_iter();
+        while let Some(word) = words.next() {
+            let (option, value) = if let Some(long) = word.strip_prefix("--") {
+                match long.split_once('=') {
+                    Some((option, value)) => (option.to_string(), Some(value.to_string())),
+                    None if FLAGS.contains(&long) => (long.to_string(), None),
+                    None => (long.to_string(), words.next()),
+                }
+            } else if let Some(short) = word.strip_prefix('-') {
+                let mut chars = short.chars();
+                let Some(&(_, option)) = chars
+                    .next()
+                    .and_then(|letter| SHORT_OPTIONS.iter().find(|(c, _)| *c == letter))
+                else {
+                    continue;
+                };
+                // The value may be attached, as in `-N2` or `-o=out.log`
+                let attached = chars.as_str();
+                let value = if FLAGS.contains(&option) {
+                    None
+                } else if attached.is_empty() {
+                    words.next()
+                } else {
+                    Some(attached.strip_prefix('=').unwrap_or(attached).to_string())
+                };
+                (option.to_string(), value)
+            } else {
+                continue;
+            };
+            directives.push(Directive { option, value });
+        }
+    }
+
+    directives
+}
+
+/// Split the options of a directive into words, removing quotes and stopping
+/// at a trailing comment
+fn split_words(options: &str) -> Vec<String> {
+    let mut words = Vec::new();
+    let mut word = String::new();
+    let mut in_word = false;
+    let mut quote = None;
+
+    for c in options.chars() {
+        match (quote, c) {
+            (Some(q), c) if c == q => quote = None,
+            (Some(_), c) => word.push(c),
+            (None, '"' | '\'') => {
+                quote = Some(c);
+                in_word = true;
+            }
+            (None, c) if c.is_whitespace() => {
+                if in_word {
+                    words.push(std::mem::take(&mut word));
+                    in_word = false;
+                }
+            }
+            (None, '#') if !in_word => break,
+            (None, c) => {
+                word.push(c);
+                in_word = true;
+            }
+        }
+    }
+    if in_word {
+        words.push(word);
+    }
+
+    words
+}
+
+/// Group of related options in the header summary
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub enum Section {
+    Resources,
+    Output,
+    Array,
+    Dependencies,
+    Mail,
+    Other,
+}
+
+impl Section {
+    pub fn title(&self) -> &'static str {
+        match self {
+            Section::Resources => "Resources",
+            Section::Output => "Output",
+            Section::Array => "Array",
+            Section::Dependencies => "Dependencies",
+            Section::Mail => "Mail",
+            Section::Other => "Other",
+        }
+    }
+
+    fn of(option: &str) -> Self {
+        match option {
+            "nodes" | "ntasks" | "ntasks-per-node" | "cpus-per-task" | "mem" | "mem-per-cpu"
+            | "mem-per-gpu" | "time" | "time-min" | "partition" | "qos" | "account" | "gres"
+            | "gpus" | "gpus-per-node" | "gpus-per-task" | "constraint" | "exclusive"
+            | "nodelist" | "exclude" | "reservation" | "licenses" => Section::Resources,
+            "output" | "error" | "input" | "chdir" | "open-mode" => Section::Output,
+            "array" => Section::Array,
+            "dependency" | "kill-on-invalid-dep" | "begin" => Section::Dependencies,
+            "mail-type" | "mail-user" => Section::Mail,
+            _ => Section::Other,
+        }
+    }
+}
+
+/// A requested option next to what Slurm made of it
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct HeaderRow {
+    pub section: Section,
+    pub option: String,
+    /// Value in the script, empty for flags
+    pub requested: String,
+    /// Value from `scontrol show job`, if Slurm reports one for the option
+    pub allocated: Option<String>,
+    /// Whether the allocated value differs from the requested one
+    pub mismatch: bool,
+}
+
+/// Summarize the directives of a script by section, next to the job details
+/// printed by `scontrol show job`. Options set more than once keep their last
+/// value, as with sbatch.
+pub fn summarize(directives: &[Directive], job: &HashMap<String, String>) -> Vec<HeaderRow> {
+    let mut rows: Vec<HeaderRow> = Vec::new();
+
+    for directive in directives {
+        let requested = directive.value.clone().unwrap_or_default();
+        let (allocated, mismatch) = compare(&directive.option, &requested, job);
+        let row = HeaderRow {
+            section: Section::of(&directive.option),
+            option: directive.option.clone(),
+            requested,
+            allocated,
+            mismatch,
+        };
+        match rows.iter_mut().find(|r| r.option == directive.option) {
+            Some(existing) => *existing = row,
+            None => rows.push(row),
+        }
+    }
+
+    // Keep the script order within each section
+    rows.sort_by_key(|row| row.section);
+    rows
+}
+
+/// Value Slurm reports for an option and whether it differs from the request
+fn compare(option: &str, requested: &str, job: &HashMap<String, String>) -> (Option<String>, bool) {
+    let get = |key: &str| {
+        job.get(key)
+            .filter(|v| !v.is_empty() && *v != "(null)")
+            .cloned()
+    };
+
+    match option {
+        "nodes" => compare_count(requested, get("NumNodes")),
+        "ntasks" => compare_count(requested, get("NumTasks")),
+        "cpus-per-task" => compare_count(requested, get("CPUs/Task")),
+        // NtasksPerN:B:S:C=2:0:*:*
+        "ntasks-per-node" => compare_count(
+            requested,
+            get("NtasksPerN:B:S:C").and_then(|v| v.split(':').next().map(str::to_string)),
+        ),
+        "mem" => compare_memory(requested, MemoryScope::PerNode, job),
+        "mem-per-cpu" => compare_memory(requested, MemoryScope::PerCpu, job),
+        "time" => compare_duration(requested, get("TimeLimit")),
+        "time-min" => compare_duration(requested, get("TimeMin")),
+        "partition" => {
+            // A list of partitions lets Slurm pick any of them
+            let allocated = get("Partition");
+            let mismatch = allocated.as_ref().is_some_and(|partition| {
+                !requested
+                    .split(',')
+                    .any(|p| partition.split(',').any(|a| a.eq_ignore_ascii_case(p)))
+            });
+            (allocated, mismatch)
+        }
+        "qos" => compare_ignore_case(requested, get("QOS")),
+        "account" => compare_ignore_case(requested, get("Account")),
+        "reservation" => compare_ignore_case(requested, get("Reservation")),
+        "job-name" => {
+            let allocated = get("JobName");
+            let mismatch = allocated.as_ref().is_some_and(|name| name != requested);
+            (allocated, mismatch)
+        }
+        "mail-user" => {
+            let allocated = get("MailUser");
+            let mismatch = allocated.as_ref().is_some_and(|user| user != requested);
+            (allocated, mismatch)
+        }
+        "mail-type" => {
+            let allocated = get("MailType");
+            let types = |value: &str| {
+                let mut types: Vec<String> = value
+                    .split(',')
+                    .map(|t| t.trim().to_ascii_uppercase())
+                    .collect();
+                types.sort();
+                types
+            };
+            // ALL is reported as the list of types it stands for
+            let mismatch = !types(requested).contains(&"ALL".to_string())
+                && allocated
+                    .as_ref()
+                    .is_some_and(|allocated| types(allocated) != types(requested));
+            (allocated, mismatch)
+        }
+        "constraint" => (get("Features"), false),
+        "nodelist" => (get("ReqNodeList"), false),
+        "exclude" => (get("ExcNodeList"), false),
+        "gres" | "gpus-per-node" => (get("TresPerNode"), false),
+        "gpus" => (get("TresPerJob"), false),
+        "gpus-per-task" => (get("TresPerTask"), false),
+        "output" => (get("StdOut"), false),
+        "error" => (get("StdErr"), false),
+        "input" => (get("StdIn"), false),
+        "chdir" => (get("WorkDir"), false),
+        "array" => (get("ArrayTaskId"), false),
+        "dependency" => (get("Dependency"), false),
+        _ => (None, false),
+    }
+}
+
+/// Compare a count such as `2` or a range such as `2-4` with the allocated count
+fn compare_count(requested: &str, allocated: Option<String>) -> (Option<String>, bool) {
+    // Pending jobs may still report a range
+    let count = allocated
+        .as_deref()
+        .and_then(|v| v.split('-').next())
+        .and_then(|v| v.parse::<u64>().ok());
+    let (min, max) = match requested.split_once('-') {
+        Some((min, max)) => (min.parse::<u64>().ok(), max.parse::<u64>().ok()),
+        None => (requested.parse().ok(), requested.parse().ok()),
+    };
+
+    let mismatch = match (count, min) {
+        (Some(count), Some(min)) => count < min || max.is_some_and(|max| count > max),
+        _ => false,
+    };
+    (allocated, mismatch)
+}
+
+/// Compare a memory request with the job's memory, which Slurm reports either
+/// per node (`MinMemoryNode`) or per CPU (`MinMemoryCPU`)
+fn compare_memory(
+    requested: &str,
+    scope: MemoryScope,
+    job: &HashMap<String, String>,
+) -> (Option<String>, bool) {
+    let allocated = [
+        ("MinMemoryNode", MemoryScope::PerNode),
+        ("MinMemoryCPU", MemoryScope::PerCpu),
+    ]
+    .into_iter()
+    .find_map(|(key, scope)| {
+        let value = job.get(key)?;
+        let memory = value.parse::<Memory>().ok()?;
+        Some((value.clone(), Memory { scope, ..memory }))
+    });
+    let Some((value, memory)) = allocated else {
+        return (None, false);
+    };
+
+    let display = match memory.scope {
+        MemoryScope::PerNode => format!("{} per node", value),
+        MemoryScope::PerCpu => format!("{} per CPU", value),
+    };
+    let mismatch = requested
+        .parse::<Memory>()
+        .is_ok_and(|request| memory.scope != scope || memory.mb != request.mb);
+    (Some(display), mismatch)
+}
+
+/// Compare time limits, which Slurm may print in another format
+fn compare_duration(requested: &str, allocated: Option<String>) -> (Option<String>, bool) {
+    let mismatch = match (
+        requested.parse::<SlurmDuration>(),
+        allocated.as_deref().map(str::parse::<SlurmDuration>),
+    ) {
+        (Ok(requested), Some(Ok(allocated))) => requested != allocated,
+        _ => false,
+    };
+    (allocated, mismatch)
+}
+
+fn compare_ignore_case(requested: &str, allocated: Option<String>) -> (Option<String>, bool) {
+    let mismatch = allocated
+        .as_ref()
+        .is_some_and(|allocated| !allocated.eq_ignore_ascii_case(requested));
+    (allocated, mismatch)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn directive(option: &str, value: Option<&str>) -> Directive {
+        Directive {
+            option: option.to_string(),
+            value: value.map(str::to_string),
+        }
+    }
+
+    fn job(fields: &[(&str, &str)]) -> HashMap<String, String> {
+        fields
+            .iter()
+            .map(|(key, value)| (key.to_string(), value.to_string()))
+            .collect()
+    }
+
+    #[test]
+    fn parses_long_and_short_options() {
+        let script = "#!/bin/bash\n\
+                      #SBATCH --nodes=2 --time 1:00:00\n\
+                      #SBATCH -N2 -c 4 -o=out.log\n\
+                      #SBATCH --exclusive -J train\n";
+        assert_eq!(
+            parse_directives(script),
+            vec![
+                directive("nodes", Some("2")),
+                directive("time", Some("1:00:00")),
+                directive("nodes", Some("2")),
+                directive("cpus-per-task", Some("4")),
+                directive("output", Some("out.log")),
+                directive("exclusive", None),
+                directive("job-name", Some("train")),
+            ]
+        );
+    }
+
+    #[test]
+    fn parses_quoted_values_and_skips_comments() {
+        let script = "#SBATCH --job-name=\"my job\" -o 'logs/%j.out' # trailing comment\n\
+                      # SBATCH --nodes=4\n\
+                      #SBATCHX --nodes=8\n";
+        assert_eq!(
+            parse_directives(script),
+            vec![
+                directive("job-name", Some("my job")),
+                directive("output", Some("logs/%j.out")),
+            ]
+        );
+    }
+
+    #[test]
+    fn stops_at_the_first_command() {
+        let script = "#!/bin/bash\n\n#SBATCH -n 4\necho start\n#SBATCH -n 8\n";
+        assert_eq!(
+            parse_directives(script),
+            vec![directive("ntasks", Some("4"))]
+        );
+    }
+
+    #[test]
+    fn compares_counts_and_ranges() {
+        let allocated = |count: &str| Some(count.to_string());
+        assert!(!compare_count("2", allocated("2")).1);
+        assert!(compare_count("2", allocated("3")).1);
+        assert!(!compare_count("2-4", allocated("3")).1);
+        assert!(compare_count("2-4", allocated("5")).1);
+        assert!(compare_count("2-4", allocated("1")).1);
+        // A pending job may still report the requested range
+        assert!(!compare_count("2", allocated("2-4")).1);
+        assert!(!compare_count("2", None).1);
+    }
+
+    #[test]
+    fn compares_memory_per_node_and_per_cpu() {
+        let per_node = job(&[("MinMemoryNode", "64G")]);
+        assert_eq!(
+            compare_memory("64G", MemoryScope::PerNode, &per_node),
+            (Some("64G per node".to_string()), false)
+        );
+        assert!(!compare_memory("65536M", MemoryScope::PerNode, &per_node).1);
+        assert!(compare_memory("32G", MemoryScope::PerNode, &per_node).1);
+
+        // --mem asks for memory per node, which Slurm didn't keep
+        let per_cpu = job(&[("MinMemoryCPU", "16G")]);
+        assert_eq!(
+            compare_memory("64G", MemoryScope::PerNode, &per_cpu),
+            (Some("16G per CPU".to_string()), true)
+        );
+        assert!(!compare_memory("16G", MemoryScope::PerCpu, &per_cpu).1);
+
+        assert_eq!(
+            compare_memory("64G", MemoryScope::PerNode, &job(&[])),
+            (None, false)
+        );
+    }
+}
@@ -1,22 +1,46 @@
 use async_process::{Command, Output};
-use color_eyre::eyre::Error;
+use color_eyre::eyre::{eyre, Error};
 use color_eyre::Result;
-use std::collections::HashMap;
-use std::str::FromStr;
 
+use super::columns::JobColumn;
+use super::fields::SlurmDuration;
 use super::Job;
 use super::JobState;
 
+/// Columns every query includes, even when they aren't displayed: job state
+/// changes are tracked by ID and state, and reported with the job name
+const TRACKED_COLUMNS: &[JobColumn] = &[JobColumn::Id, JobColumn::Name, JobColumn::State];
+
+/// Delimits the fields of squeue's output. Job names, comments and commands
+/// may contain `|`, but not this control character.
+const FIELD_SEPARATOR: char = '\x1f';
+
+/// What the client-side filters did to a list of jobs
+#[derive(Debug, Clone, Default)]
+pub struct FilterReport {
+    /// `filter: kept/total (percent)` for each filter that removed jobs
+    pub stats: Vec<String>,
+    /// Filters that were skipped because their pattern is invalid
+    pub errors: Vec<String>,
+}
+
 #[derive(Debug, Clone)]
 pub struct SqueueOptions {
     pub user: Option<String>,
+    /// Job IDs passed to squeue's `--jobs`; an array job ID lists all its tasks
+    pub jobs: Vec<String>,
     pub states: Vec<JobState>,
     pub partitions: Vec<String>,
     pub qos: Vec<String>,
     pub name_filter: Option<String>,
     pub node_filter: Option<String>,
+    pub min_time: Option<SlurmDuration>,
+    /// `|`-separated `%` codes, or `--Format` field names when `long_format` is set
     pub format: String,
-    pub sorts: HashMap<String, bool>, // Map of field to sort direction (true for ascending, false for descending)
+    /// Query with squeue's `--Format` instead of `--format`
+    pub long_format: bool,
+    /// Columns queried without being displayed, such as those hooks read
+    pub hidden_columns: Vec<JobColumn>,
 }
 
 impl Default for SqueueOptions {
@@ -24,19 +48,18 @@ impl Default for SqueueOptions {
         // Default username from environment
         let username = std::env::var("USER").unwrap_or_else(|_| "".to_string());
 
-        // Default sort options
-        let mut sorts = HashMap::new();
-        sorts.insert("i".to_string(), true); // Default sort by job ID ascending
-
         Self {
             user: Some(username),
+            jobs: Vec::new(),
             states: Vec::new(),
             partitions: Vec::new(),
             qos: Vec::new(),
             name_filter: None,
             node_filter: None,
+            min_time: None,
             format: "%i|%j|%u|%T|%M|%N|%C|%m|%P|%q".to_string(), // JobID|Name|User|State|Time|Nodes|CPUs|Memory|Partition|QOS
-            sorts,
+            long_format: false,
+            hidden_columns: Vec::new(),
         }
     }
 }
@@ -47,10 +70,90 @@ impl SqueueOptions {
         self.format.split('|').collect()
     }
 
+    /// Query the given columns, the tracked ones and the hidden ones, switching
+    /// to `--Format` when a custom column needs it. Returns the columns that
+    /// can't be fetched then: custom columns with only a `%` code, which stay
+    /// empty.
+    pub fn set_columns(&mut self, columns: &[JobColumn]) -> Vec<JobColumn> {
+        // Custom columns given as --Format field names switch the whole query to --Format
+        let long_format = columns.iter().any(|col| col.needs_long_format());
+
+        let mut codes = Vec::new();
+        let mut unfetchable = Vec::new();
+        let mut queried = columns.to_vec();
+        for column in TRACKED_COLUMNS.iter().chain(&self.hidden_columns) {
+            if !queried.contains(column) {
+                queried.push(*column);
+            }
+        }
+        for column in &queried {
+            match column.query_code(long_format) {
+                Some(code) => codes.push(code),
+                None => unfetchable.push(*column),
+            }
+        }
+        self.format = codes.join("|");
+        self.long_format = long_format;
+        unfetchable
+    }
+
+    /// Apply the filters squeue can't do itself: the name and node regexes
+    /// and the minimum elapsed time
+    pub fn filter_jobs(&self, jobs: &mut Vec<Job>) -> FilterReport {
+        let mut report = FilterReport::default();
+
+        let mut apply = |label: &str, jobs: &mut Vec<Job>, keep: &dyn Fn(&Job) -> bool| {
+            let before_count = jobs.len();
+            jobs.retain(|job| keep(job));
+            let after_count = jobs.len();
+            if before_count != after_count && before_count > 0 {
+                report.stats.push(format!(
+                    "{}: {}/{} ({:.1}%)",
+                    label,
+                    after_count,
+                    before_count,
+                    (after_count as f64 / before_count as f64) * 100.0
+                ));
+            }
+        };
+
+        if let Some(name_filter) = self.name_filter.as_deref().filter(|f| !f.is_empty()) {
+            match regex::Regex::new(name_filter) {
+                Ok(re) => apply("name", jobs, &|job| re.is_match(&job.name)),
+                Err(e) => report
+                    .errors
+                    .push(format!("Invalid name regex pattern: {}", e)),
+            }
+        }
+
+        if let Some(node_filter) = self.node_filter.as_deref().filter(|f| !f.is_empty()) {
+            match regex::Regex::new(node_filter) {
+                // Jobs without nodes yet are kept
+                Ok(re) => apply("node", jobs, &|job| {
+                    job.node.as_ref().is_none_or(|node| re.is_match(node))
+                }),
+                Err(e) => report
+                    .errors
+                    .push(format!("Invalid node regex pattern: {}", e)),
+            }
+        }
+
+        if let Some(min_time) = self.min_time {
+            apply("elapsed", jobs, &|job| {
+                job.time.is_some_and(|t| t >= min_time)
+            });
+        }
+
+        report
+    }
+
     // Validate the format string to ensure it contains valid format codes
     pub fn validate_format(&self) -> bool {
         let codes = self.format_codes();
-        !codes.is_empty() && codes.iter().all(|code| code.starts_with('%'))
+        !codes.is_empty()
+            && codes
+                .iter()
+                .all(|code| !code.is_empty() && (self.long_format || code.starts_with('%')))
     }
 }
 
@@ -64,6 +167,12 @@ impl SqueueOptions {
             args.push(user.clone());
         }
 
+        // Job filter
+        if !self.jobs.is_empty() {
+            args.push("--jobs".to_string());
+            args.push(self.jobs.join(","));
+        }
+
         // State filter
         if !self.states.is_empty() {
             let states = self
@@ -90,30 +199,28 @@ impl SqueueOptions {
             args.push(qos);
         }
 
-        // Name filter is now handled internally by the application
-        // so we don't pass it to squeue
+        // Name, node and elapsed time filters are handled internally by the
+        // application so we don't pass them to squeue
 
         // Format specification
-        args.push("--format".to_string());
-        args.push(self.format.clone());
-
-        // Sort options
-        if !self.sorts.is_empty() {
-            // Create a sort string from the sorts map
-            let sort_string = self
-                .sorts
+        if self.long_format {
+            // A zero width prints the full value, and the suffix delimits fields
+            let fields = self
+                .format_codes()
                 .iter()
-                .map(|(field, ascending)| {
-                    let prefix = if *ascending { "" } else { "-" };
-                    format!("{}{}", prefix, field)
-                })
+                .map(|field| format!("{}:0{}", field, FIELD_SEPARATOR))
                 .collect::<Vec<_>>()
                 .join(",");
-
-            args.push("--sort".to_string());
-            args.push(sort_string);
+            args.push("--Format".to_string());
+            args.push(fields);
+        } else {
+            args.push("--format".to_string());
+            args.push(self.format_codes().join(&FIELD_SEPARATOR.to_string()));
         }
 
+        // Sorting is done client-side by JobsList with typed comparators,
+        // so no --sort is passed to squeue
+
         // No header flag to make parsing easier
         args.push("--noheader".to_string());
 
@@ -143,12 +250,15 @@ pub async fn run_squeue(options: &SqueueOptions) -> Result<Vec<Job>> {
         }
     };
 
-    // // Check if squeue returned an error
-    // if !output.status.success() {
-    //     let stderr = String::from_utf8_lossy(&output.stderr);
-    //     eprintln!("squeue returned an error: {}", stderr);
-    //     return Ok(Vec::new());
-    // }
+    // Check if squeue returned an error, e.g. when the controller is down
+    if !output.status.success() {
+        let stderr = String::from_utf8_lossy(&output.stderr);
+        // squeue rejects job IDs that have already left the queue
+        if !options.jobs.is_empty() && stderr.contains("Invalid job id") {
+            return Ok(Vec::new());
+        }
+        return Err(eyre!("squeue failed: {}", stderr.trim()));
+    }
 
     // Pass the format options with the output to ensure correct parsing
     parse_squeue_output(&output, &options.format)
@@ -183,7 +293,7 @@ fn parse_squeue_output(output: &Output, format: &str) -> Result<Vec<Job>> {
             continue;
         }
 
-        let parts: Vec<&str> = line.split('|').collect();
+        let parts: Vec<&str> = line.split(FIELD_SEPARATOR).collect();
         if parts.is_empty() || parts.len() < format_codes.len() / 2 {
             // eprintln!("Skipping invalid line: {}", line);
             continue;
@@ -209,47 +319,8 @@ fn parse_squeue_output(output: &Output, format: &str) -> Result<Vec<Job>> {
                 break;
             }
 
-            match format_codes[i] {
-                "%i" | "%A" => job.id = value,
-                "%j" => job.name = value,
-                "%u" => job.user = value,
-                "%T" => {
-                    job.state = JobState::from_str(&value).unwrap_or_else(|_| {
-                        // eprintln!("Failed to parse job state: {}", value);
-                        JobState::Other
-                    })
-                }
-                "%M" => job.time = value,
-                "%D" => {
-                    job.nodes = value.parse::<u32>().unwrap_or_else(|_| {
-                        // eprintln!("Failed to parse node count: {}", value);
-                        0
-                    })
-                }
-                "%N" => job.node = Some(value),
-                "%C" => {
-                    job.cpus = value.parse::<u32>().unwrap_or_else(|_| {
-                        // eprintln!("Failed to parse CPU count: {}", value);
-                        0
-                    })
-                }
-                "%m" => job.memory = value,
-                "%P" => job.partition = value,
-                "%q" => job.qos = value,
-                "%a" => job.account = Some(value),
-                "%Q" => {
-                    job.priority = value.parse::<u32>().ok().or_else(|| {
-                        // eprintln!("Failed to parse priority: {}", value);
-                        None
-                    })
-                }
-                "%Z" => job.work_dir = Some(value),
-                "%V" => job.submit_time = Some(value),
-                "%S" => job.start_time = Some(value),
-                "%e" => job.end_time = Some(value),
-                _ => {
-                    // eprintln!("Unknown format code: {}", format_codes[i]);
-                }
+            if let Some(column) = JobColumn::from_code(format_codes[i]) {
+                column.parse(&mut job, &value);
             }
         }
 
@@ -258,3 +329,47 @@ fn parse_squeue_output(output: &Output, format: &str) -> Result<Vec<Job>> {
 
     Ok(jobs)
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::process::ExitStatus;
+
+    fn output(stdout: &str) -> Output {
+        Output {
+            status: ExitStatus::default(),
+            stdout: stdout.as_bytes().to_vec(),
+            stderr: Vec::new(),
+        }
+    }
+
+    #[test]
+    fn keeps_fields_apart_when_values_contain_pipes() {
+        let mut options = SqueueOptions::default();
+        options.set_columns(&[JobColumn::Id, JobColumn::Name, JobColumn::State]);
+        let args = options.to_args();
+        assert!(args.contains(&"%i\x1f%j\x1f%T".to_string()), "{:?}", args);
+
+        let jobs = parse_squeue_output(
+            &output("101\x1fa|b | c\x1fRUNNING\n102\x1fplain\x1fCOMPLETING\n"),
+            &options.format,
+        )
+        .unwrap();
+        assert_eq!(jobs.len(), 2);
+        assert_eq!(jobs[0].name, "a|b | c");
+        assert_eq!(jobs[0].state, JobState::Running);
+        assert_eq!(jobs[1].state, JobState::Completing);
+    }
+
+    #[test]
+    fn delimits_long_format_fields() {
+        let options = SqueueOptions {
+            format: "JobID|Name".to_string(),
+            long_format: true,
+            ..Default::default()
+        };
+        assert!(options
+            .to_args()
+            .contains(&"JobID:0\x1f,Name:0\x1f".to_string()));
+    }
+}
@@ -0,0 +1,45 @@
+use color_eyre::{eyre::eyre, Result};
+use std::{path::Path, process::Command};
+
+/// Submit a batch script with `sbatch` and return the new job ID.
+///
+/// The job is submitted from `work_dir`, so that relative paths in the script
+/// resolve as they did for the original job. `overrides` are long sbatch
+/// options, which take precedence over the `#SBATCH` lines of the script.
+pub fn submit_script(
+    script: &Path,
+    work_dir: Option<&str>,
+    overrides: &[(String, String)],
+) -> Result<String> {
+    let mut command = Command::new("sbatch");
+    command.arg("--parsable");
+    command.args(
+        overrides
+            .iter()
+            .map(|(option, value)| format!("--{}={}", option, value)),
+    );
+    command.arg(script);
+    if let Some(work_dir) = work_dir.filter(|dir| Path::new(dir).is_dir()) {
+        command.current_dir(work_dir);
+    }
+
+    let output = command
+        .output()
+        .map_err(|e| eyre!("Failed to run sbatch: {}", e))?;
+    if !output.status.success() {
+        return Err(eyre!(
+            "{}",
+            String::from_utf8_lossy(&output.stderr).trim().to_string()
+        ));
+    }
+
+    // --parsable prints <job id>[;<cluster>]
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    stdout
+        .trim()
+        .split(';')
+        .next()
+        .filter(|id| !id.is_empty())
+        .map(str::to_string)
+        .ok_or_else(|| eyre!("sbatch printed no job ID"))
+}
@@ -0,0 +1,181 @@
+use std::{collections::HashMap, process::Command};
+
+use super::{Job, JobState};
+
+/// State name of jobs that left the queue without a recorded final state
+pub const GONE: &str = "GONE";
+
+/// A change of a job's state between two refreshes
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct JobTransition {
+    pub job_id: String,
+    pub name: String,
+    /// State at the previous refresh
+    pub from: JobState,
+    /// New state, missing if the job left the queue and its state is unknown
+    pub to: Option<JobState>,
+    /// Slurm's name of the new state, such as `DEADLINE` which has no
+    /// `JobState` of its own, or `GONE`
+    pub state_name: String,
+}
+
+impl JobTransition {
+    /// One-line description, e.g. `Job 123 (train) RUNNING → FAILED`
+    pub fn message(&self) -> String {
+        format!(
+            "Job {} ({}) {} → {}",
+            self.job_id, self.name, self.from, self.state_name
+        )
+    }
+}
+
+/// States of the jobs at the last refresh, to tell what changed since
+#[derive(Debug, Clone, Default)]
+pub struct JobSnapshot {
+    /// Job name and state by job ID, `None` until the first refresh
+    jobs: Option<HashMap<String, (String, JobState)>>,
+}
+
+impl JobSnapshot {
+    /// Forget the last refresh, e.g. when the filters change which jobs are listed
+    pub fn reset(&mut self) {
+        self.jobs = None;
+    }
+
+    /// Record the jobs of a refresh and return how they changed since the
+    /// previous one. The final state of jobs that left the queue is looked up
+    /// in the accounting database.
+    pub fn update(&mut self, jobs: &[Job]) -> Vec<JobTransition> {
+        let previous_state = |id: &String| {
+            self.jobs
+                .as_ref()
+                .and_then(|previous| previous.get(id))
+                .map(|(_, state)| *state)
+        };
+        // A completing job keeps its previous state until it reaches its final one
+        let mut current: HashMap<String, (String, JobState)> = jobs
+            .iter()
+            .map(|job| {
+                let state = match job.state {
+                    JobState::Completing => previous_state(&job.id).unwrap_or(job.state),
+                    state => state,
+                };
+                (job.id.clone(), (job.name.clone(), state))
+            })
+            .collect();
+        let Some(previous) = self.jobs.take() else {
+            self.jobs = Some(current);
+            return Vec::new();
+        };
+
+        let mut transitions: Vec<JobTransition> = jobs
+            .iter()
+            .filter(|job| job.state != JobState::Completing)
+            .filter_map(|job| {
+                let (_, from) = previous.get(&job.id)?;
+                (*from != job.state).then(|| JobTransition {
+                    job_id: job.id.clone(),
+                    name: job.name.clone(),
+                    from: *from,
+                    to: Some(job.state),
+                    state_name: job.state.to_string(),
+                })
+            })
+            .collect();
+
+        let gone: Vec<&String> = previous
+            .keys()
+            .filter(|id| !current.contains_key(*id))
+            .collect();
+        if !gone.is_empty() {
+            let final_states = final_states(&gone);
+            for id in gone {
+                let (name, from) = &previous[id];
+                let state_name = final_states
+                    .get(id)
+                    .map(|(state, _)| state.clone())
+                    .unwrap_or_else(|| GONE.to_string());
+                let to =
+                    (state_name != GONE).then(|| state_name.parse().unwrap_or(JobState::Other));
+                // Still the same state, but filtered out of the list
+                if to == Some(*from) {
+                    continue;
+                }
+                // Accounting hasn't recorded the final state yet, look again next refresh
+                if to == Some(JobState::Completing) {
+                    current.insert(id.clone(), (name.clone(), *from));
+                    continue;
+                }
+                transitions.push(JobTransition {
+                    job_id: id.clone(),
+                    name: name.clone(),
+                    from: *from,
+                    to,
+                    state_name,
+                });
+            }
+        }
+
+        self.jobs = Some(current);
+        transitions.sort_by(|a, b| a.job_id.cmp(&b.job_id));
+        transitions
+    }
+}
+
+/// Current states and names of jobs from `sacct`, by job ID. The ID of an
+/// array job also gives the states of its tasks.
+pub fn final_states(job_ids: &[&String]) -> HashMap<String, (String, String)> {
+    let ids = job_ids
+        .iter()
+        .map(|id| id.as_str())
+        .collect::<Vec<_>>()
+        .join(",");
+    let Ok(output) = Command::new("sacct")
+        .args(["-j", &ids, "-X", "-n", "-P", "-o", "JobID,State,JobName"])
+        .output()
+    else {
+        return HashMap::new();
+    };
+    if !output.status.success() {
+        return HashMap::new();
+    }
+
+    String::from_utf8_lossy(&output.stdout)
+        .lines()
+        .filter_map(|line| {
+            // The name comes last, as it may contain the delimiter
+            let mut fields = line.splitn(3, '|');
+            let (id, state) = (fields.next()?, fields.next()?);
+            let name = fields.next().unwrap_or_default();
+            // CANCELLED by 1000
+            let state = state.split_whitespace().next()?;
+            Some((id.to_string(), (state.to_string(), name.to_string())))
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn job(state: JobState) -> Job {
+        Job {
+            id: "101".to_string(),
+            name: "train".to_string(),
+            state,
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn reports_the_final_state_after_completing() {
+        let mut snapshot = JobSnapshot::default();
+        assert!(snapshot.update(&[job(JobState::Running)]).is_empty());
+        assert!(snapshot.update(&[job(JobState::Completing)]).is_empty());
+
+        let transitions = snapshot.update(&[job(JobState::Failed)]);
+        assert_eq!(transitions.len(), 1);
+        assert_eq!(transitions[0].from, JobState::Running);
+        assert_eq!(transitions[0].to, Some(JobState::Failed));
+    }
+}
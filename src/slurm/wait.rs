@@ -0,0 +1,243 @@
+use std::collections::{HashMap, HashSet};
+
+use super::{hostlist::expand_hostlist, transitions::final_states, Job, JobState};
+use crate::utils::job_id_sort_key;
+
+/// State name of jobs that left the queue without a state in the accounting database
+pub const UNKNOWN: &str = "UNKNOWN";
+
+/// Polls to wait for the accounting database to record a job that left the
+/// queue, before giving up on its state
+const LOOKUP_ATTEMPTS: u32 = 3;
+
+/// Whether a job in this state has ended. `Other` covers states such as
+/// `SUSPENDED` or `REQUEUED`, so it only counts once the job left the queue.
+/// A completing job may still end as failed, its final state comes later.
+pub fn is_terminal(state: JobState) -> bool {
+    !matches!(
+        state,
+        JobState::Pending | JobState::Running | JobState::Completing | JobState::Other
+    )
+}
+
+/// Whether the accounting database still has a job as pending, running or
+/// completing
+fn is_active(state_name: &str) -> bool {
+    matches!(
+        state_name.parse(),
+        Ok(JobState::Pending) | Ok(JobState::Running) | Ok(JobState::Completing)
+    )
+}
+
+/// A job being waited for
+#[derive(Debug, Clone)]
+pub struct WaitedJob {
+    pub id: String,
+    pub name: String,
+    /// Slurm's name of the last known state
+    pub state_name: String,
+    /// Ended, or left the queue
+    pub done: bool,
+}
+
+impl WaitedJob {
+    pub fn succeeded(&self) -> bool {
+        self.state_name == JobState::Completed.to_string()
+    }
+}
+
+/// Follows a set of jobs from poll to poll until they have all ended
+#[derive(Debug, Default)]
+pub struct JobWaiter {
+    jobs: HashMap<String, WaitedJob>,
+    /// Pending array ranges such as `123_[4-10]` by ID, with their job name.
+    /// Their tasks get IDs of their own once they start.
+    ranges: HashMap<String, String>,
+    /// How often jobs that left the queue were looked up without a final state
+    lookups: HashMap<String, u32>,
+}
+
+impl JobWaiter {
+    /// Add jobs that are no longer in the queue, as found in the accounting
+    /// database. The ID of an array job adds all of its tasks.
+    pub fn add_finished(&mut self, job_ids: &[String]) {
+        let ids: Vec<&String> = job_ids
+            .iter()
+            .filter(|id| !self.jobs.contains_key(*id))
+            .collect();
+        if ids.is_empty() {
+            return;
+        }
+
+        for (id, (state_name, name)) in final_states(&ids) {
+            // Pending ranges and running tasks are followed through the queue
+            if id.contains('[') || self.jobs.contains_key(&id) {
+                continue;
+            }
+            // Not ended yet as far as accounting knows, it's looked up again
+            // on the next poll
+            let done = !is_active(&state_name);
+            self.jobs.insert(
+                id.clone(),
+                WaitedJob {
+                    id,
+                    name,
+                    state_name,
+                    done,
+                },
+            );
+        }
+    }
+
+    /// Record the matching jobs of a poll. Jobs that left the queue are done,
+    /// with their final state from the accounting database.
+    pub fn update(&mut self, jobs: &[Job]) {
+        let current: HashSet<&str> = jobs.iter().map(|job| job.id.as_str()).collect();
+
+        let mut pending_tasks = HashSet::new();
+        for job in jobs {
+            if job.id.contains('[') {
+                pending_tasks.extend(range_tasks(&job.id));
+                self.ranges.insert(job.id.clone(), job.name.clone());
+                continue;
+            }
+            let waited = self
+                .jobs
+                .entry(job.id.clone())
+                .or_insert_with(|| WaitedJob {
+                    id: job.id.clone(),
+                    name: job.name.clone(),
+                    state_name: String::new(),
+                    done: false,
+                });
+            waited.state_name = job.state.to_string();
+            waited.done = is_terminal(job.state);
+        }
+
+        // Tasks of shrinking ranges that started and ended between two polls
+        let jobs_by_id = &mut self.jobs;
+        self.ranges.retain(|range, name| {
+            if current.contains(range.as_str()) {
+                return true;
+            }
+            for task in range_tasks(range) {
+                if current.contains(task.as_str()) || pending_tasks.contains(&task) {
+                    continue;
+                }
+                jobs_by_id.entry(task.clone()).or_insert_with(|| WaitedJob {
+                    id: task,
+                    name: name.clone(),
+                    state_name: JobState::Pending.to_string(),
+                    done: false,
+                });
+            }
+            false
+        });
+
+        let gone: Vec<&String> = self
+            .jobs
+            .values()
+            .filter(|job| !job.done && !current.contains(job.id.as_str()))
+            .map(|job| &job.id)
+            .collect();
+        if gone.is_empty() {
+            return;
+        }
+
+        let final_states = final_states(&gone);
+        let gone: Vec<String> = gone.into_iter().cloned().collect();
+        for id in gone {
+            let final_state = final_states
+                .get(&id)
+                .map(|(state_name, _)| state_name.clone())
+                .filter(|state_name| !is_active(state_name));
+            let attempts = self.lookups.entry(id.clone()).or_insert(0);
+            *attempts += 1;
+            // Accounting may lag behind the queue, so try again next poll
+            if final_state.is_none() && *attempts < LOOKUP_ATTEMPTS {
+                continue;
+            }
+            if let Some(job) = self.jobs.get_mut(&id) {
+                job.state_name = final_state.unwrap_or_else(|| UNKNOWN.to_string());
+                job.done = true;
+            }
+        }
+    }
+
+    /// Whether no job has been found
+    pub fn is_empty(&self) -> bool {
+        self.jobs.is_empty() && self.ranges.is_empty()
+    }
+
+    /// Whether every job has ended
+    pub fn all_done(&self) -> bool {
+        self.ranges.is_empty() && self.jobs.values().all(|job| job.done)
+    }
+
+    /// The jobs, by job ID
+    pub fn jobs(&self) -> Vec<&WaitedJob> {
+        let mut jobs: Vec<&WaitedJob> = self.jobs.values().collect();
+        jobs.sort_by_key(|job| job_id_sort_key(&job.id));
+        jobs
+    }
+
+    /// One-line summary, e.g. `3/8 done, 2 running, 3 pending`
+    pub fn progress(&self) -> String {
+        let range_tasks: usize = self
+            .ranges
+            .keys()
+            .map(|range| range_tasks(range).len())
+            .sum();
+        let total = self.jobs.len() + range_tasks;
+        let done = self.jobs.values().filter(|job| job.done).count();
+        let running = self
+            .jobs
+            .values()
+            .filter(|job| !job.done && job.state_name == JobState::Running.to_string())
+            .count();
+        format!(
+            "{}/{} done, {} running, {} pending",
+            done,
+            total,
+            running,
+            total - done - running
+        )
+    }
+}
+
+/// Task IDs of a pending array range such as `123_[4-10%2]`
+fn range_tasks(range: &str) -> Vec<String> {
+    // A `%` suffix limits how many tasks run at once, it is not a task
+    let range = match (range.find('%'), range.rfind(']')) {
+        (Some(throttle), Some(end)) if throttle < end => {
+            format!("{}{}", &range[..throttle], &range[end..])
+        }
+        _ => range.to_string(),
+    };
+    expand_hostlist(&range)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn expands_array_ranges_without_the_throttle() {
+        assert_eq!(range_tasks("123_[4-6%2]"), vec!["123_4", "123_5", "123_6"]);
+        assert_eq!(
+            range_tasks("123_[1,3,8-9]"),
+            vec!["123_1", "123_3", "123_8", "123_9"]
+        );
+        assert_eq!(range_tasks("123_7"), vec!["123_7"]);
+    }
+
+    #[test]
+    fn completing_jobs_have_not_ended() {
+        let completing: JobState = "CG".parse().unwrap();
+        assert_eq!(completing, JobState::Completing);
+        assert!(!is_terminal(completing));
+        assert!(is_active("COMPLETING"));
+        assert!(is_terminal("CD".parse().unwrap()));
+        assert!(is_terminal("FAILED".parse().unwrap()));
+    }
+}
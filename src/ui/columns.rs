@@ -1,150 +1,16 @@
-use crossterm::event::KeyModifiers;
+use crossterm::event::{KeyCode, KeyEvent, KeyModifiers, MouseButton, MouseEvent, MouseEventKind};
 use ratatui::{
-    layout::{Constraint, Direction, Layout, Rect},
+    layout::{Constraint, Direction, Layout, Position, Rect},
     style::{Color, Modifier, Style},
     text::Line,
     widgets::{Block, Borders, Clear, List, ListItem, ListState, Paragraph},
     Frame,
 };
+use std::cmp::Ordering;
 
-/// Available columns for display in job list
-#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
-pub enum JobColumn {
-    Id,
-    Name,
-    User,
-    State,
-    Partition,
-    QoS,
-    Nodes,
-    Node,
-    CPUs,
-    Time,
-    Memory,
-    Account,
-    Priority,
-    WorkDir,
-    SubmitTime,
-    StartTime,
-    EndTime,
-    PReason,
-}
-
-impl JobColumn {
-    /// Get the title for this column
-    pub fn title(&self) -> &'static str {
-        match self {
-            JobColumn::Id => "ID",
-            JobColumn::Name => "Name",
-            JobColumn::User => "User",
-            JobColumn::State => "State",
-            JobColumn::Partition => "Partition",
-            JobColumn::QoS => "QoS",
-            JobColumn::Nodes => "Nodes",
-            JobColumn::Node => "Node",
-            JobColumn::CPUs => "CPUs",
-            JobColumn::Time => "Time",
-            JobColumn::Memory => "Memory",
-            JobColumn::Account => "Account",
-            JobColumn::Priority => "Priority",
-            JobColumn::WorkDir => "WorkDir",
-            JobColumn::SubmitTime => "Submit",
-            JobColumn::StartTime => "Start",
-            JobColumn::EndTime => "End",
-            JobColumn::PReason => "Reason", // Pending reason
-        }
-    }
-
-    /// Get the format code for this column
-    pub fn format_code(&self) -> &'static str {
-        match self {
-            JobColumn::Id => "%i",         // Job ID - using %A for array job ID
-            JobColumn::Name => "%j",       // Job name
-            JobColumn::User => "%u",       // User name
-            JobColumn::State => "%T",      // Job state
-            JobColumn::Partition => "%P",  // Partition
-            JobColumn::QoS => "%q",        // Quality of Service
-            JobColumn::Nodes => "%D",      // Node count
-            JobColumn::Node => "%N",       // Node list
-            JobColumn::CPUs => "%C",       // CPU count
-            JobColumn::Time => "%M",       // Time limit
-            JobColumn::Memory => "%m",     // Memory
-            JobColumn::Account => "%a",    // Account
-            JobColumn::Priority => "%Q",   // Priority
-            JobColumn::WorkDir => "%Z",    // Working directory
-            JobColumn::SubmitTime => "%V", // Submission time
-            JobColumn::StartTime => "%S",  // Start time
-            JobColumn::EndTime => "%e",    // End time
-            JobColumn::PReason => "%R",    // Pending reason
-        }
-    }
-
-    /// Get the default width constraint for this column
-    pub fn default_width(&self) -> Constraint {
-        match self {
-            JobColumn::Id => Constraint::Length(10),
-            JobColumn::Name => Constraint::Percentage(20),
-            JobColumn::User => Constraint::Length(10),
-            JobColumn::State => Constraint::Length(12),
-            JobColumn::Partition => Constraint::Length(12),
-            JobColumn::QoS => Constraint::Length(10),
-            JobColumn::Nodes => Constraint::Length(7),
-            JobColumn::Node => Constraint::Percentage(12), // Node list can be long
-            JobColumn::CPUs => Constraint::Length(6),
-            JobColumn::Time => Constraint::Length(12),
-            JobColumn::Memory => Constraint::Length(10),
-            JobColumn::Account => Constraint::Length(12),
-            JobColumn::Priority => Constraint::Length(10),
-            JobColumn::WorkDir => Constraint::Percentage(15),
-            JobColumn::SubmitTime => Constraint::Length(19),
-            JobColumn::StartTime => Constraint::Length(19),
-            JobColumn::EndTime => Constraint::Length(19),
-            JobColumn::PReason => Constraint::Percentage(20), // Pending reason can be long
-        }
-    }
-
-    /// Get all available columns
-    pub fn all() -> Vec<JobColumn> {
-        vec![
-            JobColumn::Id,
-            JobColumn::Name,
-            JobColumn::User,
-            JobColumn::State,
-            JobColumn::Partition,
-            JobColumn::QoS,
-            JobColumn::Nodes,
-            JobColumn::Node,
-            JobColumn::CPUs,
-            JobColumn::Time,
-            JobColumn::Memory,
-            JobColumn::Account,
-            JobColumn::Priority,
-            JobColumn::WorkDir,
-            JobColumn::SubmitTime,
-            JobColumn::StartTime,
-            JobColumn::EndTime,
-            JobColumn::PReason,
-        ]
-    }
-
-    /// Default columns to display
-    pub fn defaults() -> Vec<JobColumn> {
-        // These MUST match the defaults in App::new()
-        // "%i|%j|%u|%T|%M|%N|%C|%m|%P|%q".to_string(), // JobID|Name|User|State|Time|Nodes|CPUs|Memory|Partition|QOS
-        vec![
-            JobColumn::Id,
-            JobColumn::Name,
-            JobColumn::User,
-            JobColumn::State,
-            JobColumn::Time,
-            JobColumn::Node,
-            JobColumn::CPUs,
-            JobColumn::Memory,
-            JobColumn::Partition,
-            JobColumn::QoS,
-        ]
-    }
-}
+use crate::config::LayoutConfig;
+pub use crate::slurm::columns::JobColumn;
+use crate::slurm::Job;
 
 /// Sort order for columns
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
@@ -178,6 +44,72 @@ pub struct SortColumn {
     pub order: SortOrder,
 }
 
+/// Compare jobs by every sort column in turn, the first column being the primary key.
+/// Without sort columns, jobs are in job ID order.
+pub fn compare_jobs(a: &Job, b: &Job, sort_columns: &[SortColumn]) -> Ordering {
+    if sort_columns.is_empty() {
+        return JobColumn::Id.compare(a, b);
+    }
+    sort_columns
+        .iter()
+        .map(|sc| match sc.order {
+            SortOrder::Ascending => sc.column.compare(a, b),
+            SortOrder::Descending => sc.column.compare(b, a),
+        })
+        .find(|ord| ord.is_ne())
+        .unwrap_or(Ordering::Equal)
+}
+
+/// Columns and sort keys of a saved layout, or the defaults when it has none
+pub fn layout_columns(layout: &LayoutConfig) -> (Vec<JobColumn>, Vec<SortColumn>) {
+    let mut selected_columns: Vec<JobColumn> = layout
+        .selected
+        .iter()
+        .filter_map(|title| JobColumn::from_title(title))
+        .collect();
+    if selected_columns.is_empty() {
+        selected_columns = JobColumn::defaults();
+    }
+    let mut sort_columns: Vec<SortColumn> = layout
+        .sort
+        .iter()
+        .filter_map(|sort| {
+            Some(SortColumn {
+                column: JobColumn::from_title(&sort.column)?,
+                order: if sort.descending {
+                    SortOrder::Descending
+                } else {
+                    SortOrder::Ascending
+                },
+            })
+        })
+        .collect();
+    if sort_columns.is_empty() {
+        sort_columns = vec![SortColumn {
+            column: JobColumn::Id,
+            order: SortOrder::Ascending,
+        }];
+    }
+    (selected_columns, sort_columns)
+}
+
+/// Cycle the sort state of a column: unsorted -> ascending -> descending -> unsorted.
+/// A newly sorted column is appended, so existing sort keys keep their priority.
+pub fn cycle_sort(sort_columns: &mut Vec<SortColumn>, column: JobColumn) {
+    match sort_columns.iter().position(|sc| sc.column == column) {
+        Some(i) if sort_columns[i].order == SortOrder::Ascending => {
+            sort_columns[i].order = SortOrder::Descending;
+        }
+        Some(i) => {
+            sort_columns.remove(i);
+        }
+        None => sort_columns.push(SortColumn {
+            column,
+            order: SortOrder::Ascending,
+        }),
+    }
+}
+
 /// Which part of the columns popup is focused
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
 pub enum ColumnsFocus {
@@ -207,6 +139,8 @@ pub struct ColumnsPopup {
     pub sort_columns: Vec<SortColumn>,
     /// If show
     pub visible: bool,
+    /// Areas of the available, selected and sort lists as last rendered
+    list_areas: Vec<Rect>,
 }
 
 impl ColumnsPopup {
@@ -239,6 +173,7 @@ impl ColumnsPopup {
             selected_columns,
             sort_columns,
             visible: false,
+            list_areas: Vec::new(),
         }
     }
 
@@ -282,6 +217,7 @@ impl ColumnsPopup {
                 Constraint::Percentage(34),
             ])
             .split(area);
+        self.list_areas = columns.to_vec();
 
         // Available columns list
         let available_block = Block::default()
@@ -374,7 +310,10 @@ impl ColumnsPopup {
             _ => "",
         };
 
-        let full_help_text = format!("{} | Ctrl+a: Apply | Esc: Close", base_help_text);
+        let full_help_text = format!(
+            "{} | Ctrl+a: Apply | Ctrl+s: Save | Esc: Close",
+            base_help_text
+        );
 
         let help = Paragraph::new(full_help_text)
             .style(Style::default().fg(Color::Gray))
@@ -432,11 +371,10 @@ impl ColumnsPopup {
                 return ColumnsAction::Apply;
             }
 
-            // F10 or Ctrl+S to save settings
-            // KeyCode::F(10) => return ColumnsAction::SaveAndApply,
-            // KeyCode::Char('s') if key.modifiers.contains(KeyModifiers::CONTROL) => {
-            //     return ColumnsAction::SaveAndApply;
-            // }
+            // Ctrl+S to save settings
+            KeyCode::Char('s') if key.modifiers.contains(KeyModifiers::CONTROL) => {
+                return ColumnsAction::SaveAndApply;
+            }
             _ => {}
         }
 
@@ -642,6 +580,62 @@ impl ColumnsPopup {
         }
     }
 
+    /// Handle mouse events: a click focuses a list and picks the clicked
+    /// entry, clicking the picked entry again acts as Enter; the wheel moves
+    /// through the list under it
+    pub fn handle_mouse(&mut self, mouse: MouseEvent) {
+        let position = Position::new(mouse.column, mouse.row);
+        let Some(list) = self
+            .list_areas
+            .iter()
+            .position(|area| area.contains(position))
+        else {
+            return;
+        };
+        let area = self.list_areas[list];
+        let focus = [
+            ColumnsFocus::AvailableColumns,
+            ColumnsFocus::SelectedColumns,
+            ColumnsFocus::SortColumns,
+        ][list];
+
+        let key = match mouse.kind {
+            MouseEventKind::Down(MouseButton::Left) => {
+                let (list_state, len) = match focus {
+                    ColumnsFocus::AvailableColumns => (
+                        &mut self.available_columns_state,
+                        self.available_columns.len(),
+                    ),
+                    ColumnsFocus::SelectedColumns => (
+                        &mut self.selected_columns_state,
+                        self.selected_columns.len(),
+                    ),
+                    _ => (&mut self.sort_columns_state, self.sort_columns.len()),
+                };
+                // Rows start below the top border
+                let index = list_state.offset() + mouse.row.saturating_sub(area.y + 1) as usize;
+                let picked = mouse.row > area.y && index < len;
+                let activate =
+                    picked && self.focus == focus && list_state.selected() == Some(index);
+                if picked {
+                    list_state.select(Some(index));
+                }
+                self.focus = focus;
+                self.update_selections();
+                if !activate {
+                    return;
+                }
+                KeyCode::Enter
+            }
+            MouseEventKind::ScrollUp => KeyCode::Up,
+            MouseEventKind::ScrollDown => KeyCode::Down,
+            _ => return,
+        };
+        self.focus = focus;
+        self.update_selections();
+        self.handle_key(KeyEvent::from(key));
+    }
+
     /// Handle button key events
     fn handle_button_key(&mut self, key: crossterm::event::KeyEvent) -> ColumnsAction {
         use crossterm::event::KeyCode;
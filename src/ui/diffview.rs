@@ -0,0 +1,382 @@
+use crossterm::event::{KeyCode, KeyEvent, KeyModifiers};
+use ratatui::{
+    layout::{Constraint, Direction, Layout, Rect},
+    style::{Color, Style},
+    text::{Line, Span, Text},
+    widgets::{Block, Borders, Clear, Paragraph},
+    Frame,
+};
+
+use crate::slurm::jobdetails::{job_fields, job_script, submission_environment};
+use crate::utils::diff::{diff_lines, DiffOp};
+
+/// What is compared between the two jobs
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum DiffMode {
+    #[default]
+    Script,
+    Fields,
+    Environment,
+}
+
+impl DiffMode {
+    pub fn next(&self) -> Self {
+        match self {
+            DiffMode::Script => DiffMode::Fields,
+            DiffMode::Fields => DiffMode::Environment,
+            DiffMode::Environment => DiffMode::Script,
+        }
+    }
+
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            DiffMode::Script => "script",
+            DiffMode::Fields => "fields",
+            DiffMode::Environment => "environment",
+        }
+    }
+}
+
+/// One job's side of the diff
+#[derive(Debug, Clone, Default)]
+struct DiffSide {
+    job_id: String,
+    /// Lines compared, empty when `error` is set
+    lines: Vec<String>,
+    /// Where the lines came from, shown in the pane title
+    source: Option<String>,
+    /// Why the lines are missing
+    error: Option<String>,
+}
+
+impl DiffSide {
+    /// Fetch what `mode` compares for a job
+    fn load(job_id: &str, mode: DiffMode) -> Self {
+        let (lines, source, error) = match mode {
+            DiffMode::Script => match job_script(job_id) {
+                Some((script, source)) => (
+                    script.lines().map(str::to_string).collect(),
+                    Some(source.to_string()),
+                    None,
+                ),
+                None => (Vec::new(), None, Some("No batch script found")),
+            },
+            DiffMode::Fields => match job_fields(job_id) {
+                Some(fields) => (
+                    fields
+                        .into_iter()
+                        .map(|(key, value)| format!("{}={}", key, value))
+                        .collect(),
+                    None,
+                    None,
+                ),
+                None => (Vec::new(), None, Some("No job details found")),
+            },
+            DiffMode::Environment => match submission_environment(job_id) {
+                Some(environment) => {
+                    let mut lines: Vec<String> = environment.lines().map(str::to_string).collect();
+                    lines.sort();
+                    (lines, None, None)
+                }
+                None => (
+                    Vec::new(),
+                    None,
+                    Some("No stored environment (needs AccountingStoreFlags=job_env)"),
+                ),
+            },
+        };
+
+        Self {
+            job_id: job_id.to_string(),
+            lines,
+            source,
+            error: error.map(str::to_string),
+        }
+    }
+
+    fn title(&self) -> String {
+        match &self.source {
+            Some(source) => format!("Job {} (from {})", self.job_id, source),
+            None => format!("Job {}", self.job_id),
+        }
+    }
+}
+
+/// A screen row of the diff: a line of either job, or both
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+struct DiffRow {
+    left: Option<usize>,
+    right: Option<usize>,
+    changed: bool,
+}
+
+/// Side-by-side diff of two jobs' scripts, fields or environments
+pub struct DiffView {
+    pub visible: bool,
+    mode: DiffMode,
+    left: DiffSide,
+    right: DiffSide,
+    rows: Vec<DiffRow>,
+    scroll_position: usize,
+    /// First character shown of each line
+    horizontal_offset: usize,
+    /// Height of the panes when last rendered
+    view_height: usize,
+}
+
+impl DiffView {
+    pub fn new() -> Self {
+        Self {
+            visible: false,
+            mode: DiffMode::default(),
+            left: DiffSide::default(),
+            right: DiffSide::default(),
+            rows: Vec::new(),
+            scroll_position: 0,
+            horizontal_offset: 0,
+            view_height: 0,
+        }
+    }
+
+    /// Compare two jobs, starting with their scripts
+    pub fn show(&mut self, left_job: String, right_job: String) {
+        self.left.job_id = left_job;
+        self.right.job_id = right_job;
+        self.mode = DiffMode::default();
+        self.load();
+        self.visible = true;
+    }
+
+    pub fn hide(&mut self) {
+        self.visible = false;
+    }
+
+    /// Switch to the next kind of comparison
+    fn cycle_mode(&mut self) {
+        self.mode = self.mode.next();
+        self.load();
+    }
+
+    /// Fetch both sides for the current mode and diff them
+    fn load(&mut self) {
+        self.left = DiffSide::load(&self.left.job_id, self.mode);
+        self.right = DiffSide::load(&self.right.job_id, self.mode);
+        self.scroll_position = 0;
+        self.horizontal_offset = 0;
+
+        // Removed and added lines of a change are shown next to each other
+        self.rows.clear();
+        let mut removed = Vec::new();
+        let mut added = Vec::new();
+        for op in diff_lines(&self.left.lines, &self.right.lines) {
+            match op {
+                DiffOp::Removed(i) => removed.push(i),
+                DiffOp::Added(j) => added.push(j),
+                DiffOp::Equal(i, j) => {
+                    self.flush_change(&mut removed, &mut added);
+                    self.rows.push(DiffRow {
+                        left: Some(i),
+                        right: Some(j),
+                        changed: false,
+                    });
+                }
+            }
+        }
+        self.flush_change(&mut removed, &mut added);
+    }
+
+    fn flush_change(&mut self, removed: &mut Vec<usize>, added: &mut Vec<usize>) {
+        for k in 0..removed.len().max(added.len()) {
+            self.rows.push(DiffRow {
+                left: removed.get(k).copied(),
+                right: added.get(k).copied(),
+                changed: true,
+            });
+        }
+        removed.clear();
+        added.clear();
+    }
+
+    /// Number of removed and added lines
+    fn change_counts(&self) -> (usize, usize) {
+        self.rows
+            .iter()
+            .filter(|row| row.changed)
+            .fold((0, 0), |(removed, added), row| {
+                (
+                    removed + row.left.is_some() as usize,
+                    added + row.right.is_some() as usize,
+                )
+            })
+    }
+
+    fn max_scroll(&self) -> usize {
+        self.rows.len().saturating_sub(self.view_height)
+    }
+
+    /// Scroll to the first row of the next change
+    fn next_change(&mut self) {
+        let start = self.scroll_position + 1;
+        if let Some(row) = (start..self.rows.len())
+            .find(|&i| self.rows[i].changed && (i == 0 || !self.rows[i - 1].changed))
+        {
+            self.scroll_position = row.min(self.max_scroll());
+        }
+    }
+
+    /// Scroll to the first row of the previous change
+    fn previous_change(&mut self) {
+        if let Some(row) = (0..self.scroll_position)
+            .rev()
+            .find(|&i| self.rows[i].changed && (i == 0 || !self.rows[i - 1].changed))
+        {
+            self.scroll_position = row;
+        }
+    }
+
+    pub fn handle_key(&mut self, key: KeyEvent) {
+        match (key.modifiers, key.code) {
+            (_, KeyCode::Char('q')) => {
+                // Close the diff view
+                self.hide();
+            }
+            (_, KeyCode::Char('o')) => {
+                // Compare scripts, fields or environments
+                self.cycle_mode();
+            }
+            (_, KeyCode::Char('n')) => {
+                // Jump to the next change
+                self.next_change();
+            }
+            (_, KeyCode::Char('N')) => {
+                // Jump to the previous change
+                self.previous_change();
+            }
+            (_, KeyCode::Up) => {
+                // Scroll up
+                self.scroll_position = self.scroll_position.saturating_sub(1);
+            }
+            (_, KeyCode::Down) => {
+                // Scroll down
+                self.scroll_position = (self.scroll_position + 1).min(self.max_scroll());
+            }
+            (_, KeyCode::Left) => {
+                // Scroll left
+                self.horizontal_offset = self.horizontal_offset.saturating_sub(8);
+            }
+            (_, KeyCode::Right) => {
+                // Scroll right
+                self.horizontal_offset += 8;
+            }
+            (_, KeyCode::PageUp) | (KeyModifiers::CONTROL, KeyCode::Char('u')) => {
+                // Page up
+                self.scroll_position = self.scroll_position.saturating_sub(self.view_height.max(1));
+            }
+            (_, KeyCode::PageDown) | (KeyModifiers::CONTROL, KeyCode::Char('d')) => {
+                // Page down
+                self.scroll_position =
+                    (self.scroll_position + self.view_height.max(1)).min(self.max_scroll());
+            }
+            _ => {
+                // Ignore other keys
+            }
+        }
+    }
+
+    pub fn render(&mut self, frame: &mut Frame, area: Rect) {
+        if !self.visible {
+            return;
+        }
+
+        frame.render_widget(Clear, area);
+
+        let (removed, added) = self.change_counts();
+        let title = format!(
+            "Diff {} ↔ {} - {} [-{} +{}]",
+            self.left.job_id,
+            self.right.job_id,
+            self.mode.as_str(),
+            removed,
+            added
+        );
+        let help_text = " [↑/↓/←/→] Scroll | [Ctrl+u/d] PageUp/Down | [n/N] Next/Prev change | [o] Script/Fields/Environment | [q] Close ";
+
+        let block = Block::default()
+            .title(format!("{}{}", title, help_text))
+            .borders(Borders::ALL)
+            .border_style(Style::default().fg(Color::Cyan));
+        let inner = block.inner(area);
+        frame.render_widget(block, area);
+
+        let panes = Layout::default()
+            .direction(Direction::Horizontal)
+            .constraints([Constraint::Ratio(1, 2), Constraint::Ratio(1, 2)])
+            .split(inner);
+
+        self.view_height = panes[0].height.saturating_sub(2) as usize;
+        self.scroll_position = self.scroll_position.min(self.max_scroll());
+
+        for (i, (side, pane_area)) in [&self.left, &self.right]
+            .into_iter()
+            .zip(panes.iter())
+            .enumerate()
+        {
+            let is_left = i == 0;
+            let pane_block = Block::default()
+                .title(side.title())
+                .borders(Borders::ALL)
+                .border_style(Style::default().fg(Color::DarkGray));
+
+            let text = match &side.error {
+                Some(error) => Text::styled(error.clone(), Style::default().fg(Color::Yellow)),
+                None => {
+                    let number_width = side.lines.len().to_string().len();
+                    let text_width = pane_area
+                        .width
+                        .saturating_sub(2)
+                        .saturating_sub(number_width as u16 + 1)
+                        as usize;
+                    let change_style = if is_left {
+                        Style::default().fg(Color::Red)
+                    } else {
+                        Style::default().fg(Color::Green)
+                    };
+                    let lines: Vec<Line> = self
+                        .rows
+                        .iter()
+                        .skip(self.scroll_position)
+                        .take(self.view_height)
+                        .map(|row| {
+                            let index = if is_left { row.left } else { row.right };
+                            let Some(index) = index else {
+                                return Line::raw("");
+                            };
+                            let content: String = side.lines[index]
+                                .chars()
+                                .skip(self.horizontal_offset)
+                                .take(text_width)
+                                .collect();
+                            Line::from(vec![
+                                Span::styled(
+                                    format!("{:>width$} ", index + 1, width = number_width),
+                                    Style::default().fg(Color::DarkGray),
+                                ),
+                                Span::styled(
+                                    content,
+                                    if row.changed {
+                                        change_style
+                                    } else {
+                                        Style::default()
+                                    },
+                                ),
+                            ])
+                        })
+                        .collect();
+                    Text::from(lines)
+                }
+            };
+
+            frame.render_widget(Paragraph::new(text).block(pane_block), *pane_area);
+        }
+    }
+}
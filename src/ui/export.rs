@@ -0,0 +1,219 @@
+use chrono::Local;
+use crossterm::event::{KeyCode, KeyEvent};
+use ratatui::{
+    layout::{Position, Rect},
+    style::{Color, Modifier, Style},
+    text::{Line, Span, Text},
+    widgets::{Block, Borders, Clear, Paragraph},
+    Frame,
+};
+
+use crate::utils::export::OutputFormat;
+
+/// Formats offered by the export popup
+const EXPORT_FORMATS: &[OutputFormat] = &[
+    OutputFormat::Csv,
+    OutputFormat::Json,
+    OutputFormat::Markdown,
+];
+
+/// What the export popup asks the app to do after a key press
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ExportAction {
+    None,
+    Close,
+    /// Write the jobs to the file
+    Export,
+}
+
+/// Which jobs are exported
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ExportScope {
+    /// Every job in the list, as filtered and sorted
+    Listed,
+    /// Only the selected jobs, in list order
+    Selected,
+}
+
+/// Popup to write the job list to a file
+pub struct ExportPopup {
+    pub visible: bool,
+    pub format: OutputFormat,
+    pub scope: ExportScope,
+    /// Path typed by the user
+    pub path: String,
+    listed_count: usize,
+    selected_count: usize,
+    /// Titles of the exported columns
+    columns: Vec<String>,
+    /// Why the last export failed
+    pub error: Option<String>,
+}
+
+impl ExportPopup {
+    pub fn new() -> Self {
+        Self {
+            visible: false,
+            format: EXPORT_FORMATS[0],
+            scope: ExportScope::Listed,
+            path: String::new(),
+            listed_count: 0,
+            selected_count: 0,
+            columns: Vec::new(),
+            error: None,
+        }
+    }
+
+    /// Show the popup, exporting the selected jobs by default if there are any
+    pub fn open(&mut self, listed_count: usize, selected_count: usize, columns: Vec<String>) {
+        self.listed_count = listed_count;
+        self.selected_count = selected_count;
+        self.columns = columns;
+        self.scope = if selected_count > 0 {
+            ExportScope::Selected
+        } else {
+            ExportScope::Listed
+        };
+        self.path = format!(
+            "slurmer-jobs-{}.{}",
+            Local::now().format("%Y%m%d-%H%M%S"),
+            self.format.extension()
+        );
+        self.error = None;
+        self.visible = true;
+    }
+
+    pub fn hide(&mut self) {
+        self.visible = false;
+    }
+
+    /// Switch to the next or previous format, following with the extension
+    /// of the path
+    fn cycle_format(&mut self, forward: bool) {
+        let index = EXPORT_FORMATS
+            .iter()
+            .position(|format| *format == self.format)
+            .unwrap_or(0);
+        let index = if forward {
+            (index + 1) % EXPORT_FORMATS.len()
+        } else {
+            (index + EXPORT_FORMATS.len() - 1) % EXPORT_FORMATS.len()
+        };
+
+        let old_extension = format!(".{}", self.format.extension());
+        self.format = EXPORT_FORMATS[index];
+        if let Some(stem) = self.path.strip_suffix(&old_extension) {
+            self.path = format!("{}.{}", stem, self.format.extension());
+        }
+    }
+
+    pub fn handle_key(&mut self, key: KeyEvent) -> ExportAction {
+        match key.code {
+            KeyCode::Esc => ExportAction::Close,
+            KeyCode::Enter => {
+                if self.scope == ExportScope::Selected && self.selected_count == 0 {
+                    self.error = Some("No jobs selected".to_string());
+                    ExportAction::None
+                } else {
+                    ExportAction::Export
+                }
+            }
+            KeyCode::Left => {
+                self.cycle_format(false);
+                ExportAction::None
+            }
+            KeyCode::Right | KeyCode::Tab => {
+                self.cycle_format(true);
+                ExportAction::None
+            }
+            KeyCode::Up | KeyCode::Down => {
+                self.scope = match self.scope {
+                    ExportScope::Listed => ExportScope::Selected,
+                    ExportScope::Selected => ExportScope::Listed,
+                };
+                ExportAction::None
+            }
+            KeyCode::Backspace => {
+                self.path.pop();
+                ExportAction::None
+            }
+            KeyCode::Char(c) => {
+                self.path.push(c);
+                ExportAction::None
+            }
+            _ => ExportAction::None,
+        }
+    }
+
+    pub fn render(&self, frame: &mut Frame, area: Rect) {
+        if !self.visible {
+            return;
+        }
+
+        frame.render_widget(Clear, area);
+
+        let block = Block::default()
+            .title("Export jobs")
+            .borders(Borders::ALL)
+            .border_style(Style::default().fg(Color::Cyan));
+        let inner = block.inner(area);
+
+        let label = |text: &'static str| Span::styled(text, Style::default().fg(Color::Gray));
+        let choice = |text: String, active: bool| {
+            if active {
+                Span::styled(
+                    format!("[{}]", text),
+                    Style::default()
+                        .fg(Color::Cyan)
+                        .add_modifier(Modifier::BOLD),
+                )
+            } else {
+                Span::raw(format!(" {} ", text))
+            }
+        };
+
+        let mut format_line = vec![label("Format:  ")];
+        for format in EXPORT_FORMATS {
+            format_line.push(choice(format.to_string(), *format == self.format));
+            format_line.push(Span::raw(" "));
+        }
+        let scope_line = vec![
+            label("Jobs:    "),
+            choice(
+                format!("Listed ({})", self.listed_count),
+                self.scope == ExportScope::Listed,
+            ),
+            Span::raw(" "),
+            choice(
+                format!("Selected ({})", self.selected_count),
+                self.scope == ExportScope::Selected,
+            ),
+        ];
+
+        let mut lines = vec![
+            Line::from(format_line),
+            Line::from(scope_line),
+            Line::from(vec![label("Columns: "), Span::raw(self.columns.join(", "))]),
+            Line::raw(""),
+            Line::from(vec![label("Path: "), Span::raw(self.path.clone())]),
+            Line::raw(""),
+        ];
+        let path_row = 4;
+        if let Some(error) = &self.error {
+            lines.push(Line::styled(error.clone(), Style::default().fg(Color::Red)));
+        }
+        lines.push(Line::styled(
+            "←/→: Format | ↑/↓: Listed/Selected | Type: Path | Enter: Export | Esc: Cancel",
+            Style::default().fg(Color::Gray),
+        ));
+
+        frame.render_widget(Paragraph::new(Text::from(lines)).block(block), area);
+
+        // Show the cursor at the end of the path
+        let x = inner.x + ("Path: ".len() + self.path.chars().count()) as u16;
+        let y = inner.y + path_row;
+        if x < inner.right() && y < inner.bottom() {
+            frame.set_cursor_position(Position { x, y });
+        }
+    }
+}
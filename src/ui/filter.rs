@@ -1,4 +1,4 @@
-use crossterm::event::KeyModifiers;
+use crossterm::event::{KeyCode, KeyEvent, KeyModifiers, MouseButton, MouseEvent, MouseEventKind};
 use ratatui::{
     layout::{Constraint, Direction, Layout, Position, Rect},
     style::{Color, Modifier, Style},
@@ -8,7 +8,13 @@ use ratatui::{
 };
 use regex::Regex;
 
-use crate::slurm::{squeue::SqueueOptions, JobState};
+use crate::slurm::{fields::SlurmDuration, squeue::SqueueOptions, JobState};
+
+/// Number of tabs: the username, name, node and minimum elapsed time fields,
+/// then the state, partition and QoS lists
+const TAB_COUNT: usize = 7;
+/// Tab index of the first list
+const FIRST_LIST_TAB: usize = 4;
 
 /// Filter popup state
 pub struct FilterPopup {
@@ -34,8 +40,14 @@ pub struct FilterPopup {
     pub name_regex_valid: Option<bool>,
     /// Whether the node regex is valid
     pub node_regex_valid: Option<bool>,
+    /// Minimum elapsed time filter (Slurm duration, e.g. `1:00:00`)
+    pub min_time_filter: String,
+    /// Whether the minimum elapsed time is a valid duration
+    pub min_time_valid: Option<bool>,
     /// If visible
     pub visible: bool,
+    /// Areas of the fields and lists as last rendered, in tab order
+    tab_areas: Vec<Rect>,
 }
 
 /// Which field is currently focused in the filter popup
@@ -47,6 +59,7 @@ pub enum FilterFocus {
     QoS,
     NameFilter,
     NodeFilter,
+    MinTime,
 }
 
 impl FilterPopup {
@@ -73,7 +86,10 @@ impl FilterPopup {
             node_filter: String::new(),
             name_regex_valid: None,
             node_regex_valid: None,
+            min_time_filter: String::new(),
+            min_time_valid: None,
             visible: false,
+            tab_areas: Vec::new(),
         }
     }
 
@@ -96,6 +112,9 @@ impl FilterPopup {
         } else {
             self.node_regex_valid = None;
         }
+
+        self.min_time_filter = options.min_time.map(|t| t.to_string()).unwrap_or_default();
+        self.validate_min_time();
     }
 
     /// Validate the current name regex pattern
@@ -124,6 +143,16 @@ impl FilterPopup {
         }
     }
 
+    /// Validate the current minimum elapsed time
+    fn validate_min_time(&mut self) {
+        if self.min_time_filter.is_empty() {
+            self.min_time_valid = None;
+            return;
+        }
+
+        self.min_time_valid = Some(self.min_time_filter.parse::<SlurmDuration>().is_ok());
+    }
+
     /// Render the filter popup
     pub fn render(
         &mut self,
@@ -168,6 +197,9 @@ impl FilterPopup {
             ])
             .split(inner_area[1]);
 
+        self.tab_areas.truncate(FIRST_LIST_TAB);
+        self.tab_areas.extend(bottom_chunks.iter());
+
         // Render bottom three sections
         self.render_states_tab(frame, bottom_chunks[0], options, all_states);
         self.render_partitions_tab(frame, bottom_chunks[1], options, all_partitions);
@@ -183,16 +215,18 @@ impl FilterPopup {
     }
 
     /// Render the user and name filter tab
-    fn render_user_tab(&self, frame: &mut Frame, area: Rect) {
+    fn render_user_tab(&mut self, frame: &mut Frame, area: Rect) {
         let chunks = Layout::default()
             .direction(Direction::Horizontal)
             .margin(1)
             .constraints([
-                Constraint::Ratio(1, 3), // Username
-                Constraint::Ratio(1, 3), // Job name filter
-                Constraint::Ratio(1, 3), // Node filter
+                Constraint::Ratio(1, 4), // Username
+                Constraint::Ratio(1, 4), // Job name filter
+                Constraint::Ratio(1, 4), // Node filter
+                Constraint::Ratio(1, 4), // Minimum elapsed time
             ])
             .split(area);
+        self.tab_areas = chunks.to_vec();
 
         // Username field
         let username_block = Block::default()
@@ -260,6 +294,28 @@ impl FilterPopup {
 
         frame.render_widget(node_filter_text, chunks[2]);
 
+        // Minimum elapsed time field
+        let min_time_title = match self.min_time_valid {
+            Some(true) => "Min Elapsed ✓",
+            Some(false) => "Min Elapsed ✗ Invalid",
+            None => "Min Elapsed (e.g. 1:00:00)",
+        };
+
+        let min_time_block_style = match (self.focus == FilterFocus::MinTime, self.min_time_valid) {
+            (true, _) => Style::default().fg(Color::Cyan),
+            (false, Some(false)) => Style::default().fg(Color::Red),
+            (false, _) => Style::default(),
+        };
+
+        let min_time_block = Block::default()
+            .title(min_time_title)
+            .borders(Borders::ALL)
+            .style(min_time_block_style);
+
+        let min_time_text = Paragraph::new(self.min_time_filter.clone()).block(min_time_block);
+
+        frame.render_widget(min_time_text, chunks[3]);
+
         // Show cursor when in input mode
         if self.input_mode {
             let cursor_position = match self.focus {
@@ -275,6 +331,10 @@ impl FilterPopup {
                     chunks[2].x + 1 + self.node_filter.len() as u16,
                     chunks[2].y + 1,
                 ),
+                FilterFocus::MinTime => (
+                    chunks[3].x + 1 + self.min_time_filter.len() as u16,
+                    chunks[3].y + 1,
+                ),
                 _ => (0, 0),
             };
 
@@ -442,7 +502,10 @@ impl FilterPopup {
         match key.code {
             KeyCode::Enter => {
                 match self.focus {
-                    FilterFocus::Username | FilterFocus::NameFilter | FilterFocus::NodeFilter => {
+                    FilterFocus::Username
+                    | FilterFocus::NameFilter
+                    | FilterFocus::NodeFilter
+                    | FilterFocus::MinTime => {
                         self.input_mode = true;
                         FilterAction::None
                     }
@@ -560,37 +623,82 @@ impl FilterPopup {
                 FilterAction::None
             }
             KeyCode::Left => {
-                // Change tab
-                if self.tab_index > 0 {
-                    self.tab_index -= 1;
-                    self.update_focus_for_tab();
-                    return FilterAction::None;
-                } else if self.tab_index == 0 {
-                    self.tab_index = 5; // Wrap around to last tab
-                    self.update_focus_for_tab();
-                    return FilterAction::None;
-                } else {
-                    return FilterAction::None; // No change if already at first tab
-                }
+                // Change tab, wrapping around to the last one
+                self.tab_index = (self.tab_index + TAB_COUNT - 1) % TAB_COUNT;
+                self.update_focus_for_tab();
+                FilterAction::None
             }
             KeyCode::Right => {
-                // Change tab
-                if self.tab_index < 5 {
-                    self.tab_index += 1;
-                    self.update_focus_for_tab();
-                    return FilterAction::None;
-                } else if self.tab_index == 5 {
-                    self.tab_index = 0; // Wrap around to first tab
-                    self.update_focus_for_tab();
-                    return FilterAction::None;
-                } else {
-                    return FilterAction::None; // No change if already at last tab
-                }
+                // Change tab, wrapping around to the first one
+                self.tab_index = (self.tab_index + 1) % TAB_COUNT;
+                self.update_focus_for_tab();
+                FilterAction::None
             }
             _ => FilterAction::None,
         }
     }
 
+    /// Handle mouse events: a click focuses a field or list and toggles the
+    /// clicked entry of a list, the wheel moves through the list under it
+    pub fn handle_mouse(
+        &mut self,
+        mouse: MouseEvent,
+        options: &mut SqueueOptions,
+        all_states: &[JobState],
+        all_partitions: &[String],
+        all_qos: &[String],
+    ) {
+        let position = Position::new(mouse.column, mouse.row);
+        let Some(tab_index) = self
+            .tab_areas
+            .iter()
+            .position(|area| area.contains(position))
+        else {
+            return;
+        };
+        let area = self.tab_areas[tab_index];
+
+        let key = match mouse.kind {
+            MouseEventKind::Down(MouseButton::Left) => KeyCode::Enter,
+            // Only the lists scroll
+            MouseEventKind::ScrollUp if tab_index >= FIRST_LIST_TAB => KeyCode::Up,
+            MouseEventKind::ScrollDown if tab_index >= FIRST_LIST_TAB => KeyCode::Down,
+            _ => return,
+        };
+
+        // Leaving a field keeps what was typed, as Enter does
+        if self.input_mode && tab_index != self.tab_index {
+            self.handle_input_mode(KeyEvent::from(KeyCode::Enter), options);
+        }
+        self.tab_index = tab_index;
+        self.update_focus_for_tab();
+
+        if key == KeyCode::Enter {
+            let (list_state, len) = match self.focus {
+                FilterFocus::States => (&mut self.state_list_state, all_states.len()),
+                FilterFocus::Partitions => (&mut self.partition_list_state, all_partitions.len()),
+                FilterFocus::QoS => (&mut self.qos_list_state, all_qos.len()),
+                _ => {
+                    self.input_mode = true;
+                    return;
+                }
+            };
+            // Rows start below the top border
+            let index = list_state.offset() + mouse.row.saturating_sub(area.y + 1) as usize;
+            if mouse.row == area.y || index >= len {
+                return;
+            }
+            list_state.select(Some(index));
+        }
+        self.handle_key(
+            KeyEvent::from(key),
+            options,
+            all_states,
+            all_partitions,
+            all_qos,
+        );
+    }
+
     /// Handle input mode (text editing)
     fn handle_input_mode(
         &mut self,
@@ -630,6 +738,15 @@ impl FilterPopup {
                         }
                         // If invalid, leave the existing filter unchanged
                     }
+                    FilterFocus::MinTime => {
+                        // Only set min_time if the duration is valid or empty
+                        if self.min_time_filter.is_empty() {
+                            options.min_time = None;
+                            self.min_time_valid = None;
+                        } else if let Ok(min_time) = self.min_time_filter.parse() {
+                            options.min_time = Some(min_time);
+                        }
+                    }
                     _ => {}
                 }
                 self.input_mode = false;
@@ -649,6 +766,10 @@ impl FilterPopup {
                         self.node_filter.push(c);
                         self.validate_node_regex();
                     }
+                    FilterFocus::MinTime => {
+                        self.min_time_filter.push(c);
+                        self.validate_min_time();
+                    }
                     _ => {}
                 }
                 FilterAction::None
@@ -667,6 +788,10 @@ impl FilterPopup {
                         let _ = self.node_filter.pop();
                         self.validate_node_regex();
                     }
+                    FilterFocus::MinTime => {
+                        let _ = self.min_time_filter.pop();
+                        self.validate_min_time();
+                    }
                     _ => {}
                 }
                 FilterAction::None
@@ -681,9 +806,10 @@ impl FilterPopup {
             0 => self.focus = FilterFocus::Username,
             1 => self.focus = FilterFocus::NameFilter,
             2 => self.focus = FilterFocus::NodeFilter,
-            3 => self.focus = FilterFocus::States,
-            4 => self.focus = FilterFocus::Partitions,
-            5 => self.focus = FilterFocus::QoS,
+            3 => self.focus = FilterFocus::MinTime,
+            4 => self.focus = FilterFocus::States,
+            5 => self.focus = FilterFocus::Partitions,
+            6 => self.focus = FilterFocus::QoS,
             _ => {}
         }
     }
@@ -0,0 +1,439 @@
+use ratatui::{
+    style::{Color, Modifier, Style},
+    text::{Line, Span},
+};
+use serde::{Deserialize, Serialize};
+
+/// Shell words that start or continue a compound command
+const KEYWORDS: &[&str] = &[
+    "if", "then", "else", "elif", "fi", "case", "esac", "for", "select", "while", "until", "do",
+    "done", "in", "function", "time", "coproc", "!", "[[", "]]", "{", "}",
+];
+
+/// Keywords after which a new command starts
+const COMMAND_KEYWORDS: &[&str] = &[
+    "if", "then", "else", "elif", "while", "until", "do", "time", "!", "{",
+];
+
+/// Color theme of highlighted scripts, chosen to suit the terminal background
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Theme {
+    #[default]
+    Dark,
+    Light,
+}
+
+/// Styles of the kinds of tokens in a script
+#[derive(Debug, Clone, Copy)]
+pub struct Palette {
+    pub line_number: Style,
+    comment: Style,
+    shebang: Style,
+    directive: Style,
+    option: Style,
+    value: Style,
+    keyword: Style,
+    command: Style,
+    string: Style,
+    variable: Style,
+    number: Style,
+    operator: Style,
+}
+
+impl Theme {
+    pub fn palette(self) -> Palette {
+        match self {
+            Theme::Dark => Palette {
+                line_number: Style::default().fg(Color::DarkGray),
+                comment: Style::default().fg(Color::DarkGray),
+                shebang: Style::default()
+                    .fg(Color::DarkGray)
+                    .add_modifier(Modifier::BOLD),
+                directive: Style::default()
+                    .fg(Color::Magenta)
+                    .add_modifier(Modifier::BOLD),
+                option: Style::default().fg(Color::LightCyan),
+                value: Style::default().fg(Color::LightYellow),
+                keyword: Style::default()
+                    .fg(Color::LightBlue)
+                    .add_modifier(Modifier::BOLD),
+                command: Style::default().fg(Color::LightGreen),
+                string: Style::default().fg(Color::Yellow),
+                variable: Style::default().fg(Color::Cyan),
+                number: Style::default().fg(Color::LightMagenta),
+                operator: Style::default().fg(Color::LightRed),
+            },
+            // Solarized accents, readable on a light background
+            Theme::Light => Palette {
+                line_number: Style::default().fg(Color::Rgb(147, 161, 161)),
+                comment: Style::default().fg(Color::Rgb(147, 161, 161)),
+                shebang: Style::default()
+                    .fg(Color::Rgb(147, 161, 161))
+                    .add_modifier(Modifier::BOLD),
+                directive: Style::default()
+                    .fg(Color::Rgb(211, 54, 130))
+                    .add_modifier(Modifier::BOLD),
+                option: Style::default().fg(Color::Rgb(38, 139, 210)),
+                value: Style::default().fg(Color::Rgb(203, 75, 22)),
+                keyword: Style::default()
+                    .fg(Color::Rgb(133, 153, 0))
+                    .add_modifier(Modifier::BOLD),
+                command: Style::default().fg(Color::Rgb(108, 113, 196)),
+                string: Style::default().fg(Color::Rgb(42, 161, 152)),
+                variable: Style::default().fg(Color::Rgb(181, 137, 0)),
+                number: Style::default().fg(Color::Rgb(211, 54, 130)),
+                operator: Style::default().fg(Color::Rgb(220, 50, 47)),
+            },
+        }
+    }
+}
+
+/// Constructs that continue on the next line
+#[derive(Debug, Clone, PartialEq, Eq)]
+enum State {
+    Normal,
+    SingleQuoted,
+    DoubleQuoted,
+    /// Here-document body, ended by a line with the delimiter
+    HereDoc {
+        delimiter: String,
+        strip_tabs: bool,
+    },
+}
+
+/// Highlight a batch script: shell syntax, and `#SBATCH` directives in the
+/// header that sbatch reads (those after the first command are only comments)
+pub fn highlight_script(script: &str, theme: Theme) -> Vec<Line<'static>> {
+    let mut highlighter = Highlighter {
+        palette: theme.palette(),
+        state: State::Normal,
+        heredocs: Vec::new(),
+        in_header: true,
+        spans: Vec::new(),
+    };
+    script
+        .lines()
+        .enumerate()
+        .map(|(i, line)| highlighter.line(line, i == 0))
+        .collect()
+}
+
+struct Highlighter {
+    palette: Palette,
+    state: State,
+    /// Here-documents started on the current line, read after it
+    heredocs: Vec<State>,
+    /// Whether no command has been seen yet, so `#SBATCH` lines are directives
+    in_header: bool,
+    spans: Vec<Span<'static>>,
+}
+
+impl Highlighter {
+    fn line(&mut self, line: &str, first: bool) -> Line<'static> {
+        if let State::HereDoc {
+            delimiter,
+            strip_tabs,
+        } = &self.state
+        {
+            let body = if *strip_tabs {
+                line.trim_start_matches('\t')
+            } else {
+                line
+            };
+            if body == delimiter {
+                self.state = self.heredocs.pop().unwrap_or(State::Normal);
+                self.push(line, self.palette.operator);
+            } else {
+                self.push(line, self.palette.string);
+            }
+            return self.take_line();
+        }
+
+        let trimmed = line.trim_start();
+        if self.state == State::Normal {
+            if first && trimmed.starts_with("#!") {
+                self.push(line, self.palette.shebang);
+                return self.take_line();
+            }
+            if self.in_header && trimmed.starts_with("#SBATCH") {
+                self.directive(line);
+                return self.take_line();
+            }
+            if !trimmed.is_empty() && !trimmed.starts_with('#') {
+                self.in_header = false;
+            }
+        }
+
+        self.shell(line);
+        // Here-documents start on the line after their redirection
+        if self.state == State::Normal && !self.heredocs.is_empty() {
+            self.heredocs.reverse();
+            self.state = self.heredocs.pop().unwrap_or(State::Normal);
+        }
+        self.take_line()
+    }
+
+    /// `#SBATCH --option=value -o value  # comment`
+    fn directive(&mut self, line: &str) {
+        let start = line.find("#SBATCH").unwrap_or(0);
+        self.push(&line[..start], Style::default());
+        self.push("#SBATCH", self.palette.directive);
+
+        let mut rest = &line[start + "#SBATCH".len()..];
+        while !rest.is_empty() {
+            let spaces = rest.len() - rest.trim_start().len();
+            self.push(&rest[..spaces], Style::default());
+            rest = &rest[spaces..];
+
+            if rest.starts_with('#') {
+                self.push(rest, self.palette.comment);
+                break;
+            }
+            let end = rest.find(char::is_whitespace).unwrap_or(rest.len());
+            let word = &rest[..end];
+            rest = &rest[end..];
+
+            if word.starts_with('-') {
+                match word.split_once('=') {
+                    Some((option, value)) => {
+                        self.push(option, self.palette.option);
+                        self.push("=", self.palette.operator);
+                        self.push(value, self.palette.value);
+                    }
+                    None => self.push(word, self.palette.option),
+                }
+            } else {
+                self.push(word, self.palette.value);
+            }
+        }
+    }
+
+    /// Shell syntax, continuing any quote left open on the previous line
+    fn shell(&mut self, line: &str) {
+        let mut rest = line;
+        // A line continuing a quoted string is still in the same command
+        let mut command_position = self.state == State::Normal;
+
+        while !rest.is_empty() {
+            match self.state {
+                State::SingleQuoted => {
+                    let end = match rest.find('\'') {
+                        Some(i) => {
+                            self.state = State::Normal;
+                            i + 1
+                        }
+                        None => rest.len(),
+                    };
+                    self.push(&rest[..end], self.palette.string);
+                    rest = &rest[end..];
+                    continue;
+                }
+                State::DoubleQuoted => {
+                    rest = self.double_quoted(rest);
+                    continue;
+                }
+                _ => {}
+            }
+
+            let c = rest.chars().next().unwrap_or_default();
+            if c.is_whitespace() {
+                let end = rest
+                    .find(|c: char| !c.is_whitespace())
+                    .unwrap_or(rest.len());
+                self.push(&rest[..end], Style::default());
+                rest = &rest[end..];
+            } else if c == '#' {
+                self.push(rest, self.palette.comment);
+                rest = "";
+            } else if c == '\'' {
+                self.state = State::SingleQuoted;
+                self.push("'", self.palette.string);
+                rest = &rest[1..];
+                command_position = false;
+            } else if c == '"' {
+                self.state = State::DoubleQuoted;
+                self.push("\"", self.palette.string);
+                rest = &rest[1..];
+                command_position = false;
+            } else if c == '$' {
+                rest = self.dollar(rest, &mut command_position);
+            } else if let Some(len) = heredoc_redirection(rest, &mut self.heredocs) {
+                self.push(&rest[..len], self.palette.operator);
+                rest = &rest[len..];
+            } else if "|&;()<>`".contains(c) {
+                let end = rest
+                    .find(|c: char| !"|&;()<>".contains(c))
+                    .unwrap_or(rest.len())
+                    .max(c.len_utf8());
+                let operator = &rest[..end];
+                self.push(operator, self.palette.operator);
+                rest = &rest[end..];
+                // A new command follows pipes, lists and subshells
+                command_position = !operator.contains(['<', '>', ')']);
+            } else {
+                let end = rest
+                    .find(|c: char| c.is_whitespace() || "|&;()<>`'\"$".contains(c))
+                    .unwrap_or(rest.len());
+                let word = &rest[..end];
+                rest = &rest[end..];
+                self.word(word, &mut command_position);
+            }
+        }
+    }
+
+    /// Classify a plain word by its position in the command
+    fn word(&mut self, word: &str, command_position: &mut bool) {
+        if *command_position && KEYWORDS.contains(&word) {
+            self.push(word, self.palette.keyword);
+            *command_position = COMMAND_KEYWORDS.contains(&word);
+        } else if *command_position {
+            // Assignments such as `export`-free `VAR=value` keep the command position
+            match word.split_once('=') {
+                Some((name, value))
+                    if !name.is_empty()
+                        && name.chars().all(|c| c.is_ascii_alphanumeric() || c == '_') =>
+                {
+                    self.push(name, self.palette.variable);
+                    self.push("=", self.palette.operator);
+                    self.push(value, Style::default());
+                }
+                _ => {
+                    self.push(word, self.palette.command);
+                    *command_position = false;
+                }
+            }
+        } else if matches!(word, "in" | "do" | "]]") {
+            // `for x in ...; do`, `[[ ... ]]`
+            self.push(word, self.palette.keyword);
+            *command_position = word == "do";
+        } else if word.chars().all(|c| c.is_ascii_digit()) {
+            self.push(word, self.palette.number);
+        } else {
+            self.push(word, Style::default());
+        }
+    }
+
+    /// Text inside double quotes, with its expansions. Returns the rest of the line.
+    fn double_quoted<'a>(&mut self, mut rest: &'a str) -> &'a str {
+        while !rest.is_empty() {
+            let end = rest.find(['"', '$', '\\']).unwrap_or(rest.len());
+            self.push(&rest[..end], self.palette.string);
+            rest = &rest[end..];
+
+            if let Some(after) = rest.strip_prefix('"') {
+                self.push("\"", self.palette.string);
+                self.state = State::Normal;
+                return after;
+            } else if rest.starts_with('\\') {
+                let len = rest[1..].chars().next().map_or(1, |c| 1 + c.len_utf8());
+                self.push(&rest[..len], self.palette.string);
+                rest = &rest[len..];
+            } else if rest.starts_with('$') {
+                let mut command_position = false;
+                rest = self.dollar(rest, &mut command_position);
+            }
+        }
+        rest
+    }
+
+    /// A parameter expansion or the start of a command substitution. Returns
+    /// the rest of the line.
+    fn dollar<'a>(&mut self, rest: &'a str, command_position: &mut bool) -> &'a str {
+        let after = &rest[1..];
+        let (len, style) = if after.starts_with("((") {
+            ("$((".len(), self.palette.operator)
+        } else if let Some(command) = after.strip_prefix('(') {
+            if self.state != State::DoubleQuoted {
+                // `$(command ...)` continues as a command line
+                self.push("$(", self.palette.operator);
+                *command_position = true;
+                return command;
+            }
+            // Inside quotes the whole substitution is one expansion
+            let len = closing_paren(after).map_or(rest.len(), |i| i + 2);
+            (len, self.palette.variable)
+        } else if after.starts_with('{') {
+            let len = after.find('}').map_or(rest.len(), |i| i + 2);
+            (len, self.palette.variable)
+        } else if after.starts_with(|c: char| "@*#?$!-0123456789".contains(c)) {
+            (2, self.palette.variable)
+        } else {
+            let name = after
+                .find(|c: char| !(c.is_ascii_alphanumeric() || c == '_'))
+                .unwrap_or(after.len());
+            // A lone `$` is literal
+            let style = if name == 0 {
+                Style::default()
+            } else {
+                self.palette.variable
+            };
+            (1 + name, style)
+        };
+        self.push(&rest[..len], style);
+        *command_position = false;
+        &rest[len..]
+    }
+
+    fn push(&mut self, text: &str, style: Style) {
+        if text.is_empty() {
+            return;
+        }
+        match self.spans.last_mut() {
+            Some(last) if last.style == style => last.content.to_mut().push_str(text),
+            _ => self.spans.push(Span::styled(text.to_string(), style)),
+        }
+    }
+
+    fn take_line(&mut self) -> Line<'static> {
+        Line::from(std::mem::take(&mut self.spans))
+    }
+}
+
+/// Recognize a here-document redirection (`<<EOF`, `<<-'EOF'`, `<< "EOF"`) at
+/// the start of `rest`, queueing its body. Returns the length of the redirection.
+fn heredoc_redirection(rest: &str, heredocs: &mut Vec<State>) -> Option<usize> {
+    let after = rest.strip_prefix("<<")?;
+    if after.starts_with('<') {
+        // Here-string
+        return None;
+    }
+    let strip_tabs = after.starts_with('-');
+    let after = if strip_tabs { &after[1..] } else { after };
+    let spaces = after.len() - after.trim_start().len();
+    let word = &after[spaces..];
+    let end = word
+        .find(|c: char| c.is_whitespace() || ";|&<>()".contains(c))
+        .unwrap_or(word.len());
+    let delimiter: String = word[..end]
+        .chars()
+        .filter(|c| !"'\"\\".contains(*c))
+        .collect();
+    if delimiter.is_empty() {
+        return None;
+    }
+
+    heredocs.push(State::HereDoc {
+        delimiter,
+        strip_tabs,
+    });
+    Some(rest.len() - word.len() + end)
+}
+
+/// Position of the parenthesis closing the one `text` starts with
+fn closing_paren(text: &str) -> Option<usize> {
+    let mut depth = 0;
+    for (i, c) in text.char_indices() {
+        match c {
+            '(' => depth += 1,
+            ')' => {
+                depth -= 1;
+                if depth == 0 {
+                    return Some(i);
+                }
+            }
+            _ => {}
+        }
+    }
+    None
+}
@@ -3,10 +3,21 @@ use ratatui::{
     layout::Rect,
     style::{Color, Modifier, Style},
     text::{Line, Span, Text},
-    widgets::{Block, Borders, Clear, Paragraph, Wrap},
+    widgets::{Block, Borders, Clear, Paragraph},
     Frame,
 };
-use std::{collections::HashMap, process::Command};
+use std::{collections::HashMap, path::PathBuf, process::Command};
+
+use crate::slurm::batchscript::{fetch_batch_script, ScriptSource};
+use crate::slurm::command::parse_scontrol_output;
+use crate::slurm::sbatch::{parse_directives, summarize, HeaderRow};
+use crate::ui::highlight::{highlight_script, Theme};
+use crate::ui::viewer::{SavePrompt, ViewerAction};
+use crate::utils::external::ExternalProgram;
+use crate::utils::tempfile::TempFile;
+
+/// Width of a tab stop when displaying scripts
+const TAB_WIDTH: usize = 4;
 
 /// JobScript viewer widget for displaying job batch scripts with syntax highlighting
 pub struct JobScript {
@@ -16,12 +27,30 @@ pub struct JobScript {
     pub content: String,
     pub scroll_position: usize,
     pub script_path: Option<String>,
-    pub use_bat: bool, // If bat exists, use it for syntax highlighting
+    /// Where the shown script came from
+    pub source: Option<ScriptSource>,
+    /// Colors of the highlighted script
+    theme: Theme,
+    /// The highlighted script, or the message explaining why it is missing
+    lines: Vec<Line<'static>>,
+    /// Height of the text area when last rendered
+    view_height: usize,
+    /// Number of screen rows of the wrapped text when last rendered
+    total_rows: usize,
+    /// Prompt for saving a copy of the script
+    save_prompt: SavePrompt,
+    /// Job details from `scontrol show job`
+    job_details: HashMap<String, String>,
+    /// `#SBATCH` options of the script, next to what Slurm allocated
+    header: Vec<HeaderRow>,
+    /// Whether the header summary is shown above the script
+    show_header: bool,
+    /// Copy of a script kept by Slurm, for the pager, editor or saving
+    temp_copy: Option<TempFile>,
 }
 
 impl JobScript {
-    pub fn new() -> Self {
-        let use_bat = is_bat_installed();
+    pub fn new(theme: Theme) -> Self {
         Self {
             visible: false,
             job_id: None,
@@ -29,10 +58,24 @@ impl JobScript {
             content: String::new(),
             scroll_position: 0,
             script_path: None,
-            use_bat,
+            source: None,
+            theme,
+            lines: Vec::new(),
+            view_height: 0,
+            total_rows: 0,
+            save_prompt: SavePrompt::default(),
+            job_details: HashMap::new(),
+            header: Vec::new(),
+            show_header: true,
+            temp_copy: None,
         }
     }
 
+    /// Whether the save prompt is taking keyboard input
+    pub fn is_editing(&self) -> bool {
+        self.visible && self.save_prompt.visible
+    }
+
     /// Show the job script view for a specific job
     pub fn show(&mut self, job_id: String, job_name: String) {
         self.change_job(job_id, job_name);
@@ -42,6 +85,7 @@ impl JobScript {
     /// Hide the job script view
     pub fn hide(&mut self) {
         self.visible = false;
+        self.temp_copy = None;
     }
 
     /// Change the job being viewed
@@ -49,7 +93,9 @@ impl JobScript {
         self.job_id = Some(job_id);
         self.job_name = Some(job_name);
         self.script_path = None;
+        self.source = None;
         self.scroll_position = 0;
+        self.temp_copy = None;
 
         // Fetch the script content
         self.fetch_script_content();
@@ -62,37 +108,29 @@ impl JobScript {
         }
     }
 
-    /// Scroll the script view down
+    /// Scroll the script view down, until the last row is on screen
     pub fn scroll_down(&mut self) {
-        // We'll use transformed_lines.len() as an upper bound
-        let approx_max_lines = self.content.lines().count() * 2;
-
-        if self.scroll_position < approx_max_lines {
-            self.scroll_position += 1;
-        }
+        self.scroll_position = (self.scroll_position + 1).min(self.max_scroll());
     }
 
     /// Page up in the script view
     pub fn page_up(&mut self) {
-        // Move up by a page (10 lines)
-        self.scroll_position = self.scroll_position.saturating_sub(10);
+        self.scroll_position = self.scroll_position.saturating_sub(self.view_height.max(1));
     }
 
     /// Page down in the script view
     pub fn page_down(&mut self) {
-        // Move down by a page (10 lines)
-        let approx_max_lines = self.content.lines().count() * 2;
-
-        let new_scroll = self.scroll_position + 10;
-        self.scroll_position = if new_scroll < approx_max_lines {
-            new_scroll
-        } else {
-            approx_max_lines
-        };
+        self.scroll_position =
+            (self.scroll_position + self.view_height.max(1)).min(self.max_scroll());
+    }
+
+    /// Scroll position showing the last row at the bottom
+    fn max_scroll(&self) -> usize {
+        self.total_rows.saturating_sub(self.view_height)
     }
 
     /// Render the job script view
-    pub fn render(&self, frame: &mut Frame, area: Rect) {
+    pub fn render(&mut self, frame: &mut Frame, area: Rect) {
         if !self.visible {
             return;
         }
@@ -108,31 +146,73 @@ impl JobScript {
             None => String::from("null"),
         };
 
-        let title = format!("Job Script for {}/{}", job_name, job_id);
+        let title = match self.source {
+            Some(source) => format!("Job Script for {}/{} (from {})", job_name, job_id, source),
+            None => format!("Job Script for {}/{}", job_name, job_id),
+        };
 
         let help_text =
-            " [↑/↓] Scroll | [Ctrl+u/d] PageUp/Down | [Shift+↑/↓] Toggle Job| [q] Close ";
-
-        // Create text with line numbers if enabled
-        let text = self.create_display_text();
-
-        let script_paragraph = Paragraph::new(text)
-            // .style(Style::default().fg(Color::White))
-            // .style(Style::default().bg(Color::LightCyan))
-            .block(
-                Block::default()
-                    .title(format!("{}{}", title, help_text))
-                    .borders(Borders::ALL)
-                    .border_style(Style::default().fg(Color::Cyan)),
-            )
-            .wrap(Wrap { trim: false })
-            .scroll((self.scroll_position as u16, 0));
+            " [↑/↓] Scroll | [Ctrl+u/d] PageUp/Down | [Shift+↑/↓] Toggle Job | [h] Header | [p/v] Pager/Editor | [w] Save copy | [q] Close ";
+
+        let block = Block::default()
+            .title(format!("{}{}", title, help_text))
+            .borders(Borders::ALL)
+            .border_style(Style::default().fg(Color::Cyan));
+        let inner = block.inner(area);
+
+        // Wrap to the popup width, keeping the line numbers in their own column
+        let mut rows = Vec::new();
+        if self.show_header && !self.header.is_empty() {
+            rows.extend(self.create_header_rows(inner.width as usize));
+        }
+        rows.extend(self.create_display_rows(inner.width as usize));
+        self.view_height = inner.height as usize;
+        self.total_rows = rows.len();
+        self.scroll_position = self.scroll_position.min(self.max_scroll());
+        let text = Text::from(
+            rows.into_iter()
+                .skip(self.scroll_position)
+                .take(self.view_height)
+                .collect::<Vec<_>>(),
+        );
+
+        let script_paragraph = Paragraph::new(text).block(block);
 
         frame.render_widget(script_paragraph, area);
+
+        if self.save_prompt.visible {
+            let prompt_area = Rect {
+                x: area.x + 1,
+                y: area.bottom().saturating_sub(2),
+                width: area.width.saturating_sub(2),
+                height: 1,
+            };
+            self.save_prompt.render(frame, prompt_area);
+        }
     }
 
-    pub fn handle_key(&mut self, key: KeyEvent) -> () {
+    pub fn handle_key(&mut self, key: KeyEvent) -> ViewerAction {
+        if self.save_prompt.visible {
+            return self.save_prompt.handle_key(key);
+        }
+
         match (key.modifiers, key.code) {
+            (_, KeyCode::Char('p')) => {
+                // Open the script in the pager
+                return self.open_external(ExternalProgram::Pager);
+            }
+            (_, KeyCode::Char('v')) => {
+                // Open the script in the editor
+                return self.open_external(ExternalProgram::Editor);
+            }
+            (_, KeyCode::Char('w')) => {
+                // Save a copy of the script
+                return self.save_copy();
+            }
+            (_, KeyCode::Char('h')) => {
+                // Show or hide the #SBATCH summary
+                self.show_header = !self.show_header;
+            }
             (_, KeyCode::Char('q')) => {
                 // Close the script view
                 self.hide();
@@ -157,268 +237,214 @@ impl JobScript {
                 // Ignore other keys
             }
         }
+        ViewerAction::None
     }
 
-    /// Create display text with optional line numbers
-    fn create_display_text(&self) -> Text {
-        if self.use_bat {
-            let lines = parse_ansi_to_spans(&self.content);
-            return Text::from(lines);
+    /// Path of a file with the shown script: the script file when it was read
+    /// from disk, otherwise a temporary copy of the one kept by Slurm. The
+    /// second value tells whether it is the copy.
+    fn script_file(&mut self) -> Result<(PathBuf, bool), String> {
+        match self.source {
+            Some(ScriptSource::File) => self
+                .script_path
+                .as_ref()
+                .map(|path| (PathBuf::from(path), false))
+                .ok_or_else(|| "No script file for this job".to_string()),
+            Some(_) => {
+                let prefix = format!("slurmer-{}", self.job_id.as_deref().unwrap_or("unknown"));
+                let copy = TempFile::create(&prefix, "sh", self.content.as_bytes())
+                    .map_err(|e| format!("Failed to write a copy of the script: {}", e))?;
+                let path = copy.path().to_path_buf();
+                self.temp_copy = Some(copy);
+                Ok((path, true))
+            }
+            None => Err("No script for this job".to_string()),
         }
+    }
 
-        let content_lines: Vec<&str> = self.content.lines().collect();
-        let total_lines = content_lines.len();
-
-        // Calculate the width needed for line numbers
-        let line_num_width = total_lines.to_string().len() + 1; // +1 for the separator
-
-        // Create lines with line numbers
-        let mut numbered_lines: Vec<Line> = Vec::new();
-
-        for (i, line) in content_lines.iter().enumerate() {
-            let line_num = i + 1;
-            let mut spans = Vec::new();
-
-            spans.push(Span::styled(
-                format!("{:>width$} ", line_num, width = line_num_width - 1),
-                Style::default().fg(Color::DarkGray),
-            ));
-
-            spans.push(Span::raw(*line));
-            numbered_lines.push(Line::from(spans));
+    /// Open the script in an external program
+    fn open_external(&mut self, program: ExternalProgram) -> ViewerAction {
+        match self.script_file() {
+            Ok((path, false)) => ViewerAction::Open(program, path),
+            Ok((path, true)) => ViewerAction::OpenCopy(program, path),
+            Err(message) => ViewerAction::Status(message),
         }
+    }
 
-        Text::from(numbered_lines)
+    /// Ask where to save a copy of the script
+    fn save_copy(&mut self) -> ViewerAction {
+        let path = match self.script_file() {
+            Ok((path, _)) => path,
+            Err(message) => return ViewerAction::Status(message),
+        };
+        let default_path = format!(
+            "{}-{}.sh",
+            self.job_name.as_deref().unwrap_or("job"),
+            self.job_id.as_deref().unwrap_or("unknown")
+        );
+        self.save_prompt.open(path, default_path);
+        ViewerAction::None
     }
 
-    /// Fetch the job script content using scontrol
-    fn fetch_script_content(&mut self) {
-        if let Some(job_id) = &self.job_id {
-            // First get job details to find BatchScript path
-            let output = Command::new("scontrol")
-                .args(["show", "job", job_id, "-o"])
-                .output();
-
-            if let Ok(output) = output {
-                if output.status.success() {
-                    let output_str = String::from_utf8_lossy(&output.stdout);
-                    let key_value_pairs = parse_scontrol_output(&output_str);
-
-                    // Get the BatchScript path
-                    if let Some(script_path) = key_value_pairs.get("Command") {
-                        self.script_path = Some(script_path.to_string());
-
-                        if self.use_bat {
-                            // If bat is installed, use it to create a syntax-highlighted version
-                            if let Some(bat_output) = create_bat_out_string(script_path) {
-                                self.content = bat_output;
-                                return;
-                            }
-                        }
-                        // If bat is not available, read the script directly
-                        self.use_bat = false;
-                        // Now read the script content
-                        if let Ok(script_content) = std::fs::read_to_string(script_path) {
-                            self.content = script_content;
-                        } else {
-                            self.content =
-                                format!("Failed to read script from path: {}", script_path);
-                        }
+    /// Screen rows of the `#SBATCH` summary, wrapped to `width`. Options whose
+    /// allocated value differs from the request are highlighted.
+    fn create_header_rows(&self, width: usize) -> Vec<Line<'static>> {
+        let option_width = self
+            .header
+            .iter()
+            .map(|row| row.option.chars().count())
+            .max()
+            .unwrap_or(0);
+        let requested_width = self
+            .header
+            .iter()
+            .map(|row| row.requested.chars().count())
+            .max()
+            .unwrap_or(0)
+            .min(width / 3);
+        let title_style = Style::default()
+            .fg(Color::Cyan)
+            .add_modifier(Modifier::BOLD);
+        let allocated_style = Style::default().fg(Color::DarkGray);
+        let mismatch_style = Style::default()
+            .fg(Color::LightRed)
+            .add_modifier(Modifier::BOLD);
+
+        let mut lines = Vec::new();
+        let mut section = None;
+        for row in &self.header {
+            if section != Some(row.section) {
+                section = Some(row.section);
+                lines.push(Line::styled(row.section.title(), title_style));
+            }
+
+            let mut spans = vec![
+                Span::raw(format!("  {:<width$} ", row.option, width = option_width)),
+                Span::styled(
+                    format!("{:<width$}", row.requested, width = requested_width),
+                    if row.mismatch {
+                        mismatch_style
                     } else {
-                        self.content =
-                            String::from("No script found for this job. Maybe it's wrapped");
-                    }
+                        Style::default()
+                    },
+                ),
+            ];
+            if let Some(allocated) = &row.allocated {
+                if row.mismatch {
+                    spans.push(Span::styled(
+                        format!("  ≠ Slurm: {}", allocated),
+                        mismatch_style,
+                    ));
                 } else {
-                    self.content = String::from("Error retrieving job information");
+                    spans.push(Span::styled(
+                        format!("  Slurm: {}", allocated),
+                        allocated_style,
+                    ));
                 }
-            } else {
-                self.content = String::from("Failed to execute scontrol command");
             }
-        } else {
-            self.content = String::new();
+            lines.push(Line::from(spans));
         }
-    }
-}
+        lines.push(Line::styled("─".repeat(width), allocated_style));
 
-/// Use bat to create a syntax-highlighted version of the script
-fn create_bat_out_string(path: &str) -> Option<String> {
-    let output = Command::new("bat")
-        .arg("--style=numbers,grid")
-        .arg("--color=always")
-        .arg("--theme")
-        .arg("Solarized (light)")
-        .arg("--terminal-width=100")
-        .arg(path)
-        .output();
-    if let Ok(output) = output {
-        if output.status.success() {
-            Some(String::from_utf8_lossy(&output.stdout).to_string())
-        } else {
-            None
-        }
-    } else {
-        None
+        lines
+            .iter()
+            .flat_map(|line| wrap_spans(line, width.max(1)))
+            .map(Line::from)
+            .collect()
     }
-}
-
-/// Parse ANSI escape sequences into ratatui spans
-fn parse_ansi_to_spans(ansi_text: &str) -> Vec<Line> {
-    use regex::Regex;
-
-    // Regex to match ANSI color escape sequences
-    let ansi_escape_re = Regex::new(r"\x1B\[([0-9;]*)m").unwrap();
-    let lines = ansi_text.lines();
-    let mut result_lines = Vec::new();
-
-    let mut current_style = Style::default();
-
-    for line in lines {
-        let mut spans = Vec::new();
-        let mut last_index = 0;
 
-        // Find all ANSI escape sequences in the line
-        for cap in ansi_escape_re.captures_iter(line) {
-            let full_match = cap.get(0).unwrap();
-            let code_match = cap.get(1).unwrap();
-
-            // Get the text before this escape sequence
-            if full_match.start() > last_index {
-                let text = &line[last_index..full_match.start()];
-                spans.push(Span::styled(text, current_style));
-            }
-
-            // Update style based on the ANSI code
-            let code = code_match.as_str();
-            current_style = parse_ansi_code(code, current_style);
-
-            last_index = full_match.end();
-        }
-
-        // Add remaining text after the last escape sequence
-        if last_index < line.len() {
-            let text = &line[last_index..];
-            if !text.is_empty() {
-                spans.push(Span::styled(text, current_style));
+    /// Screen rows of the script with line numbers, wrapped to `width`
+    fn create_display_rows(&self, width: usize) -> Vec<Line<'static>> {
+        let number_width = self.lines.len().to_string().len();
+        let text_width = width.saturating_sub(number_width + 1).max(1);
+        let number_style = self.theme.palette().line_number;
+
+        let mut rows = Vec::new();
+        for (i, line) in self.lines.iter().enumerate() {
+            for (row, spans) in wrap_spans(line, text_width).into_iter().enumerate() {
+                let gutter = if row == 0 {
+                    format!("{:>width$} ", i + 1, width = number_width)
+                } else {
+                    " ".repeat(number_width + 1)
+                };
+                rows.push(Line::from(
+                    std::iter::once(Span::styled(gutter, number_style))
+                        .chain(spans)
+                        .collect::<Vec<_>>(),
+                ));
             }
         }
-
-        // Reset style at end of line
-        current_style = Style::default();
-
-        // Add the line if it has spans
-        if !spans.is_empty() {
-            result_lines.push(Line::from(spans));
-        } else {
-            // Add empty line
-            result_lines.push(Line::default());
-        }
+        rows
     }
 
-    result_lines
-}
-
-/// Convert ANSI color code to ratatui Style
-fn parse_ansi_code(code: &str, mut style: Style) -> Style {
-    // Split by semicolons
-    let parts: Vec<&str> = code.split(';').collect();
-
-    // 创建迭代器以处理连续的参数
-    let mut iter = parts.iter();
-
-    while let Some(&part) = iter.next() {
-        match part {
-            "0" => {
-                // Reset all attributes
-                style = Style::default();
-            }
-            "1" => {
-                // Bold
-                style = style.add_modifier(Modifier::BOLD);
-            }
-            "3" => {
-                // Italic
-                style = style.add_modifier(Modifier::ITALIC);
-            }
-            "4" => {
-                // Underline
-                style = style.add_modifier(Modifier::UNDERLINED);
-            }
-            // 处理 256 色模式的前景色 (38;5;n)
-            "38" => {
-                if let Some(&"5") = iter.next() {
-                    if let Some(&color_idx_str) = iter.next() {
-                        if let Ok(color_idx) = color_idx_str.parse::<u8>() {
-                            style = style.fg(Color::Indexed(color_idx));
-                        }
-                    }
-                }
-            }
-            // 处理 256 色模式的背景色 (48;5;n)
-            "48" => {
-                if let Some(&"5") = iter.next() {
-                    if let Some(&color_idx_str) = iter.next() {
-                        if let Ok(color_idx) = color_idx_str.parse::<u8>() {
-                            style = style.bg(Color::Indexed(color_idx));
-                        }
-                    }
-                }
-            }
-            // 基本 16 色前景色 (30-37, 90-97)
-            s if s.len() <= 3 && s.starts_with("3") => {
-                if let Ok(idx) = s[1..].parse::<u8>() {
-                    style = style.fg(Color::Indexed(idx));
-                }
-            }
-            s if s.len() <= 3 && s.starts_with("9") => {
-                if let Ok(idx) = s[1..].parse::<u8>() {
-                    // 亮色从 ANSI 90-97 映射到 索引 8-15
-                    style = style.fg(Color::Indexed(idx + 8));
-                }
-            }
-            // 基本 16 色背景色 (40-47, 100-107)
-            s if s.len() <= 3 && s.starts_with("4") && s != "48" => {
-                if let Ok(idx) = s[1..].parse::<u8>() {
-                    style = style.bg(Color::Indexed(idx));
-                }
+    /// Fetch the job script content using scontrol
+    fn fetch_script_content(&mut self) {
+        match self.read_script() {
+            Ok(content) => {
+                self.lines =
+                    highlight_script(&content.replace('\t', &" ".repeat(TAB_WIDTH)), self.theme);
+                self.header = summarize(&parse_directives(&content), &self.job_details);
+                self.content = content;
             }
-            s if s.len() <= 4 && s.starts_with("10") => {
-                if let Ok(idx) = s[2..].parse::<u8>() {
-                    // 亮背景色从 ANSI 100-107 映射到 索引 8-15
-                    style = style.bg(Color::Indexed(idx + 8));
-                }
+            Err(message) => {
+                self.lines = vec![Line::raw(message.clone())];
+                self.header.clear();
+                self.content = message;
             }
-            _ => {}
         }
     }
 
-    style
-}
-
-fn parse_scontrol_output(output: &str) -> HashMap<String, String> {
-    let mut result = HashMap::new();
+    /// Read the batch script, or explain why it is not available
+    fn read_script(&mut self) -> Result<String, String> {
+        self.job_details.clear();
+        let Some(job_id) = self.job_id.clone() else {
+            return Ok(String::new());
+        };
 
-    for part in output.split_whitespace() {
-        if let Some(index) = part.find('=') {
-            let key = &part[0..index];
-            let value = &part[(index + 1)..];
-            result.insert(key.to_string(), value.to_string());
+        // Job details, and the script path, while the controller knows the job
+        let output = Command::new("scontrol")
+            .args(["show", "job", &job_id, "-o"])
+            .output()
+            .map_err(|_| String::from("Failed to execute scontrol command"))?;
+        if output.status.success() {
+            let output_str = String::from_utf8_lossy(&output.stdout);
+            self.job_details = parse_scontrol_output(&output_str);
+            self.script_path = self.job_details.get("Command").cloned();
         }
-    }
 
-    result
+        let (script, source) = fetch_batch_script(&job_id, self.script_path.as_deref())
+            .ok_or_else(|| match &self.script_path {
+                Some(path) => format!("Failed to read script from path: {}", path),
+                None => String::from(
+                    "No script found for this job. It may be wrapped, or Slurm does not store batch scripts (AccountingStoreFlags=job_script)",
+                ),
+            })?;
+        self.source = Some(source);
+        Ok(script)
+    }
 }
 
-/// Check if bat is installed on the system
-fn is_bat_installed() -> bool {
-    let output = Command::new("which").arg("bat").output();
-
-    match output {
-        Ok(output) => output.status.success(),
-        Err(_) => {
-            // Try the Windows "where" command as fallback
-            let windows_output = Command::new("where").arg("bat").output();
-
-            matches!(windows_output, Ok(output) if output.status.success())
+/// Split a line into rows of at most `width` characters, keeping span styles
+fn wrap_spans(line: &Line<'static>, width: usize) -> Vec<Vec<Span<'static>>> {
+    let mut rows = vec![Vec::new()];
+    let mut row_len = 0;
+    for span in &line.spans {
+        let mut rest: &str = &span.content;
+        while !rest.is_empty() {
+            if row_len == width {
+                rows.push(Vec::new());
+                row_len = 0;
+            }
+            let take = rest
+                .char_indices()
+                .nth(width - row_len)
+                .map_or(rest.len(), |(i, _)| i);
+            row_len += rest[..take].chars().count();
+            if let Some(row) = rows.last_mut() {
+                row.push(Span::styled(rest[..take].to_string(), span.style));
+            }
+            rest = &rest[take..];
         }
     }
+    rows
 }
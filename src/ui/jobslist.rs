@@ -1,37 +1,79 @@
 use ratatui::{
-    layout::{Constraint, Rect},
+    layout::{Constraint, Flex, Layout, Position, Rect},
     style::{Color, Modifier, Style},
     widgets::{Block, Borders, Cell, Paragraph, Row, Table, TableState},
     Frame,
 };
 
-use crate::slurm::{Job, JobState};
-use crate::ui::columns::{JobColumn, SortColumn};
+use std::collections::HashMap;
+
+use crate::slurm::{fields::TimeFormat, Job, JobState};
+use crate::ui::columns::{compare_jobs, JobColumn, SortColumn};
+
+/// Width of the row highlight symbol
+const HIGHLIGHT_SYMBOL: &str = " ▶ ";
+/// Upper bound for auto-fitted column widths
+const MAX_AUTO_WIDTH: u16 = 50;
+/// Lower bound for manually resized column widths
+const MIN_COLUMN_WIDTH: u16 = 3;
+
+/// Part of the jobs table under the mouse
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum JobsListHit {
+    /// Header of the column at this index
+    Header(usize),
+    /// Row of the job at this index
+    Row(usize),
+}
 
 /// Struct to manage the jobs list view
 pub struct JobsList {
     pub state: TableState,
     pub jobs: Vec<Job>,
     pub selected_jobs: Vec<usize>,
-    pub sort_column: usize,
-    pub sort_ascending: bool,
+    /// Index of the header column focused for sorting
+    pub focused_column: usize,
+    /// How submit/start/end times are displayed
+    pub time_format: TimeFormat,
+    /// Fit column widths to the content of the visible rows
+    pub auto_fit: bool,
+    /// Widths set by the user, overriding auto-fit and the default widths
+    pub column_widths: HashMap<JobColumn, u16>,
+    /// Index of the first column shown when the table is scrolled horizontally
+    column_offset: usize,
+    /// Widths of the columns as last rendered, used as the base for resizing
+    rendered_widths: Vec<u16>,
+    /// Inside of the table border as last rendered
+    table_area: Rect,
+    /// Column index, start and end x of the visible header cells as last rendered
+    header_cells: Vec<(usize, u16, u16)>,
 }
 
 impl JobsList {
-    pub fn new() -> Self {
+    pub fn new(time_format: TimeFormat, auto_fit: bool) -> Self {
         Self {
             state: TableState::default(),
             jobs: Vec::new(),
             selected_jobs: Vec::new(),
-            sort_column: 0, // Default sort by job ID
-            sort_ascending: true,
+            focused_column: 0,
+            time_format,
+            auto_fit,
+            column_widths: HashMap::new(),
+            column_offset: 0,
+            rendered_widths: Vec::new(),
+            table_area: Rect::default(),
+            header_cells: Vec::new(),
         }
     }
 
     /// Update the list of jobs
-    pub fn update_jobs(&mut self, jobs: Vec<Job>) {
+    pub fn update_jobs(&mut self, jobs: Vec<Job>, sort_columns: &[SortColumn]) {
+        // Remember the cursor and selection by ID, as the indices refer to the old list
+        let cursor_id = self.selected_job().map(|job| job.id.clone());
+        let selected_ids = self.get_selected_jobs();
+
         self.jobs = jobs;
-        // Jobs are already sorted by the squeue command
+        self.sort_and_restore(sort_columns, cursor_id, &selected_ids);
 
         // Reset selection if out of bounds
         if let Some(selected) = self.state.selected() {
@@ -69,22 +111,107 @@ impl JobsList {
         self.selected_jobs.clear();
     }
 
-    /// Update sort configuration based on SortColumn settings
-    pub fn update_sort(&mut self, columns: &[JobColumn], sort_columns: &[SortColumn]) {
-        if let Some(first_sort) = sort_columns.first() {
-            // Find the index of the column in the displayed columns list
-            let column_index = columns
+    /// Sort jobs by the sort columns in order, keeping the cursor and
+    /// the selected jobs on the same jobs they pointed to before sorting
+    pub fn sort_jobs(&mut self, sort_columns: &[SortColumn]) {
+        let cursor_id = self.selected_job().map(|job| job.id.clone());
+        let selected_ids = self.get_selected_jobs();
+        self.sort_and_restore(sort_columns, cursor_id, &selected_ids);
+    }
+
+    /// Sort jobs, then move the cursor and selection to the jobs with these IDs
+    fn sort_and_restore(
+        &mut self,
+        sort_columns: &[SortColumn],
+        cursor_id: Option<String>,
+        selected_ids: &[String],
+    ) {
+        self.jobs.sort_by(|a, b| compare_jobs(a, b, sort_columns));
+
+        if let Some(id) = cursor_id {
+            if let Some(i) = self.jobs.iter().position(|job| job.id == id) {
+                self.state.select(Some(i));
+            }
+        }
+        self.selected_jobs = self
+            .jobs
+            .iter()
+            .enumerate()
+            .filter(|(_, job)| selected_ids.contains(&job.id))
+            .map(|(i, _)| i)
+            .collect();
+    }
+
+    /// Move the header focus to the next column
+    pub fn focus_next_column(&mut self, column_count: usize) {
+        if column_count > 0 {
+            self.focused_column = (self.focused_column + 1) % column_count;
+        }
+    }
+
+    /// Move the header focus to the previous column
+    pub fn focus_previous_column(&mut self, column_count: usize) {
+        if column_count > 0 {
+            self.focused_column = (self.focused_column + column_count - 1) % column_count;
+        }
+    }
+
+    /// Widen or narrow the focused column by `delta` characters
+    pub fn resize_focused_column(&mut self, columns: &[JobColumn], delta: i16) {
+        let Some(column) = self.focused_column(columns) else {
+            return;
+        };
+        let index = self.focused_column.min(columns.len() - 1);
+        let current = self
+            .column_widths
+            .get(&column)
+            .or_else(|| self.rendered_widths.get(index))
+            .copied()
+            .unwrap_or(MIN_COLUMN_WIDTH);
+        let width = current.saturating_add_signed(delta).max(MIN_COLUMN_WIDTH);
+        self.column_widths.insert(column, width);
+    }
+
+    /// Drop the user-set width of the focused column
+    pub fn reset_focused_column_width(&mut self, columns: &[JobColumn]) {
+        if let Some(column) = self.focused_column(columns) {
+            self.column_widths.remove(&column);
+        }
+    }
+
+    /// Get the column whose header is focused, if any
+    pub fn focused_column(&self, columns: &[JobColumn]) -> Option<JobColumn> {
+        columns
+            .get(self.focused_column.min(columns.len().saturating_sub(1)))
+            .copied()
+    }
+
+    /// Find the header cell or job row at a screen position
+    pub fn hit_test(&self, column: u16, row: u16) -> Option<JobsListHit> {
+        if !self.table_area.contains(Position::new(column, row)) {
+            return None;
+        }
+        if row == self.table_area.y {
+            return self
+                .header_cells
                 .iter()
-                .position(|col| {
-                    std::mem::discriminant(col) == std::mem::discriminant(&first_sort.column)
-                })
-                .unwrap_or(0);
-
-            self.sort_column = column_index;
-            self.sort_ascending =
-                matches!(first_sort.order, crate::ui::columns::SortOrder::Ascending);
-            // No need to sort jobs as sorting is handled by squeue
+                .find(|(_, start, end)| (*start..*end).contains(&column))
+                .map(|(i, _, _)| JobsListHit::Header(*i));
         }
+        let index = self.state.offset() + (row - self.table_area.y - 1) as usize;
+        (index < self.jobs.len()).then_some(JobsListHit::Row(index))
+    }
+
+    /// Move the cursor by `lines` jobs, stopping at either end of the list
+    pub fn scroll_by(&mut self, lines: isize) {
+        if self.jobs.is_empty() {
+            return;
+        }
+        let current = self.state.selected().unwrap_or(0);
+        let target = current
+            .saturating_add_signed(lines)
+            .min(self.jobs.len() - 1);
+        self.state.select(Some(target));
     }
 
     /// Navigate to next job
@@ -139,41 +266,66 @@ impl JobsList {
         columns: &[JobColumn],
         sort_columns: &[SortColumn],
     ) {
-        // Update sorting if needed based on sort_columns
-        if !sort_columns.is_empty() {
-            self.update_sort(columns, sort_columns);
-        }
-
         // Check if columns are empty, show warning if so
         if columns.is_empty() {
             let warning = Paragraph::new("No columns selected. Press 'c' to configure columns.")
                 .style(Style::default().fg(Color::Yellow))
                 .block(Block::default().title("Warning").borders(Borders::ALL));
             frame.render_widget(warning, area);
+            self.table_area = Rect::default();
             return;
         }
 
-        // Create headers based on selected columns
-        let headers: Vec<&str> = columns.iter().map(|col| col.title()).collect();
+        // Create header texts with the sort indicator, and the sort priority
+        // when more than one column is sorted
+        let focused = self.focused_column.min(columns.len() - 1);
+        let header_texts: Vec<String> = columns
+            .iter()
+            .map(|col| {
+                let sort_indicator = match sort_columns.iter().position(|sc| sc.column == *col) {
+                    Some(pos) => {
+                        let arrow = sort_columns[pos].order.indicator();
+                        if sort_columns.len() > 1 {
+                            format!(" {}{}", arrow, pos + 1)
+                        } else {
+                            format!(" {}", arrow)
+                        }
+                    }
+                    None => String::new(),
+                };
+                format!("{}{}", col.title(), sort_indicator)
+            })
+            .collect();
 
-        // Create header cells with appropriate styling
-        let header_cells = headers.iter().enumerate().map(|(_i, &h)| {
-            // Check if this column is in the sort list
-            let is_sort_column = sort_columns.iter().any(|sc| sc.column.title() == h);
-            let sort_indicator = if is_sort_column {
-                let sort_col = sort_columns
+        // Render every cell once, the texts are also used to fit the widths
+        let cell_texts: Vec<Vec<String>> = self
+            .jobs
+            .iter()
+            .map(|job| {
+                columns
                     .iter()
-                    .find(|sc| sc.column.title() == h)
-                    .unwrap();
-                match sort_col.order {
-                    crate::ui::columns::SortOrder::Ascending => " ↑",
-                    crate::ui::columns::SortOrder::Descending => " ↓",
-                }
-            } else {
-                ""
-            };
+                    .map(|col| col.display(job, self.time_format))
+                    .collect()
+            })
+            .collect();
 
-            let header_style = if is_sort_column {
+        // Space left for the columns: borders, highlight symbol and column spacing
+        let available_width = area
+            .width
+            .saturating_sub(2 + HIGHLIGHT_SYMBOL.chars().count() as u16);
+        let visible_rows = self.state.offset()..self.state.offset() + area.height as usize;
+        let widths = self.column_widths(
+            columns,
+            &header_texts,
+            &cell_texts
+                [visible_rows.start.min(cell_texts.len())..visible_rows.end.min(cell_texts.len())],
+            available_width,
+        );
+        let visible_columns = self.visible_columns(&widths, focused, available_width);
+        self.rendered_widths = widths.clone();
+
+        let header_cells = visible_columns.clone().map(|i| {
+            let mut header_style = if sort_columns.iter().any(|sc| sc.column == columns[i]) {
                 Style::default()
                     .fg(Color::Cyan)
                     .add_modifier(Modifier::BOLD)
@@ -182,8 +334,11 @@ impl JobsList {
                     .fg(Color::White)
                     .add_modifier(Modifier::BOLD)
             };
+            if i == focused {
+                header_style = header_style.add_modifier(Modifier::UNDERLINED);
+            }
 
-            Cell::from(format!("{}{}", h, sort_indicator)).style(header_style)
+            Cell::from(header_texts[i].clone()).style(header_style)
         });
 
         let header = Row::new(header_cells)
@@ -191,120 +346,199 @@ impl JobsList {
             .height(1);
 
         // Create rows for each job
-        let rows = self.jobs.iter().enumerate().map(|(i, job)| {
-            let is_selected = self.selected_jobs.contains(&i);
-            let color = match job.state {
-                JobState::Pending => Color::Yellow,
-                JobState::Running => Color::Green,
-                JobState::Completed => Color::Blue,
-                JobState::Failed | JobState::Timeout | JobState::NodeFail | JobState::Boot => {
-                    Color::Red
-                }
-                JobState::Cancelled => Color::Magenta,
-                _ => Color::White,
-            };
-
-            let style = if is_selected {
-                Style::default().fg(color).add_modifier(Modifier::REVERSED)
-            } else {
-                Style::default().fg(color)
-            };
-
-            // Create cells based on selected columns
-            let cells: Vec<Cell> = columns
-                .iter()
-                .map(|col| {
-                    let content = match col {
-                        JobColumn::Id => job.id.clone(),
-                        JobColumn::Name => {
-                            // Truncate name if too long
-                            if job.name.len() > 30 {
-                                format!("{}...", &job.name[0..27])
-                            } else {
-                                job.name.clone()
-                            }
-                        }
-                        JobColumn::User => job.user.clone(),
-                        JobColumn::State => job.state.to_string(),
-                        JobColumn::Partition => job.partition.clone(),
-                        JobColumn::QoS => job.qos.clone(),
-                        JobColumn::Nodes => job.nodes.to_string(),
-                        JobColumn::Node => job.node.clone().unwrap_or_else(|| "-".to_string()),
-                        JobColumn::CPUs => job.cpus.to_string(),
-                        JobColumn::Time => job.time.clone(),
-                        JobColumn::Memory => job.memory.clone(),
-                        JobColumn::Account => {
-                            job.account.clone().unwrap_or_else(|| "-".to_string())
-                        }
-                        JobColumn::Priority => job
-                            .priority
-                            .map(|p| p.to_string())
-                            .unwrap_or_else(|| "-".to_string()),
-                        JobColumn::WorkDir => {
-                            job.work_dir.clone().unwrap_or_else(|| "-".to_string())
-                        }
-                        JobColumn::SubmitTime => {
-                            job.submit_time.clone().unwrap_or_else(|| "-".to_string())
-                        }
-                        JobColumn::StartTime => {
-                            job.start_time.clone().unwrap_or_else(|| "-".to_string())
-                        }
-                        JobColumn::EndTime => {
-                            job.end_time.clone().unwrap_or_else(|| "-".to_string())
-                        }
-                        JobColumn::PReason => job
-                            .pending_reason
-                            .clone()
-                            .unwrap_or_else(|| "-".to_string()),
-                    };
-                    Cell::from(content)
-                })
-                .collect();
-
-            Row::new(cells).style(style).height(1)
-        });
-
-        // Calculate total available width
-        // let available_width = area.width.saturating_sub(2); // Subtract 2 for borders
-
-        // Get constraints for columns using the default_width method from JobColumn
-        let constraints: Vec<Constraint> = columns
+        let rows = self
+            .jobs
             .iter()
-            .map(|col| {
-                // Use the default_width from JobColumn, but with some specific overrides
-                // for better display in the jobs list context
-                match col {
-                    // Override specific columns that need different constraints in the jobs list
-                    JobColumn::Name => Constraint::Min(15),
-                    JobColumn::WorkDir => Constraint::Min(20),
-                    // For time-related columns, we use a slightly longer constraint
-                    JobColumn::SubmitTime | JobColumn::StartTime | JobColumn::EndTime => {
-                        Constraint::Length(19)
-                    }
-                    // Use the default_width for all other columns
-                    _ => col.default_width(),
+            .zip(cell_texts)
+            .enumerate()
+            .map(|(i, (job, texts))| {
+                let is_selected = self.selected_jobs.contains(&i);
+                let color = match job.state {
+                    JobState::Pending => Color::Yellow,
+                    JobState::Running => Color::Green,
+                    JobState::Completing => Color::Cyan,
+                    JobState::Completed => Color::Blue,
+                    JobState::Failed
+                    | JobState::Timeout
+                    | JobState::OutOfMemory
+                    | JobState::NodeFail
+                    | JobState::Boot => Color::Red,
+                    JobState::Cancelled => Color::Magenta,
+                    _ => Color::White,
+                };
+
+                let style = if is_selected {
+                    Style::default().fg(color).add_modifier(Modifier::REVERSED)
+                } else {
+                    Style::default().fg(color)
+                };
+
+                // Create cells based on the visible columns
+                let cells: Vec<Cell> = texts
+                    .into_iter()
+                    .enumerate()
+                    .filter(|(col, _)| visible_columns.contains(col))
+                    .map(|(_, text)| Cell::from(text))
+                    .collect();
+
+                Row::new(cells).style(style).height(1)
+            });
+
+        // Columns sized by content or by the user get fixed widths, the others
+        // keep the default constraints from the column registry
+        let constraints: Vec<Constraint> = visible_columns
+            .clone()
+            .map(|i| {
+                if self.auto_fit || self.column_widths.contains_key(&columns[i]) {
+                    Constraint::Length(widths[i])
+                } else {
+                    columns[i].default_width()
                 }
             })
             .collect();
 
-        // Create the table
+        // Create the table, noting hidden columns on either side in the title
         let job_count = self.jobs.len();
-        let title = format!("{} Jobs", job_count);
+        let title = if visible_columns.len() < columns.len() {
+            format!(
+                "{} Jobs {}columns {}-{} of {}{}",
+                job_count,
+                if visible_columns.start > 0 {
+                    "◀ "
+                } else {
+                    ""
+                },
+                visible_columns.start + 1,
+                visible_columns.end,
+                columns.len(),
+                if visible_columns.end < columns.len() {
+                    " ▶"
+                } else {
+                    ""
+                },
+            )
+        } else {
+            format!("{} Jobs", job_count)
+        };
+        // Where the table puts each column, for clicks on the header
+        let table_area = Block::default().borders(Borders::ALL).inner(area);
+        let selection_width = if self.state.selected().is_some() {
+            HIGHLIGHT_SYMBOL.chars().count() as u16
+        } else {
+            0
+        };
+        let columns_area = Rect {
+            x: table_area.x + selection_width.min(table_area.width),
+            width: table_area.width.saturating_sub(selection_width),
+            ..table_area
+        };
+        let cell_areas = Layout::horizontal(constraints.clone())
+            .flex(Flex::Start)
+            .spacing(1)
+            .split(columns_area);
+        self.header_cells = visible_columns
+            .clone()
+            .zip(cell_areas.iter())
+            .map(|(i, cell)| (i, cell.x, cell.right()))
+            .collect();
+        self.table_area = table_area;
+
         let table = Table::new(rows, constraints)
             .header(header)
             .block(Block::default().borders(Borders::ALL).title(title))
             .row_highlight_style(Style::default().add_modifier(Modifier::BOLD))
-            .highlight_symbol(" ▶ ");
+            .highlight_symbol(HIGHLIGHT_SYMBOL);
 
         // Render the table
         frame.render_stateful_widget(table, area, &mut self.state);
     }
 
+    /// Work out the width of every column: a user-set width if there is one,
+    /// otherwise the widest visible value when auto-fitting, otherwise the
+    /// registry default resolved against the available width
+    fn column_widths(
+        &self,
+        columns: &[JobColumn],
+        header_texts: &[String],
+        visible_rows: &[Vec<String>],
+        available_width: u16,
+    ) -> Vec<u16> {
+        columns
+            .iter()
+            .enumerate()
+            .map(|(i, col)| {
+                if let Some(width) = self.column_widths.get(col) {
+                    return *width;
+                }
+                if self.auto_fit {
+                    let content = visible_rows
+                        .iter()
+                        .map(|row| row[i].chars().count())
+                        .max()
+                        .unwrap_or(0);
+                    let header = header_texts[i].chars().count();
+                    return (content.max(header) as u16).min(MAX_AUTO_WIDTH);
+                }
+                match col.default_width() {
+                    Constraint::Percentage(p) => available_width * p / 100,
+                    Constraint::Length(n) | Constraint::Min(n) | Constraint::Max(n) => n,
+                    _ => header_texts[i].chars().count() as u16,
+                }
+            })
+            .collect()
+    }
+
+    /// Pick the range of columns to show, scrolling horizontally so that the
+    /// focused column is always visible when the columns do not all fit
+    fn visible_columns(
+        &mut self,
+        widths: &[u16],
+        focused: usize,
+        available_width: u16,
+    ) -> std::ops::Range<usize> {
+        // Total width of a range of columns, including the spacing between them
+        let span = |range: std::ops::Range<usize>| -> u32 {
+            let count = range.len() as u32;
+            widths[range].iter().map(|w| *w as u32).sum::<u32>() + count.saturating_sub(1)
+        };
+
+        if span(0..widths.len()) <= available_width as u32 {
+            self.column_offset = 0;
+            return 0..widths.len();
+        }
+
+        if focused < self.column_offset {
+            self.column_offset = focused;
+        }
+        while self.column_offset < focused
+            && span(self.column_offset..focused + 1) > available_width as u32
+        {
+            self.column_offset += 1;
+        }
+
+        let mut end = self.column_offset + 1;
+        while end < widths.len() && span(self.column_offset..end + 1) <= available_width as u32 {
+            end += 1;
+        }
+        self.column_offset..end
+    }
+
     /// Get the currently selected job, if any
     pub fn selected_job(&self) -> Option<&Job> {
         self.state.selected().and_then(|i| self.jobs.get(i))
     }
 
+    /// Move the cursor to the job with the given ID, if it is listed
+    pub fn select_job_id(&mut self, id: &str) -> bool {
+        match self.jobs.iter().position(|job| job.id == id) {
+            Some(i) => {
+                self.state.select(Some(i));
+                true
+            }
+            None => false,
+        }
+    }
+
     /// Get all selected jobs
     pub fn get_selected_jobs(&self) -> Vec<String> {
         self.selected_jobs
@@ -7,6 +7,19 @@ use ratatui::{
 };
 use std::time::Duration;
 
+use crate::utils::{format_duration, format_memory};
+
+/// Summary statistics of the listed jobs shown in the footer
+pub struct JobStats {
+    pub pending: usize,
+    pub running: usize,
+    pub other: usize,
+    /// Total memory allocated to running jobs, in megabytes
+    pub running_memory_mb: u64,
+    /// Longest elapsed time among running jobs, in seconds
+    pub max_elapsed: Option<u64>,
+}
+
 /// Defines the main layout of the application
 pub fn draw_main_layout(frame: &mut Frame) -> Vec<Rect> {
     let size = frame.area();
@@ -69,7 +82,7 @@ pub fn draw_header(
 }
 
 /// Draws the application footer with help text and status
-pub fn draw_footer(frame: &mut Frame, area: Rect, job_stat: (usize, usize, usize)) {
+pub fn draw_footer(frame: &mut Frame, area: Rect, job_stat: &JobStats) {
     // Controls help (lower part of footer)
     let color_style = Style::default().fg(Color::Cyan);
     let text_hashmap = [
@@ -79,6 +92,8 @@ pub fn draw_footer(frame: &mut Frame, area: Rect, job_stat: (usize, usize, usize
         ("Enter", "Script"),
         ("f", "Filter"),
         ("c", "Columns"),
+        ("←/→/s", "Sort"),
+        ("</>", "Width"),
         ("v", "Log"),
         ("a", "SelectAll"),
         ("r", "Refresh"),
@@ -99,17 +114,29 @@ pub fn draw_footer(frame: &mut Frame, area: Rect, job_stat: (usize, usize, usize
 
     footer_text.push(Span::styled("Job Stat: ", Style::default().fg(Color::Cyan)));
     footer_text.push(Span::styled(
-        format!("P[ {} ] ", job_stat.0),
+        format!("P[ {} ] ", job_stat.pending),
         Style::default().fg(Color::Yellow),
     ));
     footer_text.push(Span::styled(
-        format!("R[ {} ] ", job_stat.1),
+        format!("R[ {} ] ", job_stat.running),
         Style::default().fg(Color::Green),
     ));
     footer_text.push(Span::styled(
-        format!("Other[ {} ]", job_stat.2),
+        format!("Other[ {} ]", job_stat.other),
         Style::default().fg(Color::Blue),
     ));
+    if job_stat.running_memory_mb > 0 {
+        footer_text.push(Span::styled(
+            format!(" Mem[ {} ]", format_memory(job_stat.running_memory_mb)),
+            Style::default().fg(Color::Green),
+        ));
+    }
+    if let Some(max_elapsed) = job_stat.max_elapsed {
+        footer_text.push(Span::styled(
+            format!(" Longest[ {} ]", format_duration(max_elapsed)),
+            Style::default().fg(Color::Green),
+        ));
+    }
 
     let footer =
         Paragraph::new(Line::from(footer_text)).block(Block::default().borders(Borders::ALL));
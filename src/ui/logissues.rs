@@ -0,0 +1,155 @@
+use color_eyre::{eyre::WrapErr, Result};
+use ratatui::style::{Color, Modifier, Style};
+use regex::Regex;
+
+use crate::ui::logsearch::LogSearch;
+use crate::utils::log_index::SharedLogIndex;
+
+/// How serious a flagged line is
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Severity {
+    Error,
+    Warning,
+}
+
+impl Severity {
+    /// Style a flagged line is drawn with, over its own colors
+    pub fn style(&self) -> Style {
+        match self {
+            Severity::Error => Style::default().fg(Color::White).bg(Color::Red),
+            Severity::Warning => Style::default()
+                .fg(Color::Yellow)
+                .add_modifier(Modifier::BOLD),
+        }
+    }
+}
+
+/// The configured error and warning patterns, each combined into one regex
+#[derive(Debug, Clone, Default)]
+pub struct IssuePatterns {
+    errors: Option<Regex>,
+    warnings: Option<Regex>,
+}
+
+impl IssuePatterns {
+    pub fn new(errors: &[String], warnings: &[String]) -> Result<Self> {
+        Ok(Self {
+            errors: combine(errors)?,
+            warnings: combine(warnings)?,
+        })
+    }
+}
+
+/// Check each pattern on its own, for a precise error, then match any of them
+fn combine(patterns: &[String]) -> Result<Option<Regex>> {
+    for pattern in patterns {
+        Regex::new(pattern).wrap_err_with(|| format!("Invalid log pattern {:?}", pattern))?;
+    }
+    if patterns.is_empty() {
+        return Ok(None);
+    }
+
+    let combined = patterns
+        .iter()
+        .map(|pattern| format!("(?:{})", pattern))
+        .collect::<Vec<_>>()
+        .join("|");
+    Regex::new(&combined)
+        .map(Some)
+        .wrap_err("Invalid log patterns")
+}
+
+/// Lines of a log matching the error and warning patterns, found
+/// incrementally as the log grows by the same scan workers as the search
+#[derive(Debug, Default)]
+pub struct LogIssues {
+    errors: Option<LogSearch>,
+    warnings: Option<LogSearch>,
+    /// Error line last jumped to
+    current_error: Option<usize>,
+}
+
+impl LogIssues {
+    pub fn new(patterns: &IssuePatterns) -> Self {
+        Self {
+            errors: patterns.errors.clone().map(LogSearch::with_regex),
+            warnings: patterns.warnings.clone().map(LogSearch::with_regex),
+            current_error: None,
+        }
+    }
+
+    /// Scan the output added since the last update, in the background
+    pub fn update(&mut self, log: &SharedLogIndex) {
+        for scan in [&mut self.errors, &mut self.warnings].into_iter().flatten() {
+            scan.update(log);
+        }
+    }
+
+    /// Take the flagged lines found since the last poll
+    pub fn poll(&mut self) {
+        for scan in [&mut self.errors, &mut self.warnings].into_iter().flatten() {
+            scan.poll(None);
+        }
+    }
+
+    /// Number of lines flagged as errors
+    pub fn error_count(&self) -> usize {
+        self.errors.as_ref().map_or(0, LogSearch::matched_lines)
+    }
+
+    /// Number of lines flagged as warnings, including those that are also errors
+    pub fn warning_count(&self) -> usize {
+        self.warnings.as_ref().map_or(0, LogSearch::matched_lines)
+    }
+
+    /// How serious a line is, errors taking precedence over warnings
+    pub fn severity(&self, line: usize) -> Option<Severity> {
+        let flagged = |scan: &Option<LogSearch>| {
+            scan.as_ref()
+                .is_some_and(|scan| !scan.line_matches(line).is_empty())
+        };
+        if flagged(&self.errors) {
+            Some(Severity::Error)
+        } else if flagged(&self.warnings) {
+            Some(Severity::Warning)
+        } else {
+            None
+        }
+    }
+
+    /// Move to the next error line, wrapping around, and return it
+    pub fn next_error(&mut self) -> Option<usize> {
+        let line = self.errors.as_ref()?.next_line(self.current_error)?;
+        self.current_error = Some(line);
+        Some(line)
+    }
+
+    /// Move to the previous error line, wrapping around, and return it
+    pub fn previous_error(&mut self) -> Option<usize> {
+        let line = self.errors.as_ref()?.previous_line(self.current_error)?;
+        self.current_error = Some(line);
+        Some(line)
+    }
+
+    /// Summary such as ` [2 errors, 1 warning]`, empty if nothing was flagged
+    pub fn summary(&self) -> String {
+        summarize(self.error_count(), self.warning_count())
+    }
+}
+
+/// Summary of error and warning counts for a title
+pub fn summarize(errors: usize, warnings: usize) -> String {
+    let plural = |count: usize, noun: &str| {
+        format!("{} {}{}", count, noun, if count == 1 { "" } else { "s" })
+    };
+    match (errors, warnings) {
+        (0, 0) => String::new(),
+        (errors, 0) => format!(" [{}]", plural(errors, "error")),
+        (0, warnings) => format!(" [{}]", plural(warnings, "warning")),
+        (errors, warnings) => format!(
+            " [{}, {}]",
+            plural(errors, "error"),
+            plural(warnings, "warning")
+        ),
+    }
+}
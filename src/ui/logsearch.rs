@@ -0,0 +1,633 @@
+use crossbeam::channel::{unbounded, Receiver, Sender};
+use ratatui::{
+    style::{Color, Modifier, Style},
+    text::Span,
+};
+use regex::{Regex, RegexBuilder};
+use std::{
+    ops::Range,
+    path::Path,
+    thread,
+    time::{Duration, Instant},
+};
+
+use crate::utils::{
+    ansi,
+    log_index::{self, SharedLogIndex},
+};
+
+/// Size of the reads when searching the log file
+const SEARCH_CHUNK: usize = 4 * 1024 * 1024;
+/// How long the query has to stay unchanged before it is searched
+const SEARCH_DELAY: Duration = Duration::from_millis(200);
+
+/// A regex match in the log, located on a displayed line
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct SearchMatch {
+    /// Index of the line, as split by `str::lines`
+    pub line: usize,
+    /// Byte range of the match within the displayed part of the line
+    pub start: usize,
+    pub end: usize,
+}
+
+/// Incremental regex search over a log file.
+///
+/// The file only grows while a job writes its log, so matches are kept and
+/// only the lines added since the last update are scanned. Scans run on a
+/// worker thread, and the matches it found so far are shown.
+#[derive(Debug, Default)]
+pub struct LogSearch {
+    /// Query being typed or last confirmed
+    pub input: String,
+    /// Whether the search prompt has keyboard focus
+    pub editing: bool,
+    regex: Option<Regex>,
+    /// Error message for an invalid pattern
+    pub error: Option<String>,
+    matches: Vec<SearchMatch>,
+    /// Index of the current match in `matches`
+    current: usize,
+    /// When the query was last edited, if it wasn't searched yet
+    edited: Option<Instant>,
+    /// Whether to show the first match once it is found
+    jump_pending: bool,
+    /// Worker scanning the log
+    worker: Option<ScanWorker>,
+    /// Whether the worker hasn't reached the end of the log yet
+    scanning: bool,
+}
+
+/// What a scan reports to the search
+#[derive(Debug)]
+enum ScanMessage {
+    /// Matches from `first_line` on, replacing those found before on these lines
+    Matches {
+        first_line: usize,
+        matches: Vec<SearchMatch>,
+    },
+    /// The scan reached the end of the log
+    Done,
+}
+
+/// A thread scanning a log, woken up when the log grows. It stops once the
+/// search drops it.
+#[derive(Debug)]
+struct ScanWorker {
+    wake: Sender<()>,
+    messages: Receiver<ScanMessage>,
+}
+
+impl ScanWorker {
+    fn spawn(regex: Regex, log: SharedLogIndex) -> Self {
+        let (wake, wake_receiver) = unbounded::<()>();
+        let (sender, messages) = unbounded();
+        thread::spawn(move || {
+            let mut scanner = Scanner::new(regex);
+            loop {
+                // Only the size is read under the lock, the file is read without it
+                let Ok((path, len)) = log
+                    .lock()
+                    .map(|index| (index.path().to_path_buf(), index.len()))
+                else {
+                    return;
+                };
+                if !scanner.scan(&path, len, &mut |message| sender.send(message).is_ok())
+                    || sender.send(ScanMessage::Done).is_err()
+                    || wake_receiver.recv().is_err()
+                {
+                    return;
+                }
+                // Output reported while scanning is covered by the next scan
+                while wake_receiver.try_recv().is_ok() {}
+            }
+        });
+        Self { wake, messages }
+    }
+}
+
+/// Position of a scan in the log file
+#[derive(Debug)]
+struct Scanner {
+    regex: Regex,
+    /// Byte offset of the first line not yet fully scanned
+    scanned_bytes: usize,
+    /// Line index at `scanned_bytes`
+    scanned_lines: usize,
+    /// Start of a line longer than a chunk, read up to `scanned_bytes`. Only
+    /// what is displayed of it is kept, and it is searched once it ends.
+    long_line: Option<Vec<u8>>,
+}
+
+impl Scanner {
+    fn new(regex: Regex) -> Self {
+        Self {
+            regex,
+            scanned_bytes: 0,
+            scanned_lines: 0,
+            long_line: None,
+        }
+    }
+
+    /// Scan the file up to `len` bytes, from where the last scan stopped, and
+    /// report the matches a chunk at a time. A shrunken file is scanned again
+    /// from the start. Returns false once `report` fails.
+    fn scan(
+        &mut self,
+        path: &Path,
+        len: u64,
+        report: &mut impl FnMut(ScanMessage) -> bool,
+    ) -> bool {
+        let len = len as usize;
+        if len < self.scanned_bytes {
+            self.scanned_bytes = 0;
+            self.scanned_lines = 0;
+            self.long_line = None;
+            if !report(ScanMessage::Matches {
+                first_line: 0,
+                matches: Vec::new(),
+            }) {
+                return false;
+            }
+        }
+
+        while self.scanned_bytes < len {
+            let max = SEARCH_CHUNK.min(len - self.scanned_bytes);
+            let Ok(chunk) = log_index::read_bytes(path, self.scanned_bytes as u64, max) else {
+                break;
+            };
+            if chunk.is_empty() {
+                break;
+            }
+
+            let first_line = self.scanned_lines;
+            let at_end = self.scanned_bytes + chunk.len() >= len;
+            let newline = chunk.iter().position(|b| *b == b'\n');
+
+            // A line longer than a chunk is kept until it ends, then searched
+            // as displayed, so that matches across reads are found and located
+            if newline.is_none() && !at_end {
+                let line = self.long_line.get_or_insert_with(Vec::new);
+                line.extend_from_slice(&chunk);
+                keep_displayed(line);
+                self.scanned_bytes += chunk.len();
+                continue;
+            }
+            if let Some(mut line) = self.long_line.take() {
+                let end = newline.unwrap_or(chunk.len());
+                line.extend_from_slice(&chunk[..end]);
+                let text = log_index::display_line(&line);
+                let matches = self.find(&ansi::strip(&text), first_line);
+                if newline.is_some() {
+                    self.scanned_lines += 1;
+                    self.scanned_bytes += end + 1;
+                } else {
+                    // The end of the file is searched again on the next update
+                    line.truncate(line.len() - end);
+                    self.long_line = Some(line);
+                }
+                if !report(ScanMessage::Matches {
+                    first_line,
+                    matches,
+                }) {
+                    return false;
+                }
+                if newline.is_none() {
+                    break;
+                }
+                continue;
+            }
+
+            // Scan whole lines; the end of the file is scanned but rescanned on
+            // the next update, since more of its line may be written
+            let whole_lines = chunk
+                .iter()
+                .rposition(|b| *b == b'\n')
+                .map_or(0, |pos| pos + 1);
+
+            // Matches are located in the text as displayed, without escape codes
+            let text = String::from_utf8_lossy(&chunk[..whole_lines]);
+            let mut matches = self.find(&ansi::strip(&text), first_line);
+            self.scanned_lines += chunk[..whole_lines].iter().filter(|b| **b == b'\n').count();
+            self.scanned_bytes += whole_lines;
+
+            if at_end {
+                let rest = String::from_utf8_lossy(&chunk[whole_lines..]);
+                matches.extend(self.find(&ansi::strip(&rest), self.scanned_lines));
+            }
+            if !report(ScanMessage::Matches {
+                first_line,
+                matches,
+            }) {
+                return false;
+            }
+            if at_end {
+                break;
+            }
+        }
+        true
+    }
+
+    /// Matches in `text`, which starts at the beginning of `first_line`
+    fn find(&self, text: &str, first_line: usize) -> Vec<SearchMatch> {
+        let mut matches = Vec::new();
+        let mut line = first_line;
+        let mut line_start = 0;
+        let mut line_end = None;
+        for m in self.regex.find_iter(text) {
+            let (start, end) = (m.start(), m.end());
+            if start == end {
+                continue;
+            }
+
+            // Move to the line containing the start of the match
+            let skipped = &text[line_start..start];
+            if let Some(pos) = skipped.rfind('\n') {
+                line += skipped.bytes().filter(|b| *b == b'\n').count();
+                line_start += pos + 1;
+                line_end = None;
+            }
+            let end_of_line = *line_end.get_or_insert_with(|| {
+                text[line_start..]
+                    .find('\n')
+                    .map_or(text.len(), |i| line_start + i)
+            });
+            if end > end_of_line {
+                continue;
+            }
+
+            // Only the text after the last carriage return is displayed
+            let line_text = &text[line_start..end_of_line];
+            let line_text = line_text.strip_suffix('\r').unwrap_or(line_text);
+            let visible_start = line_text.rfind('\r').map_or(0, |i| i + 1);
+            let (start, end) = (start - line_start, end - line_start);
+            if start < visible_start || end > line_text.len() {
+                continue;
+            }
+
+            matches.push(SearchMatch {
+                line,
+                start: start - visible_start,
+                end: end - visible_start,
+            });
+        }
+        matches
+    }
+}
+
+/// Drop what a terminal would not show of the start of a line: whatever
+/// comes before a carriage return, and what goes past the displayed length.
+/// A carriage return at the end is kept, as it may end the line.
+fn keep_displayed(line: &mut Vec<u8>) {
+    let body = &line[..line.len().saturating_sub(1)];
+    if let Some(pos) = body.iter().rposition(|b| *b == b'\r') {
+        line.drain(..=pos);
+    }
+    let trailing_cr = line.last() == Some(&b'\r');
+    if line.len() > log_index::MAX_LINE_BYTES + 1 {
+        line.truncate(log_index::MAX_LINE_BYTES + 1);
+        if trailing_cr {
+            line.push(b'\r');
+        }
+    }
+}
+
+impl LogSearch {
+    /// A search for a fixed pattern, without a prompt
+    pub fn with_regex(regex: Regex) -> Self {
+        Self {
+            regex: Some(regex),
+            ..Self::default()
+        }
+    }
+
+    /// Open the search prompt, keeping the previous query for editing
+    pub fn start(&mut self) {
+        self.editing = true;
+    }
+
+    /// Close the prompt and drop the search
+    pub fn clear(&mut self) {
+        *self = Self::default();
+    }
+
+    /// Whether a pattern is active
+    pub fn is_active(&self) -> bool {
+        self.regex.is_some()
+    }
+
+    /// Whether the log is still being searched
+    pub fn is_scanning(&self) -> bool {
+        self.scanning
+    }
+
+    /// Change the query as it is typed. It is searched once it stays
+    /// unchanged for a moment, or when the search is confirmed.
+    pub fn edit_query(&mut self, query: String) {
+        self.input = query;
+        self.edited = Some(Instant::now());
+    }
+
+    /// Search the edited query right away
+    pub fn confirm(&mut self, log: Option<&SharedLogIndex>) {
+        if self.edited.is_some() {
+            self.set_query(log);
+        }
+    }
+
+    /// Compile the query and search the whole content again. Patterns without
+    /// uppercase letters are matched case-insensitively.
+    fn set_query(&mut self, log: Option<&SharedLogIndex>) {
+        self.edited = None;
+        self.regex = None;
+        self.error = None;
+        self.reset_matches();
+
+        if self.input.is_empty() {
+            return;
+        }
+
+        match RegexBuilder::new(&self.input)
+            .case_insensitive(!self.input.chars().any(char::is_uppercase))
+            .build()
+        {
+            Ok(regex) => {
+                self.regex = Some(regex);
+                self.jump_pending = true;
+                if let Some(log) = log {
+                    self.update(log);
+                }
+            }
+            Err(e) => {
+                // Keep only the last line, the others repeat the pattern
+                let message = e.to_string();
+                self.error = Some(message.lines().last().unwrap_or("").trim().to_string());
+            }
+        }
+    }
+
+    /// Search the output added to the log since the last update, on the worker
+    pub fn update(&mut self, log: &SharedLogIndex) {
+        let Some(regex) = &self.regex else {
+            return;
+        };
+        match &self.worker {
+            Some(worker) => {
+                let _ = worker.wake.send(());
+            }
+            None => self.worker = Some(ScanWorker::spawn(regex.clone(), log.clone())),
+        }
+        self.scanning = true;
+    }
+
+    /// Search the query once it is no longer being edited, and take the
+    /// matches the worker found since the last poll
+    pub fn poll(&mut self, log: Option<&SharedLogIndex>) {
+        if self
+            .edited
+            .is_some_and(|edited| edited.elapsed() >= SEARCH_DELAY)
+        {
+            self.set_query(log);
+        }
+
+        let messages: Vec<ScanMessage> = match &self.worker {
+            Some(worker) => worker.messages.try_iter().collect(),
+            None => return,
+        };
+        for message in messages {
+            self.receive(message);
+        }
+    }
+
+    fn receive(&mut self, message: ScanMessage) {
+        match message {
+            ScanMessage::Matches {
+                first_line,
+                matches,
+            } => {
+                let keep = self.matches.partition_point(|m| m.line < first_line);
+                self.matches.truncate(keep);
+                self.matches.extend(matches);
+                self.current = self.current.min(self.matches.len().saturating_sub(1));
+            }
+            ScanMessage::Done => self.scanning = false,
+        }
+    }
+
+    /// The line of the first match of a new query, once it is found
+    pub fn take_first_match(&mut self) -> Option<usize> {
+        if !self.jump_pending {
+            return None;
+        }
+        if self.matches.is_empty() {
+            // Give up once the whole log was searched
+            self.jump_pending = self.scanning || self.worker.is_none();
+            return None;
+        }
+        self.jump_pending = false;
+        self.first()
+    }
+
+    /// Forget the matches so the next update searches from the start, used
+    /// when switching to another log file
+    pub fn reset_matches(&mut self) {
+        self.matches.clear();
+        self.current = 0;
+        self.worker = None;
+        self.scanning = false;
+    }
+
+    /// Number of matches found
+    pub fn match_count(&self) -> usize {
+        self.matches.len()
+    }
+
+    /// The current match, if any
+    pub fn current_match(&self) -> Option<&SearchMatch> {
+        self.matches.get(self.current)
+    }
+
+    /// Position of the current match, counted from 1
+    pub fn current_index(&self) -> Option<usize> {
+        self.current_match().map(|_| self.current + 1)
+    }
+
+    /// Make the first match current and return its line
+    pub fn first(&mut self) -> Option<usize> {
+        self.current = 0;
+        self.current_match().map(|m| m.line)
+    }
+
+    /// Move to the next match, wrapping around, and return its line
+    pub fn next(&mut self) -> Option<usize> {
+        if self.matches.is_empty() {
+            return None;
+        }
+        self.current = (self.current + 1) % self.matches.len();
+        self.current_match().map(|m| m.line)
+    }
+
+    /// Move to the previous match, wrapping around, and return its line
+    pub fn previous(&mut self) -> Option<usize> {
+        if self.matches.is_empty() {
+            return None;
+        }
+        self.current = self
+            .current
+            .checked_sub(1)
+            .unwrap_or(self.matches.len() - 1);
+        self.current_match().map(|m| m.line)
+    }
+
+    /// Number of distinct lines with a match
+    pub fn matched_lines(&self) -> usize {
+        self.matches
+            .iter()
+            .enumerate()
+            .filter(|(i, m)| *i == 0 || self.matches[i - 1].line != m.line)
+            .count()
+    }
+
+    /// First line with a match after `line` (or from the start if None),
+    /// wrapping around
+    pub fn next_line(&self, line: Option<usize>) -> Option<usize> {
+        let start = line.map_or(0, |line| self.matches.partition_point(|m| m.line <= line));
+        self.matches
+            .get(start)
+            .or_else(|| self.matches.first())
+            .map(|m| m.line)
+    }
+
+    /// Last line with a match before `line` (or from the end if None),
+    /// wrapping around
+    pub fn previous_line(&self, line: Option<usize>) -> Option<usize> {
+        let end = line.map_or(self.matches.len(), |line| {
+            self.matches.partition_point(|m| m.line < line)
+        });
+        end.checked_sub(1)
+            .and_then(|i| self.matches.get(i))
+            .or_else(|| self.matches.last())
+            .map(|m| m.line)
+    }
+
+    /// Matches on the given line, ordered by position
+    pub fn line_matches(&self, line: usize) -> &[SearchMatch] {
+        let start = self.matches.partition_point(|m| m.line < line);
+        let end = self.matches.partition_point(|m| m.line <= line);
+        &self.matches[start..end]
+    }
+
+    /// Split a chunk of a line into spans, keeping the line's own styles and
+    /// highlighting the matches it overlaps. `offset` is the byte offset of the
+    /// chunk within the line, and `runs` the styled ranges of the whole line.
+    /// `line` is None for lines of a log that is not being searched.
+    pub fn highlight(
+        &self,
+        line: Option<usize>,
+        chunk: &str,
+        offset: usize,
+        runs: &[(Range<usize>, Style)],
+    ) -> Vec<Span<'static>> {
+        let matches = line.map_or(&[][..], |line| self.line_matches(line));
+        let chunk_end = offset + chunk.len();
+
+        // Cut the chunk wherever a style run or a match starts or ends
+        let mut cuts: Vec<usize> = runs
+            .iter()
+            .flat_map(|(range, _)| [range.start, range.end])
+            .chain(matches.iter().flat_map(|m| [m.start, m.end]))
+            // Matches located in an older version of the line may fall inside a character
+            .filter(|pos| (offset..chunk_end).contains(pos) && chunk.is_char_boundary(pos - offset))
+            .chain([offset, chunk_end])
+            .collect();
+        cuts.sort_unstable();
+        cuts.dedup();
+
+        let match_style = Style::default().fg(Color::Black).bg(Color::Yellow);
+        let current_style = Style::default()
+            .fg(Color::Black)
+            .bg(Color::LightRed)
+            .add_modifier(Modifier::BOLD);
+        let current = self.current_match();
+
+        cuts.windows(2)
+            .map(|w| {
+                let (start, end) = (w[0], w[1]);
+                let mut style = runs
+                    .iter()
+                    .find(|(range, _)| range.contains(&start))
+                    .map_or_else(Style::default, |(_, style)| *style);
+                if let Some(m) = matches.iter().find(|m| m.start <= start && start < m.end) {
+                    style = style.patch(if current == Some(m) {
+                        current_style
+                    } else {
+                        match_style
+                    });
+                }
+                Span::styled(chunk[start - offset..end - offset].to_string(), style)
+            })
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::utils::tempfile::TempFile;
+
+    /// Matches of `pattern` in a file holding `content`
+    fn scan(pattern: &str, content: &[u8]) -> Vec<SearchMatch> {
+        let file = TempFile::create("slurmer-test", "log", content).unwrap();
+        let mut search = LogSearch::with_regex(Regex::new(pattern).unwrap());
+        let mut scanner = Scanner::new(search.regex.clone().unwrap());
+        scanner.scan(file.path(), content.len() as u64, &mut |message| {
+            search.receive(message);
+            true
+        });
+        search.matches
+    }
+
+    fn at(line: usize, start: usize, end: usize) -> SearchMatch {
+        SearchMatch { line, start, end }
+    }
+
+    #[test]
+    fn locates_matches_on_displayed_lines() {
+        let content = b"ok\n\x1B[31merror\x1B[0m one\nprogress 10%\rerror two\r\nerror";
+        assert_eq!(
+            scan("error", content),
+            vec![at(1, 0, 5), at(2, 0, 5), at(3, 0, 5)]
+        );
+    }
+
+    #[test]
+    fn unterminated_escapes_keep_line_numbers() {
+        assert_eq!(scan("x", b"a\x1B[12\nb\nx\n"), vec![at(2, 0, 1)]);
+    }
+
+    #[test]
+    fn finds_matches_across_reads_of_a_long_line() {
+        // Only what follows the last carriage return is displayed
+        let mut content = vec![b'a'; SEARCH_CHUNK - 5];
+        content.extend_from_slice(b"\rxneedle\nneedle\n");
+        assert_eq!(scan("needle", &content), vec![at(0, 1, 7), at(1, 0, 6)]);
+
+        // Nor is what goes past the longest displayed line
+        let mut content = vec![b'a'; SEARCH_CHUNK - 2];
+        content.extend_from_slice(b"needle\nneedle");
+        assert_eq!(scan("needle", &content), vec![at(1, 0, 6)]);
+    }
+
+    #[test]
+    fn highlights_matches_within_a_chunk() {
+        let mut search = LogSearch::with_regex(Regex::new("é").unwrap());
+        search.receive(ScanMessage::Matches {
+            first_line: 0,
+            matches: vec![at(0, 2, 4), at(0, 3, 5)],
+        });
+        let spans = search.highlight(Some(0), "bé", 1, &[(0..4, Style::default())]);
+        let texts: Vec<&str> = spans.iter().map(|span| span.content.as_ref()).collect();
+        assert_eq!(texts, vec!["b", "é"]);
+        assert_eq!(spans[1].style.bg, Some(Color::LightRed));
+    }
+}
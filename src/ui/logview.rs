@@ -2,15 +2,22 @@ use color_eyre::Result;
 use crossbeam::channel::{unbounded, Receiver};
 use crossterm::event::{KeyCode, KeyEvent, KeyModifiers};
 use ratatui::{
-    layout::Rect,
+    layout::{Constraint, Direction, Layout, Rect},
     style::{Color, Modifier, Style},
     text::{Line, Span, Text},
     widgets::{Block, Borders, Clear, Paragraph, Wrap},
     Frame,
 };
-use std::{collections::HashMap, iter::once, path::PathBuf, process::Command, time::Duration};
+use std::{iter::once, ops::Range, path::PathBuf, thread, time::Duration};
 
+use crate::slurm::logpaths::{resolve_log_paths, LogPaths};
+use crate::ui::logissues::{summarize, IssuePatterns, LogIssues};
+use crate::ui::logsearch::LogSearch;
+use crate::ui::viewer::{SavePrompt, ViewerAction};
+use crate::utils::ansi;
+use crate::utils::external::ExternalProgram;
 use crate::utils::file_watcher::{FileWatcherError, FileWatcherHandle};
+use crate::utils::log_index::SharedLogIndex;
 
 /// Type of log to view
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
@@ -20,6 +27,8 @@ pub enum LogTab {
 }
 
 impl LogTab {
+    const ALL: [LogTab; 2] = [LogTab::StdOut, LogTab::StdErr];
+
     fn toggle(&mut self) {
         *self = match self {
             LogTab::StdOut => LogTab::StdErr,
@@ -33,29 +42,169 @@ impl LogTab {
             LogTab::StdErr => "stderr",
         }
     }
+
+    fn index(self) -> usize {
+        self as usize
+    }
+}
+
+/// How the stdout and stderr logs are arranged
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum LogLayout {
+    /// One log at a time
+    Single,
+    /// Both logs next to each other
+    SideBySide,
+    /// Both logs above each other
+    Stacked,
+    /// Both logs in one pane, interleaved in the order their lines arrived
+    Merged,
+}
+
+impl LogLayout {
+    fn next(self) -> Self {
+        match self {
+            LogLayout::Single => LogLayout::SideBySide,
+            LogLayout::SideBySide => LogLayout::Stacked,
+            LogLayout::Stacked => LogLayout::Merged,
+            LogLayout::Merged => LogLayout::Single,
+        }
+    }
+
+    fn as_str(&self) -> &'static str {
+        match self {
+            LogLayout::Single => "single",
+            LogLayout::SideBySide => "side by side",
+            LogLayout::Stacked => "stacked",
+            LogLayout::Merged => "merged",
+        }
+    }
+}
+
+/// What a pane of the log view shows
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum PaneSource {
+    Log(LogTab),
+    Merged,
+}
+
+/// Scroll state of a pane
+#[derive(Debug, Clone)]
+struct Viewport {
+    /// First displayed line when not following
+    scroll_position: usize,
+    /// Stick to the end of the log as new output arrives, like `tail -f`
+    follow: bool,
+    /// Height of the pane when last rendered
+    height: usize,
+    /// First line on screen when last rendered
+    first_visible_line: usize,
+    /// Whether the last line was on screen when last rendered
+    at_bottom: bool,
+}
+
+impl Default for Viewport {
+    fn default() -> Self {
+        Self {
+            scroll_position: 0,
+            follow: true,
+            height: 0,
+            first_visible_line: 0,
+            at_bottom: true,
+        }
+    }
+}
+
+/// One of the logs of a job
+#[derive(Default)]
+struct LogStream {
+    path: Option<String>,
+    /// Index of the log file, once it has been read
+    log: Option<SharedLogIndex>,
+    /// Indicates the status of the log file
+    status: LogFileStatus,
+    /// Error from watching or reading the log file
+    error_message: String,
+    viewport: Viewport,
+    /// Number of lines already placed in the merged view
+    merged_lines: usize,
+    /// Lines matching the error and warning patterns
+    issues: LogIssues,
+}
+
+impl LogStream {
+    /// Path of the log file, if there is one
+    fn watched_path(&self) -> Option<PathBuf> {
+        self.path
+            .as_ref()
+            .filter(|p| !p.is_empty())
+            .map(PathBuf::from)
+    }
+
+    /// Number of lines in the log
+    fn line_count(&self) -> usize {
+        self.log
+            .as_ref()
+            .and_then(|log| log.lock().ok().map(|index| index.line_count()))
+            .unwrap_or(0)
+    }
+}
+
+/// Consecutive lines of one log in the merged view
+#[derive(Debug, Clone)]
+struct MergedSegment {
+    tab: LogTab,
+    lines: Range<usize>,
+}
+
+/// A log line ready to be displayed in a pane
+struct PaneLine {
+    tab: LogTab,
+    /// Line number within its log
+    line: usize,
+    text: String,
+    /// Style the line's own colors are applied over
+    style: Style,
 }
 
 /// LogView widget for displaying job output logs
 pub struct LogView {
     pub visible: bool,
     pub job_id: Option<String>,
+    /// Log shown in the single layout, focused in the split layouts and
+    /// searched in all of them
     pub current_tab: LogTab,
-    pub content: String,
-    pub scroll_position: usize,
-    pub stdout_path: Option<String>,
-    pub stderr_path: Option<String>,
+    pub layout: LogLayout,
+    /// The stdout and stderr logs, indexed by `LogTab`
+    streams: [LogStream; 2],
+    /// Lines of both logs in the order they were read
+    merged: Vec<MergedSegment>,
+    merged_viewport: Viewport,
+    /// Render ANSI colors, or show the plain text
+    pub colors: bool,
     file_watcher: Option<FileWatcherHandle>,
-    file_receiver: Option<Receiver<Result<String, FileWatcherError>>>,
+    file_receiver: Option<Receiver<Result<SharedLogIndex, FileWatcherError>>>,
+    /// Log paths of the job being looked up on a thread of their own
+    paths_receiver: Option<Receiver<LogPaths>>,
     refresh_interval: Duration,
-    /// Indicates the status of the current log file
-    file_status: LogFileStatus,
+    /// Memory budget for the tail of a log, in bytes
+    max_retained: usize,
+    /// Regex search within the current log
+    search: LogSearch,
+    /// Error and warning patterns flagged in every log
+    issue_patterns: IssuePatterns,
+    /// Prompt for saving a copy of the current log
+    save_prompt: SavePrompt,
 }
 
 /// Status of the log file being watched
-#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
 enum LogFileStatus {
     /// No file found or not set
+    #[default]
     NotFound,
+    /// Path being looked up with scontrol or sacct
+    Resolving,
     /// File exists but waiting for content
     Waiting,
     /// Error occurred when accessing the file
@@ -63,22 +212,32 @@ enum LogFileStatus {
 }
 
 impl LogView {
-    pub fn new() -> Self {
+    pub fn new(max_retained: usize, issue_patterns: IssuePatterns) -> Self {
         Self {
             visible: false,
             job_id: None,
             current_tab: LogTab::StdOut,
-            content: String::new(),
-            scroll_position: 0,
-            stdout_path: None,
-            stderr_path: None,
+            layout: LogLayout::Single,
+            streams: Default::default(),
+            merged: Vec::new(),
+            merged_viewport: Viewport::default(),
+            colors: true,
             file_watcher: None,
             file_receiver: None,
+            paths_receiver: None,
             refresh_interval: Duration::from_secs(2),
-            file_status: LogFileStatus::NotFound,
+            max_retained,
+            search: LogSearch::default(),
+            issue_patterns,
+            save_prompt: SavePrompt::default(),
         }
     }
 
+    /// Whether the search or save prompt is taking keyboard input
+    pub fn is_editing(&self) -> bool {
+        self.visible && (self.search.editing || self.save_prompt.visible)
+    }
+
     /// Show the log view for a specific job
     pub fn show(&mut self, job_id: String) {
         self.change_job(job_id);
@@ -90,145 +249,392 @@ impl LogView {
         self.visible = false;
         // Stop watching files when hiding the view
         if let Some(watcher) = &mut self.file_watcher {
-            watcher.set_file_path(None);
+            watcher.set_file_paths(Vec::new());
         }
     }
 
     /// Change the job being viewed
     pub fn change_job(&mut self, job_id: String) {
         self.job_id = Some(job_id);
-        self.stdout_path = None;
-        self.stderr_path = None;
-        // self.content = String::new();
-        self.scroll_position = 0;
-        self.file_status = LogFileStatus::NotFound;
-
-        // Fetch the log file paths
-        self.fetch_log_paths();
+        self.streams = Default::default();
+        for stream in &mut self.streams {
+            stream.issues = LogIssues::new(&self.issue_patterns);
+        }
+        self.merged.clear();
+        self.merged_viewport = Viewport::default();
+        self.search.reset_matches();
 
         // Setup file watcher if needed
         if self.file_watcher.is_none() {
             let (sender, receiver) = unbounded();
-            self.file_watcher = Some(FileWatcherHandle::new(sender, self.refresh_interval));
+            self.file_watcher = Some(FileWatcherHandle::new(
+                sender,
+                self.refresh_interval,
+                self.max_retained,
+            ));
             self.file_receiver = Some(receiver);
         }
 
-        // Update the watched file based on current tab
-        self.update_watched_file();
+        // The files of the job are watched once their paths are known
+        self.fetch_log_paths();
     }
 
     /// Toggle between stdout and stderr logs
     pub fn toggle_tab(&mut self) {
         self.current_tab.toggle();
-        self.scroll_position = 0;
-        self.update_watched_file();
+
+        // Search the newly selected log
+        self.search.reset_matches();
+        if let Some(log) = &self.streams[self.current_tab.index()].log {
+            self.search.update(log);
+        }
     }
 
-    /// Update the file being watched based on job_id and current_tab
-    fn update_watched_file(&mut self) {
-        if let Some(watcher) = &mut self.file_watcher {
-            let path = match self.current_tab {
-                LogTab::StdOut => self.stdout_path.clone(),
-                LogTab::StdErr => self.stderr_path.clone(),
-            };
+    /// Switch to the next layout
+    pub fn cycle_layout(&mut self) {
+        self.layout = self.layout.next();
+    }
+
+    fn stream(&self, tab: LogTab) -> &LogStream {
+        &self.streams[tab.index()]
+    }
+
+    /// Whether stderr is written to the same file as stdout, as Slurm does
+    /// unless `--error` is given
+    fn shares_file(&self) -> bool {
+        let stdout = self.stream(LogTab::StdOut).watched_path();
+        stdout.is_some() && stdout == self.stream(LogTab::StdErr).watched_path()
+    }
+
+    /// The pane that scrolls and searches
+    fn active_source(&self) -> PaneSource {
+        match self.layout {
+            LogLayout::Merged => PaneSource::Merged,
+            _ => PaneSource::Log(self.current_tab),
+        }
+    }
+
+    fn viewport(&self, source: PaneSource) -> &Viewport {
+        match source {
+            PaneSource::Log(tab) => &self.stream(tab).viewport,
+            PaneSource::Merged => &self.merged_viewport,
+        }
+    }
 
-            match path {
-                Some(p) if !p.is_empty() => {
+    fn viewport_mut(&mut self, source: PaneSource) -> &mut Viewport {
+        match source {
+            PaneSource::Log(tab) => &mut self.streams[tab.index()].viewport,
+            PaneSource::Merged => &mut self.merged_viewport,
+        }
+    }
+
+    /// Number of lines shown in a pane
+    fn line_count(&self, source: PaneSource) -> usize {
+        match source {
+            PaneSource::Log(tab) => self.stream(tab).line_count(),
+            PaneSource::Merged => self.merged.iter().map(|s| s.lines.len()).sum(),
+        }
+    }
+
+    /// Update the files being watched based on the job's log paths
+    fn update_watched_files(&mut self) {
+        let mut paths = Vec::new();
+        for stream in &mut self.streams {
+            match stream.watched_path() {
+                Some(path) => {
                     // File path exists, set status to waiting for content
-                    watcher.set_file_path(Some(PathBuf::from(&p)));
-                    self.file_status = LogFileStatus::Waiting;
+                    stream.status = LogFileStatus::Waiting;
+                    if !paths.contains(&path) {
+                        paths.push(path);
+                    }
                 }
-                _ => {
+                None => {
                     // Either no path or empty path
-                    watcher.set_file_path(None);
-                    self.file_status = LogFileStatus::NotFound;
-                    self.content = String::new();
+                    stream.status = LogFileStatus::NotFound;
+                    stream.log = None;
                 }
             }
         }
+
+        if let Some(watcher) = &mut self.file_watcher {
+            watcher.set_file_paths(paths);
+        }
         self.check_refresh();
     }
 
     /// Check for file updates and refresh content
     pub fn check_refresh(&mut self) {
-        // // Check if we need to refresh
-        // // let should_refresh = match self.last_refresh {
-        // //     Some(instant) => instant.elapsed() >= self.refresh_interval,
-        // //     None => true,
-        // // };
-        // // if instant is true, we always refresh, if not, we check the interval
-        // let should_refresh = if instant {
-        //     true
-        // } else {
-        //     self.last_refresh
-        //         .map_or(true, |instant| instant.elapsed() >= self.refresh_interval)
-        // };
-
-        if self.file_receiver.is_some() {
-            let receiver = self.file_receiver.as_ref().unwrap();
-
-            // Check for new content from the file watcher
-            while let Ok(result) = receiver.try_recv() {
-                match result {
-                    Ok(content) => {
-                        // if !content.is_empty() {
-                        //     self.content = content;
-                        //     self.file_status = LogFileStatus::Loaded;
-                        // } else if self.file_status == LogFileStatus::Waiting {
-                        //     // Got empty content but file exists, keep waiting
-                        //     self.file_status = LogFileStatus::Waiting;
-                        // }
-                        self.content = content;
-                    }
-                    Err(e) => {
-                        self.content = format!("Error watching file: {}", e);
-                        self.file_status = LogFileStatus::Error;
+        // Watch the log files of the job once they are found, which checks again
+        if let Some(paths) = self
+            .paths_receiver
+            .as_ref()
+            .and_then(|receiver| receiver.try_recv().ok())
+        {
+            self.paths_receiver = None;
+            self.streams[LogTab::StdOut.index()].path = paths.stdout;
+            self.streams[LogTab::StdErr.index()].path = paths.stderr;
+            self.update_watched_files();
+            return;
+        }
+
+        let Some(receiver) = self.file_receiver.clone() else {
+            return;
+        };
+
+        // Check for new content from the file watcher
+        while let Ok(result) = receiver.try_recv() {
+            match result {
+                Ok(log) => self.receive_log(log),
+                Err(e) => {
+                    for stream in &mut self.streams {
+                        if stream.watched_path().as_deref() == Some(e.path()) {
+                            stream.error_message = format!("Error watching file: {}", e);
+                            stream.status = LogFileStatus::Error;
+                        }
                     }
                 }
             }
+        }
 
-            // self.last_refresh = Some(Instant::now());
+        // Take the lines flagged and the matches found so far, showing the
+        // first match of a new query
+        for stream in &mut self.streams {
+            stream.issues.poll();
+        }
+        let log = self.streams[self.current_tab.index()].log.clone();
+        self.search.poll(log.as_ref());
+        if let Some(line) = self.search.take_first_match() {
+            self.scroll_to_line(line);
         }
     }
 
-    /// Scroll the log view up
-    pub fn scroll_up(&mut self) {
-        if self.scroll_position > 0 {
-            self.scroll_position -= 1;
+    /// Show new content of a log file in the logs written to it
+    fn receive_log(&mut self, log: SharedLogIndex) {
+        let Ok(index) = log.lock() else {
+            return;
+        };
+        let line_count = index.line_count();
+        let shares_file = self.shares_file();
+
+        for tab in LogTab::ALL {
+            // Ignore updates still queued for a previously watched file
+            if self.stream(tab).watched_path().as_deref() != Some(index.path()) {
+                continue;
+            }
+            if tab == self.current_tab {
+                self.search.update(&log);
+            }
+
+            // Keep the scroll position, unless the file shrank below it
+            let stream = &mut self.streams[tab.index()];
+            stream.issues.update(&log);
+            stream.viewport.scroll_position = stream
+                .viewport
+                .scroll_position
+                .min(line_count.saturating_sub(1));
+            stream.log = Some(log.clone());
+            stream.status = LogFileStatus::Waiting;
+
+            // A shared file is merged once, as stdout
+            if !(shares_file && tab == LogTab::StdErr) {
+                self.merge_lines(tab, line_count);
+            }
         }
     }
 
-    /// Scroll the log view down
-    pub fn scroll_down(&mut self) {
-        // Need to calculate max scroll based on content and view size
-        // This is a simplification - in a real implementation you would
-        // calculate this based on content height and view height
-        let line_count = self.content.lines().count();
-        if self.scroll_position < line_count.saturating_sub(1) {
-            self.scroll_position += 1;
+    /// Append the lines a log gained since the last update to the merged view
+    fn merge_lines(&mut self, tab: LogTab, line_count: usize) {
+        let stream = &mut self.streams[tab.index()];
+        let merged_lines = stream.merged_lines;
+        stream.merged_lines = line_count;
+
+        if line_count < merged_lines {
+            // The file was truncated or replaced, its lines are placed again
+            self.merged.retain(|segment| segment.tab != tab);
+            if line_count > 0 {
+                self.merged.push(MergedSegment {
+                    tab,
+                    lines: 0..line_count,
+                });
+            }
+            let total = self.line_count(PaneSource::Merged);
+            let viewport = &mut self.merged_viewport;
+            viewport.scroll_position = viewport.scroll_position.min(total.saturating_sub(1));
+        } else if line_count > merged_lines {
+            match self.merged.last_mut() {
+                Some(last) if last.tab == tab && last.lines.end == merged_lines => {
+                    last.lines.end = line_count;
+                }
+                _ => self.merged.push(MergedSegment {
+                    tab,
+                    lines: merged_lines..line_count,
+                }),
+            }
         }
     }
 
+    /// Position of a line of the current log within the active pane
+    fn source_line(&self, line: usize) -> usize {
+        if self.active_source() != PaneSource::Merged {
+            return line;
+        }
+
+        let mut offset = 0;
+        for segment in &self.merged {
+            if segment.tab == self.current_tab && segment.lines.contains(&line) {
+                return offset + line - segment.lines.start;
+            }
+            offset += segment.lines.len();
+        }
+        offset
+    }
+
+    /// Scroll the log view up, pausing follow mode
+    pub fn scroll_up(&mut self) {
+        self.scroll_up_by(1);
+    }
+
+    /// Scroll the log view down, following again once the end is reached
+    pub fn scroll_down(&mut self) {
+        self.scroll_down_by(1);
+    }
+
     /// Page up in the log view
     pub fn page_up(&mut self) {
-        // Move up by a page (10 lines)
-        self.scroll_position = self.scroll_position.saturating_sub(10);
+        let height = self.viewport(self.active_source()).height;
+        self.scroll_up_by(height.max(1));
     }
 
     /// Page down in the log view
     pub fn page_down(&mut self) {
-        // Move down by a page (10 lines)
-        let line_count = self.content.lines().count();
-        let new_scroll = self.scroll_position + 10;
-        self.scroll_position = if new_scroll < line_count {
-            new_scroll
+        let height = self.viewport(self.active_source()).height;
+        self.scroll_down_by(height.max(1));
+    }
+
+    fn scroll_up_by(&mut self, lines: usize) {
+        self.pause_follow();
+        let viewport = self.viewport_mut(self.active_source());
+        viewport.scroll_position = viewport.scroll_position.saturating_sub(lines);
+    }
+
+    fn scroll_down_by(&mut self, lines: usize) {
+        let source = self.active_source();
+        let line_count = self.line_count(source);
+        let viewport = self.viewport_mut(source);
+        if viewport.follow {
+            return;
+        }
+        if viewport.at_bottom {
+            viewport.follow = true;
+            return;
+        }
+        viewport.scroll_position =
+            (viewport.scroll_position + lines).min(line_count.saturating_sub(1));
+    }
+
+    /// Stop following the end, keeping the lines currently on screen
+    fn pause_follow(&mut self) {
+        let viewport = self.viewport_mut(self.active_source());
+        if viewport.follow {
+            viewport.follow = false;
+            viewport.scroll_position = viewport.first_visible_line;
+        }
+    }
+
+    /// Jump to the first line
+    pub fn scroll_to_top(&mut self) {
+        let viewport = self.viewport_mut(self.active_source());
+        viewport.follow = false;
+        viewport.scroll_position = 0;
+    }
+
+    /// Jump to the end and follow new output
+    pub fn scroll_to_bottom(&mut self) {
+        self.viewport_mut(self.active_source()).follow = true;
+    }
+
+    /// Toggle following the end of the log
+    pub fn toggle_follow(&mut self) {
+        if self.viewport(self.active_source()).follow {
+            self.pause_follow();
         } else {
-            line_count.saturating_sub(1)
-        };
+            self.viewport_mut(self.active_source()).follow = true;
+        }
+    }
+
+    /// Scroll so that the given line of the current log is in the middle of
+    /// the view
+    fn scroll_to_line(&mut self, line: usize) {
+        let line = self.source_line(line);
+        let viewport = self.viewport_mut(self.active_source());
+        viewport.follow = false;
+        viewport.scroll_position = line.saturating_sub(viewport.height / 2);
+    }
+
+    /// Jump to the next line of the current log flagged as an error
+    pub fn next_error(&mut self) {
+        if let Some(line) = self.streams[self.current_tab.index()].issues.next_error() {
+            self.scroll_to_line(line);
+        }
+    }
+
+    /// Jump to the previous line of the current log flagged as an error
+    pub fn previous_error(&mut self) {
+        if let Some(line) = self.streams[self.current_tab.index()]
+            .issues
+            .previous_error()
+        {
+            self.scroll_to_line(line);
+        }
+    }
+
+    /// Error and warning counts of the logs in a pane
+    fn issue_summary(&self, source: PaneSource) -> String {
+        match source {
+            PaneSource::Log(tab) => self.stream(tab).issues.summary(),
+            PaneSource::Merged => {
+                let tabs: &[LogTab] = if self.shares_file() {
+                    &[LogTab::StdOut]
+                } else {
+                    &LogTab::ALL
+                };
+                let issues = tabs.iter().map(|tab| &self.stream(*tab).issues);
+                summarize(
+                    issues.clone().map(LogIssues::error_count).sum(),
+                    issues.map(LogIssues::warning_count).sum(),
+                )
+            }
+        }
+    }
+
+    /// Handle a key while the search prompt is open
+    fn handle_search_key(&mut self, key: KeyEvent) {
+        match key.code {
+            KeyCode::Enter => {
+                let log = self.streams[self.current_tab.index()].log.clone();
+                self.search.confirm(log.as_ref());
+                self.search.editing = false;
+                if let Some(line) = self.search.current_match().map(|m| m.line) {
+                    self.scroll_to_line(line);
+                }
+            }
+            KeyCode::Esc => self.search.clear(),
+            KeyCode::Backspace => {
+                let mut query = self.search.input.clone();
+                query.pop();
+                self.search.edit_query(query);
+            }
+            KeyCode::Char(c) => {
+                let mut query = self.search.input.clone();
+                query.push(c);
+                self.search.edit_query(query);
+            }
+            _ => {}
+        }
     }
 
     /// Render the log view
-    pub fn render(&self, frame: &mut Frame, area: Rect) {
+    pub fn render(&mut self, frame: &mut Frame, area: Rect) {
         if !self.visible {
             return;
         }
@@ -236,52 +642,378 @@ impl LogView {
         let log_area = area;
         frame.render_widget(Clear, log_area);
 
+        let shown = match self.layout {
+            LogLayout::Single => format!(
+                "{}{}{}",
+                self.current_tab.as_str(),
+                self.scroll_status(self.active_source()),
+                self.issue_summary(self.active_source())
+            ),
+            LogLayout::Merged => format!(
+                "stdout+stderr{}{}",
+                self.scroll_status(self.active_source()),
+                self.issue_summary(self.active_source())
+            ),
+            LogLayout::SideBySide | LogLayout::Stacked => self.layout.as_str().to_string(),
+        };
         let title = match &self.job_id {
-            Some(id) => format!("Job {} - {}", id, self.current_tab.as_str()),
-            None => format!("Log View - {}", self.current_tab.as_str()),
+            Some(id) => format!("Job {} - {}", id, shown),
+            None => format!("Log View - {}", shown),
         };
 
-        let help_text =
-            " [↑/↓] Scroll | [Shift+↑/↓] Toggle Job | [o] Toggle stdout/stderr | [q] Close ";
+        let help_text = " [↑/↓] Scroll | [g/G] Top/Bottom | [f] Follow | [c] Colors | [l] Layout | [Shift+↑/↓] Toggle Job | [o] Toggle stdout/stderr | [/] Search | [n/N] Next/Prev | [e/E] Next/Prev error | [p/v] Pager/Editor | [w] Save copy | [y/Y] Copy line/path | [q] Close ";
 
-        let log_text = match (self.file_status, self.content.is_empty()) {
-            (LogFileStatus::NotFound, _) => format!(
-                "No {} log file found for job {}",
-                self.current_tab.as_str(),
-                self.job_id.as_deref().unwrap_or("unknown")
-            ),
-            _ => self.content.clone(),
+        // Match counter for the active search, naming the searched log when
+        // both are shown
+        let search_status = if self.search.is_active() {
+            let searched = match self.layout {
+                LogLayout::Single => String::new(),
+                _ => format!(" in {}", self.current_tab.as_str()),
+            };
+            let scanning = if self.search.is_scanning() {
+                ", searching…"
+            } else {
+                ""
+            };
+            match self.search.current_index() {
+                Some(index) => format!(
+                    " [{}/{}{}{}]",
+                    index,
+                    self.search.match_count(),
+                    searched,
+                    scanning
+                ),
+                None if self.search.is_scanning() => format!(" [searching{}…]", searched),
+                None => format!(" [no matches{}]", searched),
+            }
+        } else {
+            String::new()
         };
 
-        let fit_text = Self::fit_text(
-            &log_text,
-            log_area.height as usize,
-            log_area.width as usize,
-            self.scroll_position,
-            false,
-        );
-        // eprintln!("fit_text: {}", log_text);
+        let block = Block::default()
+            .title(format!("{}{}{}", title, search_status, help_text))
+            .borders(Borders::ALL)
+            .border_style(Style::default().fg(Color::Cyan));
+        let inner = block.inner(log_area);
+        frame.render_widget(block, log_area);
 
-        let log_paragraph = Paragraph::new(fit_text)
-            .style(Style::default().fg(Color::White))
-            .block(
-                Block::default()
-                    .title(format!("{}{}", title, help_text))
-                    .borders(Borders::ALL)
-                    .border_style(Style::default().fg(Color::Cyan)),
+        // Keep the last row free for the search prompt
+        let prompt_height = if self.search.editing || self.save_prompt.visible {
+            1
+        } else {
+            0
+        };
+        let [panes_area, prompt_area] = Layout::default()
+            .direction(Direction::Vertical)
+            .constraints([Constraint::Min(0), Constraint::Length(prompt_height)])
+            .areas(inner);
+
+        match self.layout {
+            LogLayout::Single => {
+                self.render_pane(frame, panes_area, PaneSource::Log(self.current_tab), None)
+            }
+            LogLayout::Merged => self.render_pane(frame, panes_area, PaneSource::Merged, None),
+            LogLayout::SideBySide | LogLayout::Stacked => {
+                let direction = if self.layout == LogLayout::SideBySide {
+                    Direction::Horizontal
+                } else {
+                    Direction::Vertical
+                };
+                let areas = Layout::default()
+                    .direction(direction)
+                    .constraints([Constraint::Ratio(1, 2), Constraint::Ratio(1, 2)])
+                    .split(panes_area);
+                for (tab, pane_area) in LogTab::ALL.into_iter().zip(areas.iter()) {
+                    let source = PaneSource::Log(tab);
+                    let shared = if tab == LogTab::StdErr && self.shares_file() {
+                        " (same file as stdout)"
+                    } else {
+                        ""
+                    };
+                    let border_color = if tab == self.current_tab {
+                        Color::Cyan
+                    } else {
+                        Color::DarkGray
+                    };
+                    let pane_block = Block::default()
+                        .title(format!(
+                            "{}{}{}{}",
+                            tab.as_str(),
+                            shared,
+                            self.scroll_status(source),
+                            self.issue_summary(source)
+                        ))
+                        .borders(Borders::ALL)
+                        .border_style(Style::default().fg(border_color));
+                    self.render_pane(frame, *pane_area, source, Some(pane_block));
+                }
+            }
+        }
+
+        if self.save_prompt.visible {
+            self.save_prompt.render(frame, prompt_area);
+        } else if self.search.editing {
+            let prompt = match &self.search.error {
+                Some(error) => Line::from(vec![
+                    Span::styled("/", Style::default().fg(Color::Yellow)),
+                    Span::raw(self.search.input.clone()),
+                    Span::styled(format!("  {}", error), Style::default().fg(Color::Red)),
+                ]),
+                None => Line::from(vec![
+                    Span::styled("/", Style::default().fg(Color::Yellow)),
+                    Span::raw(self.search.input.clone()),
+                ]),
+            };
+            frame.render_widget(
+                Paragraph::new(prompt).style(Style::default().bg(Color::DarkGray)),
+                prompt_area,
+            );
+        }
+    }
+
+    /// Following the end, or paused at a line
+    fn scroll_status(&self, source: PaneSource) -> String {
+        let viewport = self.viewport(source);
+        if viewport.follow {
+            " [FOLLOW]".to_string()
+        } else {
+            format!(
+                " [PAUSED {}/{}]",
+                viewport.scroll_position + 1,
+                self.line_count(source).max(1)
             )
-            .wrap(Wrap { trim: false })
-            .scroll((self.scroll_position as u16, 0));
+        }
+    }
+
+    /// Render the lines of a log, or of the merged logs, in a pane
+    fn render_pane(
+        &mut self,
+        frame: &mut Frame,
+        area: Rect,
+        source: PaneSource,
+        block: Option<Block>,
+    ) {
+        let pane_area = match &block {
+            Some(block) => block.inner(area),
+            None => area,
+        };
+        let rows = pane_area.height as usize;
+        let cols = pane_area.width as usize;
+        self.viewport_mut(source).height = rows;
+
+        let line_count = self.line_count(source);
+        let text = match self.status_text(source) {
+            Some(text) if line_count == 0 || source != PaneSource::Merged => text,
+            _ => {
+                // Read only the lines that can be on screen; each takes at least one row
+                let viewport = self.viewport(source);
+                let window = if viewport.follow {
+                    line_count.saturating_sub(rows)..line_count
+                } else {
+                    viewport.scroll_position..viewport.scroll_position + rows
+                };
+                let first_line = window.start;
+                let at_end = window.end >= line_count;
+                let lines = self.read_lines(source, window);
+
+                let follow = viewport.follow;
+                let (text, first_visible, at_bottom) =
+                    self.fit_text(&lines, follow, at_end, rows, cols);
+                let viewport = self.viewport_mut(source);
+                viewport.first_visible_line =
+                    (first_line + first_visible).min(line_count.saturating_sub(1));
+                viewport.at_bottom = at_bottom;
+                text
+            }
+        };
+
+        let mut paragraph = Paragraph::new(text)
+            .style(Style::default().fg(Color::White))
+            .wrap(Wrap { trim: false });
+        if let Some(block) = block {
+            paragraph = paragraph.block(block);
+        }
+        frame.render_widget(paragraph, area);
+    }
 
-        frame.render_widget(log_paragraph, log_area);
+    /// Message to show instead of the lines of a pane whose logs are missing
+    /// or could not be read
+    fn status_text(&self, source: PaneSource) -> Option<Text<'static>> {
+        let tabs = match source {
+            PaneSource::Log(tab) => vec![tab],
+            PaneSource::Merged => LogTab::ALL.to_vec(),
+        };
+        let lines: Vec<Line> = tabs
+            .into_iter()
+            .filter_map(|tab| {
+                let stream = self.stream(tab);
+                match (stream.status, &stream.log) {
+                    (LogFileStatus::NotFound, _) => Some(Line::raw(format!(
+                        "No {} log file found for job {}",
+                        tab.as_str(),
+                        self.job_id.as_deref().unwrap_or("unknown")
+                    ))),
+                    (LogFileStatus::Resolving, _) => Some(Line::raw(format!(
+                        "Looking up the {} log file of job {}…",
+                        tab.as_str(),
+                        self.job_id.as_deref().unwrap_or("unknown")
+                    ))),
+                    (LogFileStatus::Error, _) => Some(Line::raw(stream.error_message.clone())),
+                    (LogFileStatus::Waiting, _) => None,
+                }
+            })
+            .collect();
+        (!lines.is_empty()).then(|| Text::from(lines))
+    }
+
+    /// Read the lines of a pane in `range`
+    fn read_lines(&self, source: PaneSource, range: Range<usize>) -> Vec<PaneLine> {
+        match source {
+            PaneSource::Log(tab) => self.read_log_lines(tab, range, Style::default()),
+            PaneSource::Merged => {
+                // stderr stands out from stdout
+                let stderr_style = Style::default().fg(Color::LightRed);
+                let mut lines = Vec::with_capacity(range.len());
+                let mut offset = 0;
+                for segment in &self.merged {
+                    let len = segment.lines.len();
+                    let start = range.start.max(offset);
+                    let end = range.end.min(offset + len);
+                    if start < end {
+                        let first = segment.lines.start + start - offset;
+                        let style = match segment.tab {
+                            LogTab::StdOut => Style::default(),
+                            LogTab::StdErr => stderr_style,
+                        };
+                        lines.extend(self.read_log_lines(
+                            segment.tab,
+                            first..first + end - start,
+                            style,
+                        ));
+                    }
+                    offset += len;
+                    if offset >= range.end {
+                        break;
+                    }
+                }
+                lines
+            }
+        }
+    }
+
+    /// Read the lines of a log in `range`
+    fn read_log_lines(&self, tab: LogTab, range: Range<usize>, style: Style) -> Vec<PaneLine> {
+        let Some(index) = self
+            .stream(tab)
+            .log
+            .as_ref()
+            .and_then(|log| log.lock().ok())
+        else {
+            return Vec::new();
+        };
+        let first_line = range.start;
+        let texts = index
+            .lines(range)
+            .unwrap_or_else(|e| vec![format!("Error reading {}: {}", index.path().display(), e)]);
+        texts
+            .into_iter()
+            .enumerate()
+            .map(|(i, text)| PaneLine {
+                tab,
+                line: first_line + i,
+                text,
+                style,
+            })
+            .collect()
     }
 
-    pub fn handle_key(&mut self, key: KeyEvent) -> () {
+    pub fn handle_key(&mut self, key: KeyEvent) -> ViewerAction {
+        if self.save_prompt.visible {
+            return self.save_prompt.handle_key(key);
+        }
+        if self.search.editing {
+            self.handle_search_key(key);
+            return ViewerAction::None;
+        }
+
         match (key.modifiers, key.code) {
+            (_, KeyCode::Char('g')) | (_, KeyCode::Home) => {
+                // Jump to the first line
+                self.scroll_to_top();
+            }
+            (_, KeyCode::Char('G')) | (_, KeyCode::End) => {
+                // Jump to the end and follow new output
+                self.scroll_to_bottom();
+            }
+            (_, KeyCode::Char('f')) => {
+                // Toggle following new output
+                self.toggle_follow();
+            }
+            (_, KeyCode::Char('c')) => {
+                // Toggle rendering colors
+                self.colors = !self.colors;
+            }
+            (_, KeyCode::Char('/')) => {
+                // Start a regex search
+                self.search.start();
+            }
+            (_, KeyCode::Char('n')) => {
+                // Jump to the next match
+                if let Some(line) = self.search.next() {
+                    self.scroll_to_line(line);
+                }
+            }
+            (_, KeyCode::Char('N')) => {
+                // Jump to the previous match
+                if let Some(line) = self.search.previous() {
+                    self.scroll_to_line(line);
+                }
+            }
+            (_, KeyCode::Char('e')) => {
+                // Jump to the next error
+                self.next_error();
+            }
+            (_, KeyCode::Char('E')) => {
+                // Jump to the previous error
+                self.previous_error();
+            }
             (_, KeyCode::Char('o')) => {
                 // Toggle between stdout and stderr logs
                 self.toggle_tab();
             }
+            (_, KeyCode::Char('l')) => {
+                // Switch between single, split and merged layouts
+                self.cycle_layout();
+            }
+            (_, KeyCode::Char('p')) => {
+                // Open the log in the pager
+                return self.open_external(ExternalProgram::Pager);
+            }
+            (_, KeyCode::Char('v')) => {
+                // Open the log in the editor
+                return self.open_external(ExternalProgram::Editor);
+            }
+            (_, KeyCode::Char('w')) => {
+                // Save a copy of the log
+                return self.save_copy();
+            }
+            (_, KeyCode::Char('y')) => {
+                // Copy the current line
+                return match self.current_line() {
+                    Some(line) => ViewerAction::Copy(line),
+                    None => ViewerAction::Status("No log line to copy".to_string()),
+                };
+            }
+            (_, KeyCode::Char('Y')) => {
+                // Copy the path of the log
+                return match self.stream(self.current_tab).watched_path() {
+                    Some(path) => ViewerAction::Copy(path.display().to_string()),
+                    None => ViewerAction::Status(format!(
+                        "No {} log file for this job",
+                        self.current_tab.as_str()
+                    )),
+                };
+            }
             (_, KeyCode::Char('q')) => {
                 // Close the log view
                 self.hide();
@@ -306,55 +1038,146 @@ impl LogView {
                 // Ignore other keys
             }
         }
+        ViewerAction::None
     }
 
-    fn fit_text(s: &str, lines: usize, cols: usize, offset: usize, _wrap: bool) -> Text {
-        // Process text by handling carriage returns
-        let processed_lines: Vec<String> = s
-            .lines()
-            .map(|line| {
-                // For each line, if it contains carriage returns, keep only the content after the last one
-                if line.contains('\r') {
-                    let parts: Vec<&str> = line.split('\r').collect();
-                    parts.last().unwrap_or(&"").to_string()
+    /// Open the current log in an external program
+    fn open_external(&self, program: ExternalProgram) -> ViewerAction {
+        match self.stream(self.current_tab).watched_path() {
+            Some(path) => ViewerAction::Open(program, path),
+            None => ViewerAction::Status(format!(
+                "No {} log file for this job",
+                self.current_tab.as_str()
+            )),
+        }
+    }
+
+    /// Text of the line the view is at, without colors: the current search
+    /// match, the last line when following the end, or else the first line on
+    /// screen
+    fn current_line(&self) -> Option<String> {
+        let line = match self.search.current_match() {
+            Some(search_match) => {
+                let line = search_match.line;
+                self.read_log_lines(self.current_tab, line..line + 1, Style::default())
+            }
+            None => {
+                let source = self.active_source();
+                let viewport = self.viewport(source);
+                let line = if viewport.follow {
+                    self.line_count(source).checked_sub(1)?
                 } else {
-                    line.to_string()
-                }
-            })
-            .collect();
+                    viewport.first_visible_line
+                };
+                self.read_lines(source, line..line + 1)
+            }
+        };
+        line.into_iter()
+            .next()
+            .map(|line| ansi::strip(&line.text).into_owned())
+    }
 
-        // Join the processed lines back together
-        let processed_text = processed_lines.join("\n");
-
-        // Process the clean text (without intermediate carriage returns)
-        let lines_iter = processed_text.lines();
-
-        // Create the iterator for processing text lines
-        let line_spans = lines_iter
-            .rev()
-            .skip(offset)
-            .flat_map(|l| {
-                let chunks = Self::chunked_string(l, cols, cols.saturating_sub(2));
-                chunks
-                    .into_iter()
-                    .enumerate()
-                    .map(|(i, chunk)| {
-                        if i == 0 {
-                            Line::raw(chunk.to_string())
-                        } else {
-                            Line::default().spans(vec![
-                                Span::styled("↪ ", Style::default().add_modifier(Modifier::DIM)),
-                                Span::raw(chunk.to_string()),
-                            ])
-                        }
-                    })
-                    .rev()
-            })
-            .take(lines);
+    /// Ask where to save a copy of the current log
+    fn save_copy(&mut self) -> ViewerAction {
+        let Some(path) = self.stream(self.current_tab).watched_path() else {
+            return ViewerAction::Status(format!(
+                "No {} log file for this job",
+                self.current_tab.as_str()
+            ));
+        };
+        let default_path = format!(
+            "{}-{}.log",
+            self.job_id.as_deref().unwrap_or("job"),
+            self.current_tab.as_str()
+        );
+        self.save_prompt.open(path, default_path);
+        ViewerAction::None
+    }
 
-        // Collect and build the final Text widget
-        let collected_lines: Vec<Line> = line_spans.collect();
-        Text::from(collected_lines.into_iter().rev().collect::<Vec<Line>>())
+    /// Wrap a window of log lines into at most `rows` lines of `cols`
+    /// characters. When following, the end of the window is shown, otherwise
+    /// its first line. `at_end` tells whether the window reaches the last line.
+    /// Also returns the index in `lines` of the first line on screen and
+    /// whether the last line is shown.
+    fn fit_text(
+        &self,
+        lines: &[PaneLine],
+        follow: bool,
+        at_end: bool,
+        rows: usize,
+        cols: usize,
+    ) -> (Text<'static>, usize, bool) {
+        // Split a line into screen rows, with its colors and search matches
+        let wrap_line = |i: usize| -> Vec<Line<'static>> {
+            let line = &lines[i];
+            let (mut styled, _) = ansi::parse_line(&line.text, Style::default());
+            if self.colors {
+                for (_, style) in &mut styled.runs {
+                    *style = line.style.patch(*style);
+                }
+            } else {
+                styled.runs = vec![(0..styled.text.len(), line.style)];
+            }
+            // Flagged lines stand out whatever their own colors
+            if let Some(severity) = self.stream(line.tab).issues.severity(line.line) {
+                for (_, style) in &mut styled.runs {
+                    *style = style.patch(severity.style());
+                }
+            }
+            let searched = (line.tab == self.current_tab).then_some(line.line);
+            let chunks = Self::chunked_string(&styled.text, cols, cols.saturating_sub(2));
+            let mut chunk_offset = 0;
+            chunks
+                .into_iter()
+                .enumerate()
+                .map(|(i, chunk)| {
+                    let spans = self
+                        .search
+                        .highlight(searched, chunk, chunk_offset, &styled.runs);
+                    chunk_offset += chunk.len();
+                    if i == 0 {
+                        Line::default().spans(spans)
+                    } else {
+                        Line::default().spans(
+                            once(Span::styled(
+                                "↪ ",
+                                Style::default().add_modifier(Modifier::DIM),
+                            ))
+                            .chain(spans),
+                        )
+                    }
+                })
+                .collect()
+        };
+
+        let mut collected_lines: Vec<Line> = Vec::new();
+        if !follow {
+            // Fill the screen downwards from the first line
+            let mut i = 0;
+            while i < lines.len() && collected_lines.len() < rows {
+                collected_lines.extend(wrap_line(i));
+                i += 1;
+            }
+            let at_bottom = at_end && i >= lines.len() && collected_lines.len() <= rows;
+            collected_lines.truncate(rows);
+            (Text::from(collected_lines), 0, at_bottom)
+        } else {
+            // Fill the screen upwards from the last line
+            let mut i = lines.len();
+            while i > 0 && collected_lines.len() < rows {
+                i -= 1;
+                let mut wrapped = wrap_line(i);
+                wrapped.append(&mut collected_lines);
+                collected_lines = wrapped;
+            }
+            let skip = collected_lines.len().saturating_sub(rows);
+            let first_visible = if skip > 0 { i + 1 } else { i };
+            (
+                Text::from(collected_lines.split_off(skip)),
+                first_visible,
+                true,
+            )
+        }
     }
 
     fn chunked_string(s: &str, first_chunk_size: usize, chunk_size: usize) -> Vec<&str> {
@@ -364,7 +1187,7 @@ impl LogView {
             .enumerate()
             .filter(|&(i, _)| {
                 if i > (first_chunk_size) {
-                    chunk_size > 0 && (i - first_chunk_size) % chunk_size == 0
+                    chunk_size > 0 && (i - first_chunk_size).is_multiple_of(chunk_size)
                 } else {
                     i == 0 || i == first_chunk_size
                 }
@@ -378,60 +1201,26 @@ impl LogView {
         iter.chain(once(&s[last_index..])).collect()
     }
 
-    /// Fetch the stdout and stderr paths for the current job
+    /// Resolve the stdout and stderr paths for the current job in the
+    /// background, as scontrol and sacct may take a while. Switching jobs
+    /// drops the lookup of the previous one.
     fn fetch_log_paths(&mut self) {
-        if let Some(job_id) = &self.job_id {
-            let output = Command::new("scontrol")
-                .args(["show", "job", job_id, "-o"])
-                .output();
-
-            if let Ok(output) = output {
-                if output.status.success() {
-                    let output_str = String::from_utf8_lossy(&output.stdout);
-                    let key_value_pairs = parse_scontrol_output(&output_str);
-
-                    self.stdout_path = key_value_pairs.get("StdOut").map(|s| s.to_string());
-                    self.stderr_path = key_value_pairs.get("StdErr").map(|s| s.to_string());
-
-                    // Check if we have valid paths for the current tab
-                    let has_path = match self.current_tab {
-                        LogTab::StdOut => {
-                            self.stdout_path.is_some()
-                                && !self.stdout_path.as_ref().unwrap().is_empty()
-                        }
-                        LogTab::StdErr => {
-                            self.stderr_path.is_some()
-                                && !self.stderr_path.as_ref().unwrap().is_empty()
-                        }
-                    };
+        let Some(job_id) = self.job_id.clone() else {
+            return;
+        };
 
-                    if has_path {
-                        self.file_status = LogFileStatus::Waiting;
-                    } else {
-                        self.file_status = LogFileStatus::NotFound;
-                    }
-                } else {
-                    self.file_status = LogFileStatus::Error;
-                }
-            } else {
-                self.file_status = LogFileStatus::Error;
-            }
-        } else {
-            self.file_status = LogFileStatus::NotFound;
+        // Stop watching the files of the previous job meanwhile
+        if let Some(watcher) = &mut self.file_watcher {
+            watcher.set_file_paths(Vec::new());
         }
-    }
-}
-
-fn parse_scontrol_output(output: &str) -> HashMap<String, String> {
-    let mut result = HashMap::new();
-
-    for part in output.split_whitespace() {
-        if let Some(index) = part.find('=') {
-            let key = &part[0..index];
-            let value = &part[(index + 1)..];
-            result.insert(key.to_string(), value.to_string());
+        for stream in &mut self.streams {
+            stream.status = LogFileStatus::Resolving;
         }
-    }
 
-    result
+        let (sender, receiver) = unbounded();
+        thread::spawn(move || {
+            let _ = sender.send(resolve_log_paths(&job_id));
+        });
+        self.paths_receiver = Some(receiver);
+    }
 }
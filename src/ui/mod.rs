@@ -1,6 +1,13 @@
 pub mod columns;
+pub mod diffview;
+pub mod export;
 pub mod filter;
+pub mod highlight;
 pub mod jobscript;
 pub mod jobslist;
 pub mod layout;
+pub mod logissues;
+pub mod logsearch;
 pub mod logview;
+pub mod resubmit;
+pub mod viewer;
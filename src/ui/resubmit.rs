@@ -0,0 +1,342 @@
+use crossterm::event::{KeyCode, KeyEvent, KeyModifiers};
+use ratatui::{
+    layout::{Position, Rect},
+    style::{Color, Style},
+    text::{Line, Span, Text},
+    widgets::{Block, Borders, Clear, Paragraph},
+    Frame,
+};
+use std::{
+    fs,
+    path::{Path, PathBuf},
+};
+
+use crate::slurm::{
+    batchscript::ScriptSource,
+    fields::{Memory, SlurmDuration},
+    jobdetails::{job_fields, job_script},
+    sbatch::parse_directives,
+    Job, JobState,
+};
+use crate::utils::tempfile::TempFile;
+
+/// sbatch options offered as overrides, with their labels
+const OVERRIDES: &[(&str, &str)] = &[
+    ("time", "Time limit"),
+    ("mem", "Memory per node"),
+    ("mem-per-cpu", "Memory per CPU"),
+    ("cpus-per-task", "CPUs per task"),
+    ("nodes", "Nodes"),
+    ("ntasks", "Tasks"),
+    ("partition", "Partition"),
+    ("qos", "QoS"),
+    ("gres", "Generic resources"),
+];
+
+/// What the resubmit popup asks the app to do after a key press
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ResubmitAction {
+    None,
+    Close,
+    /// Open the copy of the script in the editor
+    Edit(PathBuf),
+    /// Submit the script with the overrides
+    Submit,
+}
+
+/// An sbatch option that can be set on the command line
+#[derive(Debug, Clone)]
+struct Override {
+    option: &'static str,
+    label: &'static str,
+    value: String,
+    /// Value set by the `#SBATCH` lines of the script
+    current: Option<String>,
+}
+
+/// Popup to submit a job's script again, optionally edited and with some
+/// options overridden
+pub struct ResubmitPopup {
+    pub visible: bool,
+    pub job_id: String,
+    job_name: String,
+    /// Copy of the original script that gets submitted, removed once the
+    /// popup is closed
+    script: Option<TempFile>,
+    source: Option<ScriptSource>,
+    /// Working directory of the original job
+    pub work_dir: Option<String>,
+    overrides: Vec<Override>,
+    focus: usize,
+    /// Why the last submission failed
+    pub error: Option<String>,
+}
+
+impl ResubmitPopup {
+    pub fn new() -> Self {
+        Self {
+            visible: false,
+            job_id: String::new(),
+            job_name: String::new(),
+            script: None,
+            source: None,
+            work_dir: None,
+            overrides: Vec::new(),
+            focus: 0,
+            error: None,
+        }
+    }
+
+    /// Fetch the script of a job and show the popup, or explain why the job
+    /// cannot be resubmitted
+    pub fn open(&mut self, job: &Job) -> Result<(), String> {
+        let (script, source) = job_script(&job.id)
+            .ok_or_else(|| format!("No batch script found for job {}", job.id))?;
+
+        // sbatch reads the script when submitting, so the copy can be edited first
+        let copy = TempFile::create(
+            &format!("slurmer-resubmit-{}", job.id),
+            "sh",
+            script.as_bytes(),
+        )
+        .map_err(|e| format!("Failed to write a copy of the script: {}", e))?;
+
+        let directives = parse_directives(&script);
+        self.overrides = OVERRIDES
+            .iter()
+            .map(|&(option, label)| Override {
+                option,
+                label,
+                value: String::new(),
+                current: directives
+                    .iter()
+                    .rev()
+                    .find(|directive| directive.option == option)
+                    .and_then(|directive| directive.value.clone()),
+            })
+            .collect();
+
+        self.job_id = job.id.clone();
+        self.job_name = job.name.clone();
+        self.script = Some(copy);
+        self.source = Some(source);
+        self.work_dir = job.work_dir.clone().or_else(|| {
+            job_fields(&job.id)?
+                .into_iter()
+                .find(|(key, _)| key == "WorkDir")
+                .map(|(_, dir)| dir)
+        });
+        // A job that ran out of time or memory most likely needs more of it.
+        // Memory is raised the way the script requests it.
+        let focused_option = match job.state {
+            JobState::Timeout => Some("time"),
+            JobState::OutOfMemory if directives.iter().any(|d| d.option == "mem-per-cpu") => {
+                Some("mem-per-cpu")
+            }
+            JobState::OutOfMemory => Some("mem"),
+            _ => None,
+        };
+        self.focus = focused_option
+            .and_then(|focused| OVERRIDES.iter().position(|(option, _)| *option == focused))
+            .unwrap_or(0);
+        self.error = None;
+        self.visible = true;
+        Ok(())
+    }
+
+    /// Close the popup, removing the copy of the script. sbatch keeps its own
+    /// copy of submitted scripts.
+    pub fn hide(&mut self) {
+        self.visible = false;
+        self.script = None;
+    }
+
+    /// Path of the copy of the script
+    pub fn script_path(&self) -> Option<&Path> {
+        self.script.as_ref().map(TempFile::path)
+    }
+
+    /// The overrides that were filled in, as sbatch options and values
+    pub fn overrides(&self) -> Result<Vec<(String, String)>, String> {
+        let mut overrides = Vec::new();
+        for field in self
+            .overrides
+            .iter()
+            .filter(|field| !field.value.is_empty())
+        {
+            validate(field.option, &field.value).map_err(|e| format!("{}: {}", field.label, e))?;
+            overrides.push((field.option.to_string(), field.value.trim().to_string()));
+        }
+
+        // sbatch would name the job after the copy of the script, so keep the
+        // original name unless the script, maybe edited, sets one
+        let names_job = self
+            .script_path()
+            .and_then(|path| fs::read_to_string(path).ok())
+            .is_some_and(|script| {
+                parse_directives(&script)
+                    .iter()
+                    .any(|directive| directive.option == "job-name")
+            });
+        if !names_job && !self.job_name.is_empty() {
+            overrides.push(("job-name".to_string(), self.job_name.clone()));
+        }
+        Ok(overrides)
+    }
+
+    pub fn handle_key(&mut self, key: KeyEvent) -> ResubmitAction {
+        match (key.modifiers, key.code) {
+            (_, KeyCode::Esc) => ResubmitAction::Close,
+            (KeyModifiers::CONTROL, KeyCode::Char('e')) => match self.script_path() {
+                Some(path) => ResubmitAction::Edit(path.to_path_buf()),
+                None => ResubmitAction::None,
+            },
+            (_, KeyCode::Enter) => match self.overrides() {
+                Ok(_) => ResubmitAction::Submit,
+                Err(e) => {
+                    self.error = Some(e);
+                    ResubmitAction::None
+                }
+            },
+            (_, KeyCode::Up) | (_, KeyCode::BackTab) => {
+                self.focus = self
+                    .focus
+                    .checked_sub(1)
+                    .unwrap_or(self.overrides.len() - 1);
+                ResubmitAction::None
+            }
+            (_, KeyCode::Down) | (_, KeyCode::Tab) => {
+                self.focus = (self.focus + 1) % self.overrides.len();
+                ResubmitAction::None
+            }
+            (_, KeyCode::Backspace) => {
+                if let Some(field) = self.overrides.get_mut(self.focus) {
+                    field.value.pop();
+                }
+                ResubmitAction::None
+            }
+            (_, KeyCode::Char(c)) => {
+                if let Some(field) = self.overrides.get_mut(self.focus) {
+                    field.value.push(c);
+                }
+                ResubmitAction::None
+            }
+            _ => ResubmitAction::None,
+        }
+    }
+
+    pub fn render(&self, frame: &mut Frame, area: Rect) {
+        if !self.visible {
+            return;
+        }
+
+        frame.render_widget(Clear, area);
+
+        let block = Block::default()
+            .title(format!("Resubmit {}/{}", self.job_name, self.job_id))
+            .borders(Borders::ALL)
+            .border_style(Style::default().fg(Color::Cyan));
+        let inner = block.inner(area);
+
+        let label_width = OVERRIDES
+            .iter()
+            .map(|(_, label)| label.len())
+            .max()
+            .unwrap_or(0);
+        let source = self
+            .source
+            .map(|source| format!(" (from {})", source))
+            .unwrap_or_default();
+
+        let mut lines = vec![
+            Line::from(vec![
+                Span::styled("Script: ", Style::default().fg(Color::Gray)),
+                Span::raw(format!(
+                    "{}{}",
+                    self.script_path().unwrap_or(Path::new("")).display(),
+                    source
+                )),
+            ]),
+            Line::from(vec![
+                Span::styled("Submit from: ", Style::default().fg(Color::Gray)),
+                Span::raw(self.work_dir.clone().unwrap_or_else(|| ".".to_string())),
+            ]),
+            Line::raw(""),
+            Line::styled(
+                "Overrides (empty keeps the script's value):",
+                Style::default().fg(Color::Gray),
+            ),
+        ];
+        let first_field_row = lines.len();
+
+        for (i, field) in self.overrides.iter().enumerate() {
+            let invalid = !field.value.is_empty() && validate(field.option, &field.value).is_err();
+            let style = match (i == self.focus, invalid) {
+                (_, true) => Style::default().fg(Color::Red),
+                (true, false) => Style::default().fg(Color::Cyan),
+                (false, false) => Style::default(),
+            };
+            let current = match &field.current {
+                Some(current) => format!("  script: {}", current),
+                None => String::new(),
+            };
+            lines.push(Line::from(vec![
+                Span::styled(
+                    format!("{:<width$} ", field.label, width = label_width),
+                    style,
+                ),
+                Span::styled(
+                    format!("--{}=", field.option),
+                    Style::default().fg(Color::Gray),
+                ),
+                Span::styled(field.value.clone(), style),
+                Span::styled(current, Style::default().fg(Color::DarkGray)),
+            ]));
+        }
+
+        lines.push(Line::raw(""));
+        if let Some(error) = &self.error {
+            lines.push(Line::styled(error.clone(), Style::default().fg(Color::Red)));
+        }
+        lines.push(Line::styled(
+            "↑/↓: Navigate | Type: Override | Ctrl+e: Edit script | Enter: Submit | Esc: Cancel",
+            Style::default().fg(Color::Gray),
+        ));
+
+        frame.render_widget(Paragraph::new(Text::from(lines)).block(block), area);
+
+        // Show the cursor at the end of the focused field
+        if let Some(field) = self.overrides.get(self.focus) {
+            let x = inner.x
+                + (label_width + 1 + field.option.len() + 3 + field.value.chars().count()) as u16;
+            let y = inner.y + (first_field_row + self.focus) as u16;
+            if x < inner.right() && y < inner.bottom() {
+                frame.set_cursor_position(Position { x, y });
+            }
+        }
+    }
+}
+
+/// Check an override value before handing it to sbatch
+fn validate(option: &str, value: &str) -> Result<(), String> {
+    let value = value.trim();
+    match option {
+        "time" => value.parse::<SlurmDuration>().map(|_| ()),
+        "mem" | "mem-per-cpu" => value.parse::<Memory>().map(|_| ()),
+        // A count, or a min-max range for nodes
+        "cpus-per-task" | "nodes" | "ntasks" => {
+            let valid = value
+                .split_once('-')
+                .filter(|_| option == "nodes")
+                .map_or(value.parse::<u32>().is_ok(), |(min, max)| {
+                    min.parse::<u32>().is_ok() && max.parse::<u32>().is_ok()
+                });
+            if valid {
+                Ok(())
+            } else {
+                Err(format!("Invalid count: {}", value))
+            }
+        }
+        _ => Ok(()),
+    }
+}
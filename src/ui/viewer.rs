@@ -0,0 +1,118 @@
+use color_eyre::{
+    eyre::{eyre, WrapErr},
+    Result,
+};
+use crossterm::event::{KeyCode, KeyEvent};
+use ratatui::{
+    layout::Rect,
+    style::{Color, Style},
+    text::{Line, Span},
+    widgets::{Clear, Paragraph},
+    Frame,
+};
+use std::{
+    fs,
+    path::{Path, PathBuf},
+};
+
+use crate::utils::external::ExternalProgram;
+
+/// What a file viewer asks the app to do after a key press
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ViewerAction {
+    None,
+    /// Suspend the UI and open a file in an external program
+    Open(ExternalProgram, PathBuf),
+    /// Like `Open`, for a temporary copy whose changes are not kept
+    OpenCopy(ExternalProgram, PathBuf),
+    /// Show a message in the header
+    Status(String),
+    /// Copy text to the clipboard
+    Copy(String),
+}
+
+/// Prompt for the path to save a copy of the viewed file to
+#[derive(Debug, Default)]
+pub struct SavePrompt {
+    pub visible: bool,
+    pub input: String,
+    /// File to copy
+    source: PathBuf,
+}
+
+impl SavePrompt {
+    /// Ask where to copy `source`, suggesting `default_path`
+    pub fn open(&mut self, source: PathBuf, default_path: String) {
+        self.visible = true;
+        self.source = source;
+        self.input = default_path;
+    }
+
+    pub fn handle_key(&mut self, key: KeyEvent) -> ViewerAction {
+        match key.code {
+            KeyCode::Enter => {
+                self.visible = false;
+                match save_copy(&self.source, &self.input) {
+                    Ok(path) => ViewerAction::Status(format!("Saved a copy to {}", path.display())),
+                    Err(e) => ViewerAction::Status(format!("Failed to save a copy: {:#}", e)),
+                }
+            }
+            KeyCode::Esc => {
+                self.visible = false;
+                ViewerAction::None
+            }
+            KeyCode::Backspace => {
+                self.input.pop();
+                ViewerAction::None
+            }
+            KeyCode::Char(c) => {
+                self.input.push(c);
+                ViewerAction::None
+            }
+            _ => ViewerAction::None,
+        }
+    }
+
+    /// Render the prompt on a single row
+    pub fn render(&self, frame: &mut Frame, area: Rect) {
+        let prompt = Line::from(vec![
+            Span::styled("Save a copy to: ", Style::default().fg(Color::Yellow)),
+            Span::raw(self.input.clone()),
+        ]);
+        frame.render_widget(Clear, area);
+        frame.render_widget(
+            Paragraph::new(prompt).style(Style::default().bg(Color::DarkGray)),
+            area,
+        );
+    }
+}
+
+/// Path typed into a prompt, where `~` stands for the home directory
+pub fn resolve_path(input: &str) -> Result<PathBuf> {
+    let input = input.trim();
+    if input.is_empty() {
+        return Err(eyre!("No path given"));
+    }
+    match input.strip_prefix("~/") {
+        Some(rest) => std::env::var_os("HOME")
+            .map(|home| PathBuf::from(home).join(rest))
+            .ok_or_else(|| eyre!("Cannot determine the home directory")),
+        None => Ok(PathBuf::from(input)),
+    }
+}
+
+/// Copy a file to `destination`. Returns the path written.
+fn save_copy(source: &Path, destination: &str) -> Result<PathBuf> {
+    let destination = resolve_path(destination)?;
+
+    // Copying a file onto itself would truncate it
+    if let (Ok(from), Ok(to)) = (source.canonicalize(), destination.canonicalize()) {
+        if from == to {
+            return Err(eyre!("{} is the file itself", destination.display()));
+        }
+    }
+
+    fs::copy(source, &destination)
+        .wrap_err_with(|| format!("Cannot copy to {}", destination.display()))?;
+    Ok(destination)
+}
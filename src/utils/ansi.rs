@@ -0,0 +1,225 @@
+use ratatui::style::{Color, Modifier, Style};
+use std::{borrow::Cow, ops::Range};
+
+const ESC: char = '\x1B';
+const BEL: char = '\x07';
+
+/// A line with its ANSI escape sequences removed, and the style of each part
+#[derive(Debug, Clone, Default)]
+pub struct StyledLine {
+    /// The visible text
+    pub text: String,
+    /// Byte ranges of `text` with their style, in order and without gaps
+    pub runs: Vec<(Range<usize>, Style)>,
+}
+
+/// Parse a line starting with `style`. SGR sequences change the style, other
+/// escape sequences (cursor movement, erasing, titles, hyperlinks) are
+/// dropped. Returns the line and the style at its end.
+pub fn parse_line(line: &str, mut style: Style) -> (StyledLine, Style) {
+    let mut styled = StyledLine {
+        text: String::with_capacity(line.len()),
+        runs: Vec::new(),
+    };
+    let mut run_start = 0;
+
+    let mut rest = line;
+    while let Some(pos) = rest.find(ESC) {
+        styled.text.push_str(&rest[..pos]);
+        let (sequence, len) = split_escape(&rest[pos..]);
+        rest = &rest[pos + len..];
+
+        if let Escape::Sgr(params) = sequence {
+            let new_style = apply_sgr(params, style);
+            if new_style != style {
+                if styled.text.len() > run_start {
+                    styled.runs.push((run_start..styled.text.len(), style));
+                    run_start = styled.text.len();
+                }
+                style = new_style;
+            }
+        }
+    }
+    styled.text.push_str(rest);
+    if styled.text.len() > run_start {
+        styled.runs.push((run_start..styled.text.len(), style));
+    }
+
+    (styled, style)
+}
+
+/// Remove all escape sequences from the text
+pub fn strip(text: &str) -> Cow<'_, str> {
+    if !text.contains(ESC) {
+        return Cow::Borrowed(text);
+    }
+
+    let mut stripped = String::with_capacity(text.len());
+    let mut rest = text;
+    while let Some(pos) = rest.find(ESC) {
+        stripped.push_str(&rest[..pos]);
+        let (_, len) = split_escape(&rest[pos..]);
+        rest = &rest[pos + len..];
+    }
+    stripped.push_str(rest);
+    Cow::Owned(stripped)
+}
+
+/// Kinds of escape sequences
+enum Escape<'a> {
+    /// Select Graphic Rendition, with its parameters
+    Sgr(&'a str),
+    /// Anything else, which is dropped
+    Other,
+}
+
+/// Split the escape sequence at the start of `s` (which starts with ESC),
+/// returning it and its length in bytes. Incomplete sequences take the rest
+/// of the line, never its newline, so that stripping text keeps its lines.
+fn split_escape(s: &str) -> (Escape<'_>, usize) {
+    let s = &s[..s.find('\n').unwrap_or(s.len())];
+    let body = &s[ESC.len_utf8()..];
+    match body.chars().next() {
+        // Control Sequence Introducer: parameters, intermediates, final byte
+        Some('[') => {
+            let params = &body[1..];
+            match params.find(|c: char| ('@'..='~').contains(&c)) {
+                Some(end) => {
+                    let len = ESC.len_utf8() + 1 + end + 1;
+                    if params.as_bytes()[end] == b'm' {
+                        (Escape::Sgr(&params[..end]), len)
+                    } else {
+                        (Escape::Other, len)
+                    }
+                }
+                None => (Escape::Other, s.len()),
+            }
+        }
+        // Operating System Command, ended by BEL or ESC \
+        Some(']') => {
+            let command = &body[1..];
+            let end = command
+                .find(BEL)
+                .map(|i| i + 1)
+                .or_else(|| command.find("\x1B\\").map(|i| i + 2))
+                .unwrap_or(command.len());
+            (Escape::Other, ESC.len_utf8() + 1 + end)
+        }
+        // Character set selection takes one more character
+        Some(c @ ('(' | ')' | '*' | '+')) => {
+            let len = body[c.len_utf8()..]
+                .chars()
+                .next()
+                .map_or(0, char::len_utf8);
+            (Escape::Other, ESC.len_utf8() + c.len_utf8() + len)
+        }
+        // Other two-character sequences, such as ESC = or ESC 7
+        Some(c) => (Escape::Other, ESC.len_utf8() + c.len_utf8()),
+        None => (Escape::Other, s.len()),
+    }
+}
+
+/// Apply SGR parameters such as `1;31` or `38;2;255;128;0` to a style
+fn apply_sgr(params: &str, mut style: Style) -> Style {
+    // An empty parameter list is a reset
+    if params.is_empty() {
+        return Style::default();
+    }
+
+    let mut iter = params
+        .split([';', ':'])
+        .map(|p| p.parse::<u16>().unwrap_or(0));
+    while let Some(code) = iter.next() {
+        style = match code {
+            0 => Style::default(),
+            1 => style.add_modifier(Modifier::BOLD),
+            2 => style.add_modifier(Modifier::DIM),
+            3 => style.add_modifier(Modifier::ITALIC),
+            4 => style.add_modifier(Modifier::UNDERLINED),
+            5 => style.add_modifier(Modifier::SLOW_BLINK),
+            6 => style.add_modifier(Modifier::RAPID_BLINK),
+            7 => style.add_modifier(Modifier::REVERSED),
+            8 => style.add_modifier(Modifier::HIDDEN),
+            9 => style.add_modifier(Modifier::CROSSED_OUT),
+            22 => style.remove_modifier(Modifier::BOLD | Modifier::DIM),
+            23 => style.remove_modifier(Modifier::ITALIC),
+            24 => style.remove_modifier(Modifier::UNDERLINED),
+            25 => style.remove_modifier(Modifier::SLOW_BLINK | Modifier::RAPID_BLINK),
+            27 => style.remove_modifier(Modifier::REVERSED),
+            28 => style.remove_modifier(Modifier::HIDDEN),
+            29 => style.remove_modifier(Modifier::CROSSED_OUT),
+            30..=37 => style.fg(Color::Indexed((code - 30) as u8)),
+            38 => match extended_color(&mut iter) {
+                Some(color) => style.fg(color),
+                None => style,
+            },
+            39 => Style { fg: None, ..style },
+            40..=47 => style.bg(Color::Indexed((code - 40) as u8)),
+            48 => match extended_color(&mut iter) {
+                Some(color) => style.bg(color),
+                None => style,
+            },
+            49 => Style { bg: None, ..style },
+            // Bright colors map to indices 8-15
+            90..=97 => style.fg(Color::Indexed((code - 90 + 8) as u8)),
+            100..=107 => style.bg(Color::Indexed((code - 100 + 8) as u8)),
+            _ => style,
+        };
+    }
+
+    style
+}
+
+/// Read the rest of a 256-color (`5;n`) or 24-bit (`2;r;g;b`) color
+fn extended_color(iter: &mut impl Iterator<Item = u16>) -> Option<Color> {
+    match iter.next()? {
+        5 => Some(Color::Indexed(iter.next()?.min(255) as u8)),
+        2 => {
+            let r = iter.next()?.min(255) as u8;
+            let g = iter.next()?.min(255) as u8;
+            let b = iter.next()?.min(255) as u8;
+            Some(Color::Rgb(r, g, b))
+        }
+        _ => None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn strips_escape_sequences() {
+        assert_eq!(strip("plain"), "plain");
+        assert_eq!(strip("\x1B[1;31merror\x1B[0m: x"), "error: x");
+        assert_eq!(
+            strip("\x1B]0;title\x07text\x1B]8;;url\x1B\\link"),
+            "textlink"
+        );
+        assert_eq!(strip("\x1B(Bab\x1B7c"), "abc");
+    }
+
+    #[test]
+    fn unterminated_sequences_end_at_the_newline() {
+        assert_eq!(strip("a\x1B[12\nb\nc"), "a\nb\nc");
+        assert_eq!(strip("a\x1B]0;title\nb"), "a\nb");
+        assert_eq!(strip("a\x1B(\nb"), "a\nb");
+        assert_eq!(strip("a\x1B\nb"), "a\nb");
+        assert_eq!(strip("a\x1B[1"), "a");
+    }
+
+    #[test]
+    fn parses_styled_runs() {
+        let (line, style) = parse_line("a\x1B[31mbé\x1B[mc", Style::default());
+        assert_eq!(line.text, "abéc");
+        assert_eq!(
+            line.runs,
+            vec![
+                (0..1, Style::default()),
+                (1..4, Style::default().fg(Color::Indexed(1))),
+                (4..5, Style::default()),
+            ]
+        );
+        assert_eq!(style, Style::default());
+    }
+}
@@ -0,0 +1,87 @@
+use std::io::{self, Write};
+
+use crate::config::Osc52;
+use crate::utils::notification::tmux_passthrough;
+
+const BASE64_ALPHABET: &[u8; 64] =
+    b"ABCDEFGHIJKLMNOPQRSTUVWXYZabcdefghijklmnopqrstuvwxyz0123456789+/";
+
+/// Copies text to the system clipboard through the terminal with OSC 52, which
+/// also works over SSH. The last copied text is kept in a register that can be
+/// pasted into slurmer's own text fields, for terminals without OSC 52.
+pub struct Clipboard {
+    osc52: bool,
+    register: Option<String>,
+}
+
+impl Clipboard {
+    pub fn new(osc52: Osc52) -> Self {
+        let osc52 = match osc52 {
+            Osc52::Always => true,
+            Osc52::Never => false,
+            Osc52::Auto => osc52_supported(),
+        };
+        Self {
+            osc52,
+            register: None,
+        }
+    }
+
+    /// Copy text, returning whether it was sent to the system clipboard
+    pub fn copy(&mut self, text: String) -> bool {
+        let sent = self.osc52 && send_osc52(&text);
+        self.register = Some(text);
+        sent
+    }
+
+    /// The last copied text
+    pub fn register(&self) -> Option<&str> {
+        self.register.as_deref()
+    }
+}
+
+/// Whether the terminal is likely to understand OSC 52. Terminals that don't
+/// just ignore the sequence, so only those known to lack it are excluded.
+fn osc52_supported() -> bool {
+    let var = |name: &str| std::env::var(name).unwrap_or_default();
+    let term = var("TERM");
+    !(term.is_empty()
+        || term == "dumb"
+        || term == "linux"
+        || var("TERM_PROGRAM") == "Apple_Terminal"
+        // GNOME Terminal and other VTE based terminals
+        || (std::env::var_os("VTE_VERSION").is_some() && std::env::var_os("TMUX").is_none()))
+}
+
+fn send_osc52(text: &str) -> bool {
+    let sequence = format!("\x1b]52;c;{}\x07", base64(text.as_bytes()));
+    // tmux takes the sequence itself with `set-clipboard on`, and hands it on
+    // to the outer terminal with `allow-passthrough on`
+    let mut output = sequence.clone();
+    if std::env::var_os("TMUX").is_some() {
+        output.push_str(&tmux_passthrough(&sequence));
+    }
+
+    let mut stdout = io::stdout();
+    stdout.write_all(output.as_bytes()).is_ok() && stdout.flush().is_ok()
+}
+
+fn base64(bytes: &[u8]) -> String {
+    let mut encoded = String::with_capacity(bytes.len().div_ceil(3) * 4);
+    for chunk in bytes.chunks(3) {
+        let b = [
+            chunk[0],
+            chunk.get(1).copied().unwrap_or(0),
+            chunk.get(2).copied().unwrap_or(0),
+        ];
+        let n = (b[0] as u32) << 16 | (b[1] as u32) << 8 | b[2] as u32;
+        for i in 0..4 {
+            if i <= chunk.len() {
+                encoded.push(BASE64_ALPHABET[(n >> (18 - 6 * i) & 0x3f) as usize] as char);
+            } else {
+                encoded.push('=');
+            }
+        }
+    }
+    encoded
+}
@@ -0,0 +1,129 @@
+/// Largest table of the longest common subsequence, in cells; texts differing
+/// in more lines are shown as entirely replaced
+const MAX_TABLE_CELLS: usize = 4_000_000;
+
+/// One step of a line diff
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum DiffOp {
+    /// Line of the old text, equal to the line of the new text
+    Equal(usize, usize),
+    /// Line of the old text missing from the new one
+    Removed(usize),
+    /// Line of the new text missing from the old one
+    Added(usize),
+}
+
+/// Diff two texts line by line. The lines between their common prefix and
+/// suffix are matched by their longest common subsequence, removed lines
+/// coming before added ones.
+pub fn diff_lines<T: PartialEq>(old: &[T], new: &[T]) -> Vec<DiffOp> {
+    let prefix = old.iter().zip(new).take_while(|(a, b)| a == b).count();
+    let suffix = old[prefix..]
+        .iter()
+        .rev()
+        .zip(new[prefix..].iter().rev())
+        .take_while(|(a, b)| a == b)
+        .count();
+    let a = &old[prefix..old.len() - suffix];
+    let b = &new[prefix..new.len() - suffix];
+
+    let mut ops: Vec<DiffOp> = (0..prefix).map(|i| DiffOp::Equal(i, i)).collect();
+
+    if (a.len() + 1) * (b.len() + 1) > MAX_TABLE_CELLS {
+        ops.extend((0..a.len()).map(|i| DiffOp::Removed(prefix + i)));
+        ops.extend((0..b.len()).map(|j| DiffOp::Added(prefix + j)));
+    } else {
+        // lcs[i][j]: length of the longest common subsequence of a[i..] and b[j..]
+        let width = b.len() + 1;
+        let mut lcs = vec![0u32; (a.len() + 1) * width];
+        for i in (0..a.len()).rev() {
+            for j in (0..b.len()).rev() {
+                lcs[i * width + j] = if a[i] == b[j] {
+                    lcs[(i + 1) * width + j + 1] + 1
+                } else {
+                    lcs[(i + 1) * width + j].max(lcs[i * width + j + 1])
+                };
+            }
+        }
+
+        let (mut i, mut j) = (0, 0);
+        while i < a.len() || j < b.len() {
+            if i < a.len() && j < b.len() && a[i] == b[j] {
+                ops.push(DiffOp::Equal(prefix + i, prefix + j));
+                i += 1;
+                j += 1;
+            } else if j == b.len()
+                || (i < a.len() && lcs[(i + 1) * width + j] >= lcs[i * width + j + 1])
+            {
+                ops.push(DiffOp::Removed(prefix + i));
+                i += 1;
+            } else {
+                ops.push(DiffOp::Added(prefix + j));
+                j += 1;
+            }
+        }
+    }
+
+    let old_end = old.len() - suffix;
+    let new_end = new.len() - suffix;
+    ops.extend((0..suffix).map(|k| DiffOp::Equal(old_end + k, new_end + k)));
+    ops
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use DiffOp::*;
+
+    #[test]
+    fn equal_texts_only_have_equal_lines() {
+        assert_eq!(
+            diff_lines(&["a", "b"], &["a", "b"]),
+            vec![Equal(0, 0), Equal(1, 1)]
+        );
+        assert_eq!(diff_lines::<&str>(&[], &[]), vec![]);
+    }
+
+    #[test]
+    fn finds_added_and_removed_lines() {
+        assert_eq!(
+            diff_lines(&["a", "b", "c"], &["a", "x", "b", "c"]),
+            vec![Equal(0, 0), Added(1), Equal(1, 2), Equal(2, 3)]
+        );
+        assert_eq!(
+            diff_lines(&["a", "b", "c"], &["a", "c"]),
+            vec![Equal(0, 0), Removed(1), Equal(2, 1)]
+        );
+    }
+
+    #[test]
+    fn lists_removed_lines_before_added_ones() {
+        assert_eq!(
+            diff_lines(&["a", "b", "c", "d"], &["a", "x", "y", "d"]),
+            vec![
+                Equal(0, 0),
+                Removed(1),
+                Removed(2),
+                Added(1),
+                Added(2),
+                Equal(3, 3)
+            ]
+        );
+    }
+
+    #[test]
+    fn keeps_common_lines_between_changes() {
+        assert_eq!(
+            diff_lines(&[1, 2, 3, 4, 5], &[0, 2, 4, 5, 6]),
+            vec![
+                Removed(0),
+                Added(0),
+                Equal(1, 1),
+                Removed(2),
+                Equal(3, 2),
+                Equal(4, 3),
+                Added(4)
+            ]
+        );
+    }
+}
@@ -1,6 +1,9 @@
 use crossterm::event::{self, Event as CrosstermEvent, KeyEvent, MouseEvent};
 use std::{
-    sync::mpsc,
+    sync::{
+        atomic::{AtomicBool, Ordering},
+        mpsc, Arc,
+    },
     thread,
     time::{Duration, Instant},
 };
@@ -47,44 +50,60 @@ pub struct EventHandler {
     /// Thread handle for the event handler
     #[allow(dead_code)]
     handle: thread::JoinHandle<()>,
+    /// Set while another program uses the terminal, so its input is left alone
+    paused: Arc<AtomicBool>,
+    /// Set by the event thread once it stopped reading input
+    idle: Arc<AtomicBool>,
 }
 
 impl EventHandler {
     /// Create a new event handler with the given configuration
     pub fn new(config: EventConfig) -> Self {
         let (tx, rx) = mpsc::channel();
+        let paused = Arc::new(AtomicBool::new(false));
+        let idle = Arc::new(AtomicBool::new(false));
         let handle = {
             let tx = tx.clone();
+            let paused = paused.clone();
+            let idle = idle.clone();
             thread::spawn(move || {
                 let tick_rate = config.tick_rate;
                 let mut last_tick = Instant::now();
 
                 loop {
+                    if paused.load(Ordering::SeqCst) {
+                        idle.store(true, Ordering::SeqCst);
+                        thread::sleep(Duration::from_millis(20));
+                        continue;
+                    }
+                    // Check again, a pause may have seen the previous idle state
+                    idle.store(false, Ordering::SeqCst);
+                    if paused.load(Ordering::SeqCst) {
+                        continue;
+                    }
+
                     let timeout = tick_rate
                         .checked_sub(last_tick.elapsed())
                         .unwrap_or(Duration::from_secs(0));
 
                     if event::poll(timeout).expect("Failed to poll for events") {
-                        match event::read().expect("Failed to read event") {
-                            CrosstermEvent::Key(key) => {
-                                if tx.send(Event::Key(key)).is_err() {
-                                    return;
-                                }
-                            }
+                        let event = match event::read().expect("Failed to read event") {
+                            CrosstermEvent::Key(key) => Some(Event::Key(key)),
                             CrosstermEvent::Mouse(mouse) => {
                                 if !config.enable_mouse_capture {
                                     continue;
                                 }
-                                if tx.send(Event::Mouse(mouse)).is_err() {
-                                    return;
-                                }
+                                Some(Event::Mouse(mouse))
                             }
                             CrosstermEvent::Resize(width, height) => {
-                                if tx.send(Event::Resize(width, height)).is_err() {
-                                    return;
-                                }
+                                Some(Event::Resize(width, height))
+                            }
+                            _ => None,
+                        };
+                        if let Some(event) = event {
+                            if tx.send(event).is_err() {
+                                return;
                             }
-                            _ => {}
                         }
                     }
 
@@ -98,6 +117,25 @@ impl EventHandler {
             })
         };
 
-        Self { rx, tx, handle }
+        Self {
+            rx,
+            tx,
+            handle,
+            paused,
+            idle,
+        }
+    }
+
+    /// Stop reading terminal events, waiting for a pending read to finish
+    pub fn pause(&mut self) {
+        self.paused.store(true, Ordering::SeqCst);
+        while !self.idle.load(Ordering::SeqCst) {
+            thread::sleep(Duration::from_millis(5));
+        }
+    }
+
+    /// Read terminal events again
+    pub fn resume(&mut self) {
+        self.paused.store(false, Ordering::SeqCst);
     }
 }
@@ -0,0 +1,235 @@
+use std::{fmt, str::FromStr};
+
+use crate::slurm::{columns::JobColumn, fields::TimeFormat, Job};
+
+/// Text formats a job list can be written in
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum OutputFormat {
+    /// Aligned columns, for reading in a terminal
+    #[default]
+    Table,
+    /// An array of objects keyed by column title
+    Json,
+    Csv,
+    Tsv,
+    /// A Markdown table, for reports and tickets
+    Markdown,
+}
+
+impl OutputFormat {
+    pub fn all() -> &'static [OutputFormat] {
+        &[
+            OutputFormat::Table,
+            OutputFormat::Json,
+            OutputFormat::Csv,
+            OutputFormat::Tsv,
+            OutputFormat::Markdown,
+        ]
+    }
+
+    /// File name extension for this format
+    pub fn extension(&self) -> &'static str {
+        match self {
+            OutputFormat::Table => "txt",
+            OutputFormat::Json => "json",
+            OutputFormat::Csv => "csv",
+            OutputFormat::Tsv => "tsv",
+            OutputFormat::Markdown => "md",
+        }
+    }
+}
+
+impl fmt::Display for OutputFormat {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let name = match self {
+            OutputFormat::Table => "table",
+            OutputFormat::Json => "json",
+            OutputFormat::Csv => "csv",
+            OutputFormat::Tsv => "tsv",
+            OutputFormat::Markdown => "markdown",
+        };
+        write!(f, "{}", name)
+    }
+}
+
+impl FromStr for OutputFormat {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        OutputFormat::all()
+            .iter()
+            .find(|format| format.to_string().eq_ignore_ascii_case(s))
+            .copied()
+            .or_else(|| {
+                s.eq_ignore_ascii_case("md")
+                    .then_some(OutputFormat::Markdown)
+            })
+            .ok_or_else(|| format!("Unknown format: {}", s))
+    }
+}
+
+/// Write jobs in the given format, one field per column
+pub fn format_jobs(
+    jobs: &[Job],
+    columns: &[JobColumn],
+    format: OutputFormat,
+    time_format: TimeFormat,
+) -> String {
+    let header: Vec<String> = columns.iter().map(|col| col.title().to_string()).collect();
+    let rows: Vec<Vec<String>> = jobs
+        .iter()
+        .map(|job| {
+            columns
+                .iter()
+                .map(|col| col.display(job, time_format))
+                .collect()
+        })
+        .collect();
+
+    match format {
+        OutputFormat::Table => format_table(&header, &rows),
+        OutputFormat::Json => format_json(&header, &rows),
+        OutputFormat::Csv => format_delimited(&header, &rows, ',', csv_field),
+        OutputFormat::Tsv => format_delimited(&header, &rows, '\t', tsv_field),
+        OutputFormat::Markdown => format_markdown(&header, &rows),
+    }
+}
+
+/// Align rows under a header, two spaces apart
+pub fn format_table(header: &[String], rows: &[Vec<String>]) -> String {
+    let widths: Vec<usize> = (0..header.len())
+        .map(|i| {
+            rows.iter()
+                .map(|row| row[i].chars().count())
+                .chain([header[i].chars().count()])
+                .max()
+                .unwrap_or(0)
+        })
+        .collect();
+
+    let mut output = String::new();
+    for row in [header].into_iter().chain(rows.iter().map(Vec::as_slice)) {
+        let line = row
+            .iter()
+            .zip(&widths)
+            .map(|(value, width)| format!("{:<width$}", value, width = width))
+            .collect::<Vec<_>>()
+            .join("  ");
+        output.push_str(line.trim_end());
+        output.push('\n');
+    }
+    output
+}
+
+/// A pipe table, padded so that it also reads well as plain text
+fn format_markdown(header: &[String], rows: &[Vec<String>]) -> String {
+    let header: Vec<String> = header.iter().map(|title| markdown_cell(title)).collect();
+    let rows: Vec<Vec<String>> = rows
+        .iter()
+        .map(|row| row.iter().map(|value| markdown_cell(value)).collect())
+        .collect();
+    let widths: Vec<usize> = (0..header.len())
+        .map(|i| {
+            rows.iter()
+                .map(|row| row[i].chars().count())
+                .chain([header[i].chars().count(), 3])
+                .max()
+                .unwrap_or(3)
+        })
+        .collect();
+
+    let line = |cells: &[String]| {
+        let cells = cells
+            .iter()
+            .zip(&widths)
+            .map(|(value, width)| format!("{:<width$}", value, width = width))
+            .collect::<Vec<_>>()
+            .join(" | ");
+        format!("| {} |\n", cells)
+    };
+    let separator: Vec<String> = widths.iter().map(|width| "-".repeat(*width)).collect();
+
+    let mut output = line(&header);
+    output.push_str(&line(&separator));
+    for row in &rows {
+        output.push_str(&line(row));
+    }
+    output
+}
+
+/// Pipes end a Markdown table cell and line breaks the row
+fn markdown_cell(value: &str) -> String {
+    value.replace('|', "\\|").replace(['\n', '\r'], " ")
+}
+
+fn format_json(header: &[String], rows: &[Vec<String>]) -> String {
+    let objects: Vec<String> = rows
+        .iter()
+        .map(|row| {
+            let fields = header
+                .iter()
+                .zip(row)
+                .map(|(key, value)| format!("{}: {}", json_string(key), json_string(value)))
+                .collect::<Vec<_>>()
+                .join(", ");
+            format!("  {{{}}}", fields)
+        })
+        .collect();
+
+    if objects.is_empty() {
+        "[]\n".to_string()
+    } else {
+        format!("[\n{}\n]\n", objects.join(",\n"))
+    }
+}
+
+fn format_delimited(
+    header: &[String],
+    rows: &[Vec<String>],
+    delimiter: char,
+    field: fn(&str) -> String,
+) -> String {
+    let mut output = String::new();
+    for row in [header].into_iter().chain(rows.iter().map(Vec::as_slice)) {
+        let line = row
+            .iter()
+            .map(|value| field(value))
+            .collect::<Vec<_>>()
+            .join(&delimiter.to_string());
+        output.push_str(&line);
+        output.push('\n');
+    }
+    output
+}
+
+/// Quote a CSV field when it contains a delimiter, quote or line break
+fn csv_field(value: &str) -> String {
+    if value.contains([',', '"', '\n', '\r']) {
+        format!("\"{}\"", value.replace('"', "\"\""))
+    } else {
+        value.to_string()
+    }
+}
+
+/// TSV has no quoting, so tabs and line breaks become spaces
+fn tsv_field(value: &str) -> String {
+    value.replace(['\t', '\n', '\r'], " ")
+}
+
+fn json_string(value: &str) -> String {
+    let mut escaped = String::with_capacity(value.len() + 2);
+    escaped.push('"');
+    for c in value.chars() {
+        match c {
+            '"' => escaped.push_str("\\\""),
+            '\\' => escaped.push_str("\\\\"),
+            '\n' => escaped.push_str("\\n"),
+            '\r' => escaped.push_str("\\r"),
+            '\t' => escaped.push_str("\\t"),
+            c if (c as u32) < 0x20 => escaped.push_str(&format!("\\u{:04x}", c as u32)),
+            c => escaped.push(c),
+        }
+    }
+    escaped.push('"');
+    escaped
+}
@@ -0,0 +1,68 @@
+use color_eyre::{
+    eyre::{eyre, WrapErr},
+    Result,
+};
+use crossterm::{
+    event::{DisableMouseCapture, EnableMouseCapture},
+    execute,
+    terminal::{disable_raw_mode, enable_raw_mode, EnterAlternateScreen, LeaveAlternateScreen},
+};
+use std::{
+    io,
+    path::Path,
+    process::{Command, ExitStatus},
+};
+
+/// Programs a file can be opened in while the TUI is suspended
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ExternalProgram {
+    Pager,
+    Editor,
+}
+
+impl ExternalProgram {
+    /// Command line from `$PAGER`, or `$VISUAL`/`$EDITOR`, with a fallback
+    fn command(&self) -> Vec<String> {
+        let (vars, fallback): (&[&str], &str) = match self {
+            // -R keeps the colors of logs
+            ExternalProgram::Pager => (&["PAGER"], "less -R"),
+            ExternalProgram::Editor => (&["VISUAL", "EDITOR"], "vi"),
+        };
+        let command = vars
+            .iter()
+            .filter_map(|var| std::env::var(var).ok())
+            .find(|value| !value.trim().is_empty())
+            .unwrap_or_else(|| fallback.to_string());
+        command.split_whitespace().map(str::to_string).collect()
+    }
+
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            ExternalProgram::Pager => "pager",
+            ExternalProgram::Editor => "editor",
+        }
+    }
+}
+
+/// Leave the alternate screen and raw mode, run the program on the file until
+/// it exits, then restore the terminal
+pub fn run_suspended(program: ExternalProgram, path: &Path) -> Result<ExitStatus> {
+    let command = program.command();
+    let (name, args) = command
+        .split_first()
+        .ok_or_else(|| eyre!("No {} configured", program.as_str()))?;
+
+    disable_raw_mode()?;
+    execute!(io::stdout(), LeaveAlternateScreen, DisableMouseCapture)?;
+
+    let status = Command::new(name)
+        .args(args)
+        .arg(path)
+        .status()
+        .wrap_err_with(|| format!("Failed to run {}", name));
+
+    enable_raw_mode()?;
+    execute!(io::stdout(), EnterAlternateScreen, EnableMouseCapture)?;
+
+    status
+}
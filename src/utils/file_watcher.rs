@@ -1,8 +1,7 @@
 use std::{
-    fmt,
-    fs::File,
-    io::{self, Read, Seek},
+    fmt, io,
     path::{Path, PathBuf},
+    sync::{Arc, Mutex},
     thread,
     time::Duration,
 };
@@ -13,42 +12,57 @@ use crossbeam::{
 };
 use notify::{event::ModifyKind, RecursiveMode, Watcher};
 
-type JobOutput = Result<String, FileWatcherError>;
+use super::log_index::{LogIndex, SharedLogIndex};
+
+type JobOutput = Result<SharedLogIndex, FileWatcherError>;
 
 struct FileReader {
-    content_sender: Sender<io::Result<String>>,
+    content_sender: Sender<JobOutput>,
     receiver: Receiver<()>,
-    file_path: PathBuf,
     interval: Duration,
-    content: String,
-    pos: u64,
+    path: PathBuf,
+    index: SharedLogIndex,
+    /// Whether the index was sent to the app at least once
+    sent: bool,
 }
 
 struct FileWatcher {
     app: Sender<JobOutput>,
     receiver: Receiver<FileWatcherMessage>,
-    file_path: Option<PathBuf>,
+    /// Watched files, with the channel that wakes up their reader
+    files: Vec<(PathBuf, Sender<()>)>,
     interval: Duration,
+    /// Memory budget for the tail of each log, in bytes
+    max_retained: usize,
 }
 pub enum FileWatcherMessage {
-    FilePath(Option<PathBuf>),
+    FilePaths(Vec<PathBuf>),
 }
 
 pub struct FileWatcherHandle {
     sender: Sender<FileWatcherMessage>,
-    file_path: Option<PathBuf>,
+    file_paths: Vec<PathBuf>,
 }
 
 pub enum FileWatcherError {
-    Watcher(notify::Error),
-    File(io::Error),
+    Watcher(PathBuf, notify::Error),
+    File(PathBuf, io::Error),
+}
+
+impl FileWatcherError {
+    /// Path of the file the error is about
+    pub fn path(&self) -> &Path {
+        match self {
+            FileWatcherError::Watcher(path, _) | FileWatcherError::File(path, _) => path,
+        }
+    }
 }
 
 impl fmt::Display for FileWatcherError {
     fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
         match self {
-            FileWatcherError::Watcher(e) => write!(f, "Watcher error: {}", e),
-            FileWatcherError::File(e) => write!(f, "Read error: {}", e),
+            FileWatcherError::Watcher(_, e) => write!(f, "Watcher error: {}", e),
+            FileWatcherError::File(_, e) => write!(f, "Read error: {}", e),
         }
     }
 }
@@ -58,12 +72,14 @@ impl FileWatcher {
         app: Sender<JobOutput>,
         receiver: Receiver<FileWatcherMessage>,
         interval: Duration,
+        max_retained: usize,
     ) -> Self {
         FileWatcher {
-            app: app,
-            receiver: receiver,
-            file_path: None,
-            interval: interval,
+            app,
+            receiver,
+            files: Vec::new(),
+            interval,
+            max_retained,
         }
     }
 
@@ -71,49 +87,54 @@ impl FileWatcher {
         let (watch_sender, watch_receiver) = unbounded();
         let mut watcher = notify::recommended_watcher(move |res: notify::Result<notify::Event>| {
             let event = res.unwrap();
-            match event.kind {
-                notify::EventKind::Modify(ModifyKind::Data(_)) => {
-                    watch_sender.send(event.paths).unwrap();
-                }
-                _ => {}
+            if let notify::EventKind::Modify(ModifyKind::Data(_)) = event.kind {
+                watch_sender.send(event.paths).unwrap();
             };
         })
         .unwrap();
 
-        let (mut _content_sender, mut _content_receiver) = unbounded::<io::Result<String>>();
-        let (mut _watch_sender, mut _watch_receiver) = unbounded::<()>();
+        let (mut _content_sender, mut _content_receiver) = unbounded::<JobOutput>();
         loop {
             select! {
                 recv(self.receiver) -> msg => {
                     match msg? {
-                        FileWatcherMessage::FilePath(file_path) => {
+                        FileWatcherMessage::FilePaths(file_paths) => {
+                            // readers of the previous files stop once their channels are dropped
                             (_content_sender, _content_receiver) = unbounded();
-                            (_watch_sender, _watch_receiver) = unbounded::<()>();
 
-                            if let Some(p) = &self.file_path {
-                                watcher.unwatch(p).expect(format!("Failed to unwatch {:?}", p).as_str());
-                                self.file_path = None;
+                            for (p, _) in self.files.drain(..) {
+                                watcher.unwatch(&p).unwrap_or_else(|_| panic!("Failed to unwatch {:?}", p));
                             }
 
-                            if let Some(p) = file_path {
+                            for p in file_paths {
+                                if self.files.iter().any(|(watched, _)| *watched == p) {
+                                    continue;
+                                }
                                 let res = watcher.watch(Path::new(&p), RecursiveMode::NonRecursive);
                                 match res {
                                     Ok(_) => {
-                                        self.file_path = Some(p.clone());
-                                        let i = self.interval.clone();
-                                        thread::spawn(move || FileReader::new(_content_sender, _watch_receiver, p, i).run());
+                                        let (wake_sender, wake_receiver) = unbounded::<()>();
+                                        self.files.push((p.clone(), wake_sender));
+                                        let i = self.interval;
+                                        let content_sender = _content_sender.clone();
+                                        let index = LogIndex::new(p, self.max_retained);
+                                        thread::spawn(move || FileReader::new(content_sender, wake_receiver, index, i).run());
                                     },
-                                    Err(e) => self.app.send(Err(FileWatcherError::Watcher(e))).unwrap()
+                                    Err(e) => self.app.send(Err(FileWatcherError::Watcher(p, e))).unwrap()
                                 };
-                            } else {
-                                _content_sender.send(Ok("".to_string())).unwrap();
                             }
                         }
                     }
                 }
-                recv(watch_receiver) -> _ => { _watch_sender.send(()).unwrap(); }
+                recv(watch_receiver) -> paths => {
+                    for (p, wake_sender) in &self.files {
+                        if paths.as_ref().is_ok_and(|paths| paths.contains(p)) {
+                            wake_sender.send(()).unwrap();
+                        }
+                    }
+                }
                 recv(_content_receiver) -> msg => {
-                    self.app.send(msg.unwrap().map_err(|e| FileWatcherError::File(e))).unwrap();
+                    self.app.send(msg.unwrap()).unwrap();
                 }
             }
         }
@@ -122,18 +143,18 @@ impl FileWatcher {
 
 impl FileReader {
     fn new(
-        content_sender: Sender<io::Result<String>>,
+        content_sender: Sender<JobOutput>,
         receiver: Receiver<()>,
-        file_path: PathBuf,
+        index: LogIndex,
         interval: Duration,
     ) -> Self {
         FileReader {
-            content_sender: content_sender,
-            receiver: receiver,
-            file_path: file_path,
-            interval: interval,
-            content: "".to_string(),
-            pos: 0,
+            content_sender,
+            receiver,
+            interval,
+            path: index.path().to_path_buf(),
+            index: Arc::new(Mutex::new(index)),
+            sent: false,
         }
     }
 
@@ -150,35 +171,63 @@ impl FileReader {
         }
     }
 
-    fn update(&mut self) -> Result<(), SendError<io::Result<String>>> {
-        let s = File::open(&self.file_path).and_then(|mut f| {
-            // avoid reading the whole file every time
-            self.pos = f.seek(io::SeekFrom::Start(self.pos))?;
-            self.pos += f.read_to_string(&mut self.content)? as u64;
-            Ok(self.content.clone())
-        });
-        // let s = fs::read_to_string(&self.file_path); // alternative: always read the whole file
-        self.content_sender.send(s)
+    fn update(&mut self) -> Result<(), SendError<JobOutput>> {
+        // Only the output appended since the last update is read, without
+        // holding the lock, and indexed a chunk at a time so the log view
+        // never waits for more than one chunk
+        loop {
+            let Ok(position) = self.index.lock().map(|index| index.position()) else {
+                return self.send_error(io::Error::other("log index lock poisoned"));
+            };
+            match position.read_next(&self.path) {
+                Ok(Some(output)) => {
+                    let Ok(mut index) = self.index.lock() else {
+                        return self.send_error(io::Error::other("log index lock poisoned"));
+                    };
+                    index.append(output);
+                    drop(index);
+
+                    // Let the app show each chunk as it is indexed
+                    self.sent = true;
+                    self.content_sender.send(Ok(self.index.clone()))?;
+                }
+                Ok(None) => break,
+                Err(e) => return self.send_error(e),
+            }
+        }
+
+        // The app is told about an empty file too
+        if !self.sent {
+            self.sent = true;
+            self.content_sender.send(Ok(self.index.clone()))?;
+        }
+        Ok(())
+    }
+
+    fn send_error(&self, e: io::Error) -> Result<(), SendError<JobOutput>> {
+        self.content_sender
+            .send(Err(FileWatcherError::File(self.path.clone(), e)))
     }
 }
 
 impl FileWatcherHandle {
-    pub fn new(app: Sender<JobOutput>, interval: Duration) -> Self {
+    pub fn new(app: Sender<JobOutput>, interval: Duration, max_retained: usize) -> Self {
         let (sender, receiver) = unbounded();
-        let mut actor = FileWatcher::new(app, receiver, interval);
+        let mut actor = FileWatcher::new(app, receiver, interval, max_retained);
         thread::spawn(move || actor.run());
 
         Self {
             sender,
-            file_path: None,
+            file_paths: Vec::new(),
         }
     }
 
-    pub fn set_file_path(&mut self, file_path: Option<PathBuf>) {
-        if self.file_path != file_path {
-            self.file_path = file_path.clone();
+    /// Watch the given files instead of the current ones
+    pub fn set_file_paths(&mut self, file_paths: Vec<PathBuf>) {
+        if self.file_paths != file_paths {
+            self.file_paths = file_paths.clone();
             self.sender
-                .send(FileWatcherMessage::FilePath(file_path))
+                .send(FileWatcherMessage::FilePaths(file_paths))
                 .unwrap();
         }
     }
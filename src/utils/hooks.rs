@@ -0,0 +1,505 @@
+use chrono::Local;
+use color_eyre::{eyre::WrapErr, Result};
+use crossbeam::channel::{bounded, Sender, TrySendError};
+use regex::Regex;
+use std::{
+    collections::{HashMap, HashSet},
+    fs::{self, OpenOptions},
+    io::{Read, Write},
+    path::PathBuf,
+    process::{Child, Command, ExitStatus, Stdio},
+    sync::{
+        mpsc::{self, Receiver},
+        OnceLock,
+    },
+    thread,
+    time::{Duration, Instant},
+};
+
+use crate::config::{HookConfig, HookEvent};
+use crate::slurm::{
+    columns::JobColumn, hostlist::expand_hostlist, transitions::JobTransition, Job, JobState,
+};
+
+/// How often a running hook is checked for exit
+const POLL_INTERVAL: Duration = Duration::from_millis(100);
+/// Commands run at the same time, the others wait in the queue
+const WORKERS: usize = 4;
+/// Commands waiting to run, beyond which new ones are dropped and logged
+const QUEUE_SIZE: usize = 1024;
+
+/// A hook with its name filter compiled
+struct Hook {
+    config: HookConfig,
+    name_filter: Option<Regex>,
+}
+
+/// Runs the configured hooks on the job events of each refresh
+pub struct HookRunner {
+    hooks: Vec<Hook>,
+    /// Events already handled, by hook index, job ID and node, so that
+    /// lasting conditions trigger once
+    fired: HashSet<(usize, String, String)>,
+    /// When pending jobs without a submit time were first seen
+    pending_since: HashMap<String, Instant>,
+    /// Down nodes, polled when a hook watches for them
+    down_nodes: Option<DownNodes>,
+}
+
+/// Nodes that are down or failed, polled with `sinfo` on a thread of its own
+/// so that a slow controller doesn't hold up refreshes
+struct DownNodes {
+    receiver: Receiver<HashSet<String>>,
+    /// Result of the last poll taken
+    nodes: HashSet<String>,
+}
+
+impl DownNodes {
+    fn spawn() -> Self {
+        // Room for a single result, so the next poll waits until it is taken
+        // and sinfo runs about once per refresh. The thread stops once the
+        // receiver is dropped.
+        let (sender, receiver) = mpsc::sync_channel(1);
+        thread::spawn(move || while sender.send(down_nodes()).is_ok() {});
+        Self {
+            receiver,
+            nodes: HashSet::new(),
+        }
+    }
+
+    /// Down nodes from the latest finished poll, none until the first one
+    fn latest(&mut self) -> &HashSet<String> {
+        if let Ok(nodes) = self.receiver.try_recv() {
+            self.nodes = nodes;
+        }
+        &self.nodes
+    }
+}
+
+impl HookRunner {
+    pub fn new(hooks: Vec<HookConfig>) -> Result<Self> {
+        let hooks = hooks
+            .into_iter()
+            .map(|config| {
+                let name_filter = config
+                    .name_filter
+                    .as_deref()
+                    .map(Regex::new)
+                    .transpose()
+                    .wrap_err_with(|| {
+                        format!("Invalid name_filter of hook {:?}", config.command)
+                    })?;
+                Ok(Hook {
+                    config,
+                    name_filter,
+                })
+            })
+            .collect::<Result<Vec<_>>>()?;
+
+        let down_nodes = hooks
+            .iter()
+            .any(|hook| hook.config.event == HookEvent::NodeDown)
+            .then(DownNodes::spawn);
+        Ok(Self {
+            hooks,
+            fired: HashSet::new(),
+            pending_since: HashMap::new(),
+            down_nodes,
+        })
+    }
+
+    /// Columns the hooks read, to query even when they aren't displayed
+    pub fn columns(&self) -> Vec<JobColumn> {
+        if self.hooks.is_empty() {
+            return Vec::new();
+        }
+        // Every hook gets these in its environment
+        let mut columns = vec![
+            JobColumn::User,
+            JobColumn::Partition,
+            JobColumn::Node,
+            JobColumn::WorkDir,
+        ];
+        if self
+            .hooks
+            .iter()
+            .any(|hook| hook.config.event == HookEvent::PendingTooLong)
+        {
+            columns.push(JobColumn::SubmitTime);
+        }
+        columns
+    }
+
+    /// Start the hooks triggered by a refresh, without waiting for them
+    pub fn on_refresh(&mut self, jobs: &[Job], transitions: &[JobTransition]) {
+        if self.hooks.is_empty() {
+            return;
+        }
+
+        for job in jobs.iter().filter(|job| job.state == JobState::Pending) {
+            self.pending_since
+                .entry(job.id.clone())
+                .or_insert_with(Instant::now);
+        }
+        self.pending_since.retain(|id, _| {
+            jobs.iter()
+                .any(|job| job.id == *id && job.state == JobState::Pending)
+        });
+
+        // Jobs that left the queue won't trigger again
+        let queued: HashSet<&str> = jobs.iter().map(|job| job.id.as_str()).collect();
+        self.fired.retain(|(_, id, _)| queued.contains(id.as_str()));
+
+        let down_nodes = self
+            .down_nodes
+            .as_mut()
+            .map(|down_nodes| down_nodes.latest().clone())
+            .unwrap_or_default();
+
+        for (index, hook) in self.hooks.iter().enumerate() {
+            let matches_name =
+                |name: &str| hook.name_filter.as_ref().is_none_or(|re| re.is_match(name));
+
+            match hook.config.event {
+                HookEvent::Started | HookEvent::Finished => {
+                    for transition in transitions.iter().filter(|t| matches_name(&t.name)) {
+                        let triggered = if hook.config.event == HookEvent::Started {
+                            transition.to == Some(JobState::Running)
+                        } else {
+                            is_finished(transition, jobs)
+                                && (hook.config.states.is_empty()
+                                    || hook.config.states.iter().any(|state| {
+                                        state.eq_ignore_ascii_case(&transition.state_name)
+                                    }))
+                        };
+                        if triggered {
+                            let job = jobs.iter().find(|job| job.id == transition.job_id);
+                            let mut env = job_env(job, &transition.job_id, &transition.name);
+                            env.push(("SLURMER_STATE".into(), transition.state_name.clone()));
+                            env.push((
+                                "SLURMER_PREVIOUS_STATE".into(),
+                                transition.from.to_string(),
+                            ));
+                            spawn_hook(&hook.config, env);
+                        }
+                    }
+                }
+                HookEvent::PendingTooLong => {
+                    for job in jobs
+                        .iter()
+                        .filter(|job| job.state == JobState::Pending && matches_name(&job.name))
+                    {
+                        let pending_secs = job
+                            .submit_time
+                            .as_ref()
+                            .and_then(|time| time.seconds_ago())
+                            .map(|secs| secs.max(0) as u64)
+                            .or_else(|| {
+                                self.pending_since
+                                    .get(&job.id)
+                                    .map(|since| since.elapsed().as_secs())
+                            })
+                            .unwrap_or(0);
+                        if pending_secs < hook.config.pending_minutes * 60
+                            || !self.fired.insert((index, job.id.clone(), String::new()))
+                        {
+                            continue;
+                        }
+                        let mut env = job_env(Some(job), &job.id, &job.name);
+                        env.push(("SLURMER_STATE".into(), job.state.to_string()));
+                        env.push((
+                            "SLURMER_PENDING_MINUTES".into(),
+                            (pending_secs / 60).to_string(),
+                        ));
+                        spawn_hook(&hook.config, env);
+                    }
+                }
+                HookEvent::NodeDown => {
+                    for job in jobs
+                        .iter()
+                        .filter(|job| job.state == JobState::Running && matches_name(&job.name))
+                    {
+                        let Some(nodes) = &job.node else {
+                            continue;
+                        };
+                        for node in expand_hostlist(nodes) {
+                            if !down_nodes.contains(&node)
+                                || !self.fired.insert((index, job.id.clone(), node.clone()))
+                            {
+                                continue;
+                            }
+                            let mut env = job_env(Some(job), &job.id, &job.name);
+                            env.push(("SLURMER_STATE".into(), job.state.to_string()));
+                            env.push(("SLURMER_DOWN_NODE".into(), node));
+                            spawn_hook(&hook.config, env);
+                        }
+                    }
+                }
+            }
+        }
+    }
+}
+
+/// Whether a transition ends a job: a final state, or leaving the queue.
+/// States without a `JobState` of their own, such as `SUSPENDED`, only count
+/// once the job has left the queue.
+fn is_finished(transition: &JobTransition, jobs: &[Job]) -> bool {
+    match transition.to {
+        Some(JobState::Pending) | Some(JobState::Running) | Some(JobState::Completing) => false,
+        Some(JobState::Other) => !jobs.iter().any(|job| job.id == transition.job_id),
+        _ => true,
+    }
+}
+
+/// Environment variables describing a job, with what is known of it
+fn job_env(job: Option<&Job>, job_id: &str, name: &str) -> Vec<(String, String)> {
+    let mut env = vec![
+        ("SLURMER_JOB_ID".to_string(), job_id.to_string()),
+        ("SLURMER_JOB_NAME".to_string(), name.to_string()),
+    ];
+    if let Some(job) = job {
+        env.push(("SLURMER_USER".to_string(), job.user.clone()));
+        env.push(("SLURMER_PARTITION".to_string(), job.partition.clone()));
+        if let Some(nodes) = &job.node {
+            env.push(("SLURMER_NODES".to_string(), nodes.clone()));
+        }
+        if let Some(work_dir) = &job.work_dir {
+            env.push(("SLURMER_WORK_DIR".to_string(), work_dir.clone()));
+        }
+    }
+    env
+}
+
+fn spawn_hook(hook: &HookConfig, mut env: Vec<(String, String)>) {
+    env.push(("SLURMER_EVENT".to_string(), hook.event.as_str().to_string()));
+    spawn_command(
+        hook.event.as_str(),
+        &hook.command,
+        env,
+        Duration::from_secs(hook.timeout_secs),
+    );
+}
+
+/// How a command ended
+enum Outcome {
+    Exited(ExitStatus),
+    TimedOut,
+    Failed(std::io::Error),
+    /// Not run, as too many commands were waiting
+    Dropped,
+}
+
+/// A command waiting for a worker
+struct Task {
+    label: String,
+    command: String,
+    env: Vec<(String, String)>,
+    timeout: Duration,
+}
+
+/// Queue of the commands to run, served by `WORKERS` threads started on first use
+fn task_queue() -> &'static Sender<Task> {
+    static QUEUE: OnceLock<Sender<Task>> = OnceLock::new();
+    QUEUE.get_or_init(|| {
+        let (sender, receiver) = bounded::<Task>(QUEUE_SIZE);
+        for _ in 0..WORKERS {
+            let receiver = receiver.clone();
+            thread::spawn(move || {
+                for task in receiver {
+                    run_task(task);
+                }
+            });
+        }
+        sender
+    })
+}
+
+/// Run a shell command in the background, killing it after `timeout`, and
+/// append the outcome to the hooks log. At most `WORKERS` commands run at
+/// once, so that many jobs ending together don't start a shell each.
+pub fn spawn_command(label: &str, command: &str, env: Vec<(String, String)>, timeout: Duration) {
+    let task = Task {
+        label: label.to_string(),
+        command: command.to_string(),
+        env,
+        timeout,
+    };
+    if let Err(TrySendError::Full(task) | TrySendError::Disconnected(task)) =
+        task_queue().try_send(task)
+    {
+        log_outcome(
+            &task.label,
+            &job_id(&task.env),
+            &task.command,
+            &Outcome::Dropped,
+            "",
+            Duration::ZERO,
+        );
+    }
+}
+
+fn run_task(task: Task) {
+    let started = Instant::now();
+    let (outcome, stderr) = run_with_timeout(&task.command, &task.env, task.timeout);
+    log_outcome(
+        &task.label,
+        &job_id(&task.env),
+        &task.command,
+        &outcome,
+        &stderr,
+        started.elapsed(),
+    );
+}
+
+/// Job ID a command runs for, from its environment
+fn job_id(env: &[(String, String)]) -> String {
+    env.iter()
+        .find(|(key, _)| key == "SLURMER_JOB_ID")
+        .map(|(_, id)| id.clone())
+        .unwrap_or_default()
+}
+
+fn run_with_timeout(
+    command: &str,
+    env: &[(String, String)],
+    timeout: Duration,
+) -> (Outcome, String) {
+    let child = shell_command(command)
+        .envs(env.iter().map(|(key, value)| (key, value)))
+        .stdin(Stdio::null())
+        .stdout(Stdio::null())
+        .stderr(Stdio::piped())
+        .spawn();
+    let mut child = match child {
+        Ok(child) => child,
+        Err(e) => return (Outcome::Failed(e), String::new()),
+    };
+
+    // Drain stderr while waiting, so a chatty command doesn't block on a full pipe
+    let stderr = child.stderr.take().map(|mut stderr| {
+        thread::spawn(move || {
+            let mut output = String::new();
+            let _ = stderr.read_to_string(&mut output);
+            output
+        })
+    });
+
+    let deadline = Instant::now() + timeout;
+    let outcome = loop {
+        match child.try_wait() {
+            Ok(Some(status)) => break Outcome::Exited(status),
+            Ok(None) if Instant::now() >= deadline => {
+                kill_process_group(&child);
+                let _ = child.kill();
+                let _ = child.wait();
+                break Outcome::TimedOut;
+            }
+            Ok(None) => thread::sleep(POLL_INTERVAL),
+            Err(e) => break Outcome::Failed(e),
+        }
+    };
+
+    let stderr = stderr
+        .and_then(|reader| reader.join().ok())
+        .unwrap_or_default();
+    (outcome, stderr)
+}
+
+/// A shell running `command`, in a process group of its own so that a
+/// timeout kills what it started too
+#[cfg(unix)]
+fn shell_command(command: &str) -> Command {
+    use std::os::unix::process::CommandExt;
+    let mut shell = Command::new("sh");
+    shell.args(["-c", command]).process_group(0);
+    shell
+}
+
+#[cfg(not(unix))]
+fn shell_command(command: &str) -> Command {
+    let mut shell = Command::new("cmd");
+    shell.args(["/C", command]);
+    shell
+}
+
+#[cfg(unix)]
+fn kill_process_group(child: &Child) {
+    let _ = Command::new("kill")
+        .args(["-KILL", "--", &format!("-{}", child.id())])
+        .status();
+}
+
+/// Only the shell itself is killed
+#[cfg(not(unix))]
+fn kill_process_group(_child: &Child) {}
+
+/// Path of the hooks log, `$XDG_STATE_HOME/slurmer/hooks.log` (or
+/// `~/.local/state/slurmer/hooks.log`)
+pub fn log_path() -> Option<PathBuf> {
+    let state_dir = std::env::var_os("XDG_STATE_HOME")
+        .filter(|dir| !dir.is_empty())
+        .map(PathBuf::from)
+        .or_else(|| {
+            std::env::var_os("HOME").map(|home| PathBuf::from(home).join(".local").join("state"))
+        })?;
+    Some(state_dir.join("slurmer").join("hooks.log"))
+}
+
+fn log_outcome(
+    label: &str,
+    job_id: &str,
+    command: &str,
+    outcome: &Outcome,
+    stderr: &str,
+    elapsed: Duration,
+) {
+    let Some(path) = log_path() else {
+        return;
+    };
+    let result = match outcome {
+        Outcome::Exited(status) => status.to_string(),
+        Outcome::TimedOut => "killed after timeout".to_string(),
+        Outcome::Failed(e) => format!("failed to run: {}", e),
+        Outcome::Dropped => "dropped, too many commands queued".to_string(),
+    };
+
+    let mut entry = format!(
+        "{} {} job={} ({:.1}s) {}: {}\n",
+        Local::now().format("%Y-%m-%dT%H:%M:%S"),
+        label,
+        job_id,
+        elapsed.as_secs_f64(),
+        result,
+        command
+    );
+    for line in stderr.lines() {
+        entry.push_str(&format!("    {}\n", line));
+    }
+
+    if let Some(dir) = path.parent() {
+        let _ = fs::create_dir_all(dir);
+    }
+    // A single write per entry keeps concurrent hooks from interleaving
+    if let Ok(mut file) = OpenOptions::new().create(true).append(true).open(&path) {
+        let _ = file.write_all(entry.as_bytes());
+    }
+}
+
+/// Nodes that are down or failed, from `sinfo`
+fn down_nodes() -> HashSet<String> {
+    let Ok(output) = Command::new("sinfo")
+        .args(["-h", "-N", "-t", "down,fail", "-o", "%N"])
+        .output()
+    else {
+        return HashSet::new();
+    };
+    if !output.status.success() {
+        return HashSet::new();
+    }
+
+    String::from_utf8_lossy(&output.stdout)
+        .lines()
+        .map(|line| line.trim().to_string())
+        .filter(|node| !node.is_empty())
+        .collect()
+}
@@ -0,0 +1,413 @@
+use std::{
+    collections::VecDeque,
+    fs::{File, Metadata},
+    io::{self, BufRead, BufReader, Read, Seek, SeekFrom},
+    ops::Range,
+    path::{Path, PathBuf},
+    sync::{Arc, Mutex},
+};
+
+/// A byte offset is kept for every this many lines
+const CHECKPOINT_INTERVAL: usize = 256;
+/// Longest line kept for display; the rest of a longer line is dropped
+pub const MAX_LINE_BYTES: usize = 64 * 1024;
+/// Size of the reads when indexing new output, and of the output indexed at
+/// once while holding the lock
+const READ_CHUNK: usize = 1024 * 1024;
+
+/// A log index shared between the file reader thread and the log view
+pub type SharedLogIndex = Arc<Mutex<LogIndex>>;
+
+/// Line index of a log file that is read incrementally.
+///
+/// Only a byte offset every `CHECKPOINT_INTERVAL` lines and the last lines of
+/// the file, up to `max_retained` bytes, are kept in memory. Other lines are
+/// read from the file on demand by seeking to the nearest checkpoint.
+#[derive(Debug)]
+pub struct LogIndex {
+    path: PathBuf,
+    /// Budget in bytes for the in-memory tail
+    max_retained: usize,
+    /// Identity of the indexed file, to detect it being replaced
+    file_id: Option<u64>,
+    /// Number of bytes indexed so far
+    len: u64,
+    /// Number of newline-terminated lines
+    complete_lines: usize,
+    /// Byte offset of line `i * CHECKPOINT_INTERVAL` at index `i`
+    checkpoints: Vec<u64>,
+    /// Offset of the line being indexed
+    line_start: u64,
+    /// Display text of the last complete lines
+    tail: VecDeque<String>,
+    tail_bytes: usize,
+    /// Bytes of the unterminated last line, without text hidden by a carriage return
+    pending: Vec<u8>,
+    /// Whether there is an unterminated last line
+    has_pending: bool,
+}
+
+/// Indexed size and identity of a log file, to read what was appended to it
+#[derive(Debug, Clone, Copy)]
+pub struct ReadPosition {
+    len: u64,
+    file_id: Option<u64>,
+}
+
+/// Output of a log file not indexed yet
+#[derive(Debug)]
+pub struct NewOutput {
+    /// Whether the file was truncated or replaced, and `bytes` start it again
+    reset: bool,
+    file_id: Option<u64>,
+    bytes: Vec<u8>,
+}
+
+impl ReadPosition {
+    /// Read the next chunk of output appended to the file at `path`, or None
+    /// if there is nothing new
+    pub fn read_next(&self, path: &Path) -> io::Result<Option<NewOutput>> {
+        let file = File::open(path)?;
+        let metadata = file.metadata()?;
+        let file_id = file_id(&metadata);
+
+        let reset =
+            metadata.len() < self.len || (self.file_id.is_some() && file_id != self.file_id);
+        let offset = if reset { 0 } else { self.len };
+        if !reset && metadata.len() == offset {
+            return Ok(None);
+        }
+
+        let mut file = file;
+        file.seek(SeekFrom::Start(offset))?;
+        let available = (metadata.len() - offset).min(READ_CHUNK as u64);
+        let mut bytes = Vec::with_capacity(available as usize);
+        file.take(available).read_to_end(&mut bytes)?;
+        Ok(Some(NewOutput {
+            reset,
+            file_id,
+            bytes,
+        }))
+    }
+}
+
+impl LogIndex {
+    pub fn new(path: PathBuf, max_retained: usize) -> Self {
+        Self {
+            path,
+            max_retained,
+            file_id: None,
+            len: 0,
+            complete_lines: 0,
+            checkpoints: Vec::new(),
+            line_start: 0,
+            tail: VecDeque::new(),
+            tail_bytes: 0,
+            pending: Vec::new(),
+            has_pending: false,
+        }
+    }
+
+    /// Path of the indexed file
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    /// Number of bytes indexed
+    pub fn len(&self) -> u64 {
+        self.len
+    }
+
+    /// Number of lines, counted like `str::lines`
+    pub fn line_count(&self) -> usize {
+        self.complete_lines + usize::from(self.has_pending)
+    }
+
+    /// Forget everything indexed, e.g. after the file was truncated
+    fn reset(&mut self) {
+        *self = Self::new(std::mem::take(&mut self.path), self.max_retained);
+    }
+
+    /// Where output not indexed yet starts. Reading it doesn't need the
+    /// index, so the lock is only taken to append what was read.
+    pub fn position(&self) -> ReadPosition {
+        ReadPosition {
+            len: self.len,
+            file_id: self.file_id,
+        }
+    }
+
+    /// Index output read from `position()`. If the file shrank below the
+    /// indexed size or was replaced, it is indexed again from the start.
+    pub fn append(&mut self, output: NewOutput) {
+        if output.reset {
+            self.reset();
+        }
+        self.file_id = output.file_id;
+        for segment in output.bytes.split_inclusive(|b| *b == b'\n') {
+            self.index_segment(segment);
+        }
+    }
+
+    /// Index a piece of output that is either a whole line or the end of one,
+    /// terminated by a newline, or the start of an unterminated line
+    fn index_segment(&mut self, segment: &[u8]) {
+        self.len += segment.len() as u64;
+        match segment.strip_suffix(b"\n") {
+            Some(rest) => {
+                if self.complete_lines.is_multiple_of(CHECKPOINT_INTERVAL) {
+                    self.checkpoints.push(self.line_start);
+                }
+                self.pending.extend_from_slice(rest);
+                let line = display_line(&self.pending);
+                self.pending.clear();
+                self.has_pending = false;
+                self.complete_lines += 1;
+                self.line_start = self.len;
+                self.push_tail(line);
+            }
+            None => {
+                self.pending.extend_from_slice(segment);
+                self.has_pending = true;
+
+                // Progress bars rewrite a line with carriage returns without
+                // ending it; only the text after the last one is displayed
+                let body = &self.pending[..self.pending.len() - 1];
+                if let Some(pos) = body.iter().rposition(|b| *b == b'\r') {
+                    self.pending.drain(..=pos);
+                }
+                if self.pending.len() > MAX_LINE_BYTES * 2 {
+                    self.pending.truncate(MAX_LINE_BYTES);
+                }
+            }
+        }
+    }
+
+    /// Keep a line in the tail, dropping the oldest lines over the budget
+    fn push_tail(&mut self, line: String) {
+        self.tail_bytes += line.len();
+        self.tail.push_back(line);
+        while self.tail_bytes > self.max_retained && self.tail.len() > 1 {
+            if let Some(dropped) = self.tail.pop_front() {
+                self.tail_bytes -= dropped.len();
+            }
+        }
+    }
+
+    /// Get the display text of the lines in `range`, reading lines that are
+    /// not in the tail from the file
+    pub fn lines(&self, range: Range<usize>) -> io::Result<Vec<String>> {
+        let end = range.end.min(self.line_count());
+        let start = range.start.min(end);
+        let tail_start = self.complete_lines - self.tail.len();
+
+        let mut lines = Vec::with_capacity(end - start);
+        if start < tail_start {
+            lines.extend(self.read_lines(start..end.min(tail_start))?);
+        }
+        for index in start.max(tail_start)..end {
+            match self.tail.get(index - tail_start) {
+                Some(line) => lines.push(line.clone()),
+                None => lines.push(display_line(&self.pending)),
+            }
+        }
+        Ok(lines)
+    }
+
+    /// Read complete lines from the file, starting at the nearest checkpoint
+    fn read_lines(&self, range: Range<usize>) -> io::Result<Vec<String>> {
+        let checkpoint = range.start / CHECKPOINT_INTERVAL;
+        let offset = self.checkpoints.get(checkpoint).copied().unwrap_or(0);
+
+        let mut file = File::open(&self.path)?;
+        file.seek(SeekFrom::Start(offset))?;
+        let mut reader = BufReader::new(file);
+
+        let mut lines = Vec::with_capacity(range.len());
+        let mut buf = Vec::new();
+        for index in checkpoint * CHECKPOINT_INTERVAL..range.end {
+            buf.clear();
+            if !read_line_capped(&mut reader, &mut buf)? {
+                break;
+            }
+            if index >= range.start {
+                lines.push(display_line(&buf));
+            }
+        }
+        Ok(lines)
+    }
+}
+
+/// Read up to `max` raw bytes of the file at `path` from `offset`
+pub fn read_bytes(path: &Path, offset: u64, max: usize) -> io::Result<Vec<u8>> {
+    let mut file = File::open(path)?;
+    file.seek(SeekFrom::Start(offset))?;
+    let mut buf = Vec::with_capacity(max);
+    file.take(max as u64).read_to_end(&mut buf)?;
+    Ok(buf)
+}
+
+/// Read a line without its newline into `buf`, keeping at most
+/// `MAX_LINE_BYTES` of it. Returns false at the end of the file.
+fn read_line_capped(reader: &mut impl BufRead, buf: &mut Vec<u8>) -> io::Result<bool> {
+    let mut read_any = false;
+    loop {
+        let available = reader.fill_buf()?;
+        if available.is_empty() {
+            return Ok(read_any);
+        }
+        read_any = true;
+
+        let (used, done) = match available.iter().position(|b| *b == b'\n') {
+            Some(pos) => (pos + 1, Some(pos)),
+            None => (available.len(), None),
+        };
+        let content = &available[..done.unwrap_or(used)];
+        let room = MAX_LINE_BYTES.saturating_sub(buf.len());
+        buf.extend_from_slice(&content[..content.len().min(room)]);
+        reader.consume(used);
+
+        if done.is_some() {
+            return Ok(true);
+        }
+    }
+}
+
+/// Text of a line as displayed: only what follows the last carriage return,
+/// as a terminal would show it, capped to `MAX_LINE_BYTES`
+pub fn display_line(bytes: &[u8]) -> String {
+    let bytes = bytes.strip_suffix(b"\r").unwrap_or(bytes);
+    let visible = match bytes.iter().rposition(|b| *b == b'\r') {
+        Some(pos) => &bytes[pos + 1..],
+        None => bytes,
+    };
+    let mut line =
+        String::from_utf8_lossy(&visible[..visible.len().min(MAX_LINE_BYTES)]).into_owned();
+    if visible.len() > MAX_LINE_BYTES {
+        line.push('…');
+    }
+    line
+}
+
+#[cfg(unix)]
+fn file_id(metadata: &Metadata) -> Option<u64> {
+    use std::os::unix::fs::MetadataExt;
+    Some(metadata.ino())
+}
+
+#[cfg(not(unix))]
+fn file_id(_metadata: &Metadata) -> Option<u64> {
+    None
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::utils::tempfile::TempFile;
+    use std::{fs, io::Write};
+
+    /// Index all output not indexed yet
+    fn update(index: &mut LogIndex) {
+        while let Some(output) = index.position().read_next(index.path()).unwrap() {
+            index.append(output);
+        }
+    }
+
+    fn append(path: &Path, text: &str) {
+        let mut file = fs::OpenOptions::new().append(true).open(path).unwrap();
+        file.write_all(text.as_bytes()).unwrap();
+    }
+
+    fn numbered(lines: Range<usize>) -> Vec<String> {
+        lines.map(|i| format!("line {}", i)).collect()
+    }
+
+    #[test]
+    fn reads_lines_across_checkpoints() {
+        let file = TempFile::create("slurmer-test", "log", b"").unwrap();
+        let mut index = LogIndex::new(file.path().to_path_buf(), 0);
+
+        // Output ends in the middle of the line at the checkpoint, then goes on
+        let text: String = numbered(0..600)
+            .iter()
+            .map(|l| format!("{}\n", l))
+            .collect();
+        let split = text.find("line 256").unwrap() + 3;
+        append(file.path(), &text[..split]);
+        update(&mut index);
+        assert_eq!(index.line_count(), 257);
+        assert_eq!(index.lines(255..257).unwrap(), vec!["line 255", "lin"]);
+
+        append(file.path(), &text[split..]);
+        update(&mut index);
+        assert_eq!(index.line_count(), 600);
+        assert_eq!(index.len(), text.len() as u64);
+        assert_eq!(index.checkpoints.len(), 3);
+        assert_eq!(index.lines(250..260).unwrap(), numbered(250..260));
+        assert_eq!(index.lines(510..514).unwrap(), numbered(510..514));
+        assert_eq!(index.lines(598..700).unwrap(), numbered(598..600));
+    }
+
+    #[test]
+    fn trims_the_tail_to_the_budget() {
+        let file = TempFile::create("slurmer-test", "log", b"aaaa\nbbbb\ncccc\ndddd\n").unwrap();
+        let mut index = LogIndex::new(file.path().to_path_buf(), 10);
+        update(&mut index);
+        assert_eq!(index.tail, ["cccc", "dddd"]);
+        assert_eq!(index.tail_bytes, 8);
+        assert_eq!(
+            index.lines(0..4).unwrap(),
+            vec!["aaaa", "bbbb", "cccc", "dddd"]
+        );
+
+        // A single line over the budget is still kept
+        append(file.path(), &"e".repeat(20));
+        append(file.path(), "\n");
+        update(&mut index);
+        assert_eq!(index.tail.len(), 1);
+        assert_eq!(
+            index.lines(3..5).unwrap(),
+            vec!["dddd".to_string(), "e".repeat(20)]
+        );
+    }
+
+    #[test]
+    fn starts_again_when_the_file_shrinks() {
+        let file = TempFile::create("slurmer-test", "log", b"a\nb\nc\n").unwrap();
+        let mut index = LogIndex::new(file.path().to_path_buf(), 1024);
+        update(&mut index);
+        assert_eq!(index.line_count(), 3);
+
+        fs::write(file.path(), "x\ny").unwrap();
+        update(&mut index);
+        assert_eq!(index.line_count(), 2);
+        assert_eq!(index.len(), 3);
+        assert_eq!(index.lines(0..3).unwrap(), vec!["x", "y"]);
+    }
+
+    /// Files are told apart by their inode
+    #[cfg(unix)]
+    #[test]
+    fn starts_again_when_the_file_is_replaced() {
+        let file = TempFile::create("slurmer-test", "log", b"a\n").unwrap();
+        let mut index = LogIndex::new(file.path().to_path_buf(), 1024);
+        update(&mut index);
+
+        let replacement = TempFile::create("slurmer-test", "log", b"one\ntwo\n").unwrap();
+        fs::rename(replacement.path(), file.path()).unwrap();
+        update(&mut index);
+        assert_eq!(index.lines(0..3).unwrap(), vec!["one", "two"]);
+    }
+
+    #[test]
+    fn shows_only_the_text_after_a_carriage_return() {
+        let file = TempFile::create("slurmer-test", "log", b"10%\r20%\r").unwrap();
+        let mut index = LogIndex::new(file.path().to_path_buf(), 1024);
+        update(&mut index);
+        assert_eq!(index.lines(0..1).unwrap(), vec!["20%"]);
+
+        append(file.path(), "\n");
+        update(&mut index);
+        assert_eq!(index.lines(0..2).unwrap(), vec!["20%"]);
+    }
+}
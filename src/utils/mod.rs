@@ -1,5 +1,14 @@
+pub mod ansi;
+pub mod clipboard;
+pub mod diff;
 pub mod event;
+pub mod export;
+pub mod external;
 pub mod file_watcher;
+pub mod hooks;
+pub mod log_index;
+pub mod notification;
+pub mod tempfile;
 
 /// Returns the current username from the environment
 pub fn get_username() -> String {
@@ -16,7 +25,7 @@ pub fn _truncate_string(s: &str, max_len: usize) -> String {
 }
 
 /// Format memory size to a human-readable string
-pub fn _format_memory(memory_mb: u64) -> String {
+pub fn format_memory(memory_mb: u64) -> String {
     if memory_mb < 1024 {
         format!("{}M", memory_mb)
     } else {
@@ -25,7 +34,7 @@ pub fn _format_memory(memory_mb: u64) -> String {
 }
 
 /// Format time duration in a human-readable format
-pub fn _format_duration(seconds: u64) -> String {
+pub fn format_duration(seconds: u64) -> String {
     let days = seconds / (24 * 3600);
     let hours = (seconds % (24 * 3600)) / 3600;
     let minutes = (seconds % 3600) / 60;
@@ -37,3 +46,42 @@ pub fn _format_duration(seconds: u64) -> String {
         format!("{:02}:{:02}:{:02}", hours, minutes, secs)
     }
 }
+
+/// Format a time difference relative to now, e.g. `3h ago` or `in 20m`
+pub fn format_relative_time(seconds_ago: i64) -> String {
+    let secs = seconds_ago.unsigned_abs();
+    let amount = if secs < 60 {
+        return "just now".to_string();
+    } else if secs < 3600 {
+        format!("{}m", secs / 60)
+    } else if secs < 24 * 3600 {
+        format!("{}h", secs / 3600)
+    } else {
+        format!("{}d", secs / (24 * 3600))
+    };
+
+    if seconds_ago >= 0 {
+        format!("{} ago", amount)
+    } else {
+        format!("in {}", amount)
+    }
+}
+
+/// Split a job ID into a numeric sort key.
+/// Array tasks (`1234_5`) sort by task after their parent, and pending
+/// array ranges (`1234_[6-10]`) sort after all of the parent's started tasks.
+pub fn job_id_sort_key(id: &str) -> (u64, u64, String) {
+    let (base, task) = match id.split_once('_') {
+        Some((base, task)) => (base, Some(task)),
+        None => (id, None),
+    };
+    let base = base.parse::<u64>().unwrap_or(u64::MAX);
+
+    match task {
+        None => (base, 0, String::new()),
+        Some(task) => match task.parse::<u64>() {
+            Ok(n) => (base, n + 1, String::new()),
+            Err(_) => (base, u64::MAX, task.to_string()),
+        },
+    }
+}
@@ -0,0 +1,118 @@
+use color_eyre::{eyre::WrapErr, Result};
+use regex::Regex;
+use std::{
+    io::{self, Write},
+    time::Duration,
+};
+
+use crate::config::{DesktopNotification, NotificationConfig};
+use crate::slurm::transitions::JobTransition;
+use crate::utils::hooks::spawn_command;
+
+/// Seconds after which the notification command is killed
+const COMMAND_TIMEOUT: Duration = Duration::from_secs(60);
+
+/// Sends notifications about job state changes, as configured
+pub struct Notifier {
+    config: NotificationConfig,
+    name_filter: Option<Regex>,
+}
+
+impl Notifier {
+    pub fn new(config: NotificationConfig) -> Result<Self> {
+        let name_filter = config
+            .name_filter
+            .as_deref()
+            .map(Regex::new)
+            .transpose()
+            .wrap_err("Invalid notifications.name_filter")?;
+        Ok(Self {
+            config,
+            name_filter,
+        })
+    }
+
+    /// Whether a transition matches the configured states and name filter
+    fn wants(&self, transition: &JobTransition) -> bool {
+        (self.config.states.is_empty()
+            || self
+                .config
+                .states
+                .iter()
+                .any(|state| state.eq_ignore_ascii_case(&transition.state_name)))
+            && self
+                .name_filter
+                .as_ref()
+                .is_none_or(|re| re.is_match(&transition.name))
+    }
+
+    /// Notify about the transitions the rules select, returning them
+    pub fn notify<'a>(&self, transitions: &'a [JobTransition]) -> Vec<&'a JobTransition> {
+        if !self.config.enabled {
+            return Vec::new();
+        }
+
+        let selected: Vec<&JobTransition> = transitions.iter().filter(|t| self.wants(t)).collect();
+        if selected.is_empty() {
+            return selected;
+        }
+
+        // Escape sequences don't move the cursor, so they can be written
+        // while the TUI owns the screen
+        let mut sequences = String::new();
+        if self.config.bell {
+            sequences.push('\x07');
+        }
+        for transition in &selected {
+            // Job names may contain a bell or escape that would end the sequence
+            let message: String = transition
+                .message()
+                .chars()
+                .filter(|c| !c.is_control())
+                .collect();
+            let sequence = match self.config.desktop {
+                DesktopNotification::Off => continue,
+                DesktopNotification::Osc9 => format!("\x1b]9;{}\x07", message),
+                DesktopNotification::Osc777 => {
+                    format!("\x1b]777;notify;slurmer;{}\x07", message)
+                }
+            };
+            sequences.push_str(&tmux_passthrough(&sequence));
+        }
+        let mut stdout = io::stdout();
+        let _ = stdout.write_all(sequences.as_bytes());
+        let _ = stdout.flush();
+
+        if let Some(command) = &self.config.command {
+            for transition in &selected {
+                run_command(command, transition);
+            }
+        }
+
+        selected
+    }
+}
+
+/// Wrap an escape sequence so that tmux hands it to the outer terminal
+pub fn tmux_passthrough(sequence: &str) -> String {
+    if std::env::var_os("TMUX").is_some() {
+        format!("\x1bPtmux;{}\x1b\\", sequence.replace('\x1b', "\x1b\x1b"))
+    } else {
+        sequence.to_string()
+    }
+}
+
+/// Run the notification command in the background, with the job in the environment
+fn run_command(command: &str, transition: &JobTransition) {
+    let env = [
+        ("SLURMER_JOB_ID", transition.job_id.clone()),
+        ("SLURMER_JOB_NAME", transition.name.clone()),
+        ("SLURMER_STATE", transition.state_name.clone()),
+        ("SLURMER_PREVIOUS_STATE", transition.from.to_string()),
+        ("SLURMER_MESSAGE", transition.message()),
+    ]
+    .into_iter()
+    .map(|(key, value)| (key.to_string(), value))
+    .collect();
+    spawn_command("notification", command, env, COMMAND_TIMEOUT);
+}
@@ -0,0 +1,76 @@
+use std::{
+    collections::hash_map::RandomState,
+    fs::{self, OpenOptions},
+    hash::BuildHasher,
+    io::{self, Write},
+    path::{Path, PathBuf},
+};
+
+/// Random names tried before giving up on creating a file
+const ATTEMPTS: u32 = 16;
+
+/// A temporary file only the user can read and write, removed when dropped
+#[derive(Debug)]
+pub struct TempFile {
+    path: PathBuf,
+}
+
+impl TempFile {
+    /// Create a file such as `<prefix>-1f3a9c0b2e4d5a67.sh` with `content`.
+    /// The name is random and the file is created exclusively, so an existing
+    /// file or link is never written through.
+    pub fn create(prefix: &str, extension: &str, content: &[u8]) -> io::Result<Self> {
+        let dir = private_dir();
+        let random = RandomState::new();
+        for attempt in 0..ATTEMPTS {
+            let name = format!(
+                "{}-{:016x}.{}",
+                prefix,
+                random.hash_one((std::process::id(), attempt)),
+                extension
+            );
+            let path = dir.join(name);
+
+            let mut options = OpenOptions::new();
+            options.write(true).create_new(true);
+            #[cfg(unix)]
+            {
+                use std::os::unix::fs::OpenOptionsExt;
+                options.mode(0o600);
+            }
+            let mut file = match options.open(&path) {
+                Ok(file) => file,
+                Err(e) if e.kind() == io::ErrorKind::AlreadyExists => continue,
+                Err(e) => return Err(e),
+            };
+
+            // The file is removed again if it can't be written
+            let temp_file = TempFile { path };
+            file.write_all(content)?;
+            return Ok(temp_file);
+        }
+        Err(io::Error::new(
+            io::ErrorKind::AlreadyExists,
+            format!("No unused file name for {} in {}", prefix, dir.display()),
+        ))
+    }
+
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+}
+
+impl Drop for TempFile {
+    fn drop(&mut self) {
+        let _ = fs::remove_file(&self.path);
+    }
+}
+
+/// `$XDG_RUNTIME_DIR`, which only the user can access, or else the system's
+/// temporary directory
+fn private_dir() -> PathBuf {
+    std::env::var_os("XDG_RUNTIME_DIR")
+        .map(PathBuf::from)
+        .filter(|dir| dir.is_absolute() && dir.is_dir())
+        .unwrap_or_else(std::env::temp_dir)
+}